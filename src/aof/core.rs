@@ -0,0 +1,366 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use bytes::BytesMut;
+use tokio::sync::Mutex;
+use tokio_util::codec::Decoder;
+
+use crate::{
+    cache::{
+        core::{instant_to_unix_millis, CacheRepository, Value},
+        stream::Stream,
+    },
+    cli::config::FsyncPolicy,
+    cmd_queue::core::CmdQueue,
+    command::core::{apply_command, parse_args},
+    rdb::core::RdbEntry,
+    resp::{codec::RespCodec, core::RESPDatatypes},
+};
+
+/// The append only log of every write, in the same encoding the replicas receive.
+#[derive(Debug)]
+pub struct Aof {
+    path: PathBuf,
+    file: File,
+    fsync: FsyncPolicy,
+    dirty: bool,
+    // writes made while a rewrite runs, they are appended to the rewritten file
+    rewrite_buffer: Option<Vec<u8>>,
+}
+
+impl Aof {
+    pub fn open(path: PathBuf, fsync: FsyncPolicy) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Aof {
+            path,
+            file,
+            fsync,
+            dirty: false,
+            rewrite_buffer: None,
+        })
+    }
+
+    pub fn append(&mut self, cmd: &[u8]) -> io::Result<()> {
+        self.file.write_all(cmd)?;
+        if let Some(rewrite_buffer) = self.rewrite_buffer.as_mut() {
+            rewrite_buffer.extend_from_slice(cmd);
+        }
+
+        match self.fsync {
+            FsyncPolicy::Always => self.file.sync_data()?,
+            FsyncPolicy::EverySec => self.dirty = true,
+            FsyncPolicy::No => {}
+        }
+        Ok(())
+    }
+
+    pub fn fsync_if_dirty(&mut self) -> io::Result<()> {
+        if self.dirty {
+            self.file.sync_data()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    pub fn is_rewriting(&self) -> bool {
+        self.rewrite_buffer.is_some()
+    }
+
+    pub fn start_rewrite(&mut self) {
+        self.rewrite_buffer = Some(Vec::new());
+    }
+
+    pub fn abort_rewrite(&mut self) {
+        self.rewrite_buffer.take();
+    }
+
+    /// Appends the writes made during the rewrite to `rewritten`, moves it over the current
+    /// log and continues appending to it.
+    pub fn finish_rewrite(&mut self, rewritten: &Path) -> io::Result<()> {
+        let pending = self.rewrite_buffer.take().unwrap_or_default();
+        let mut file = OpenOptions::new().append(true).open(rewritten)?;
+        file.write_all(&pending)?;
+        file.sync_all()?;
+
+        std::fs::rename(rewritten, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Flushes the log once per second for the `everysec` policy.
+pub async fn fsync_every_second(cmdq: Arc<Mutex<CmdQueue>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        if let Some(aof) = cmdq.lock().await.aof.as_mut() {
+            if let Err(err) = aof.fsync_if_dirty() {
+                println!("unable to fsync the append only file: {}", err);
+            }
+        }
+    }
+}
+
+/// Replays the log into the keyspace. A command cut short by a crash at the end of the file
+/// is ignored, like `aof-load-truncated yes` does, and so is a transaction missing its `EXEC`.
+pub async fn replay(path: &Path, cache_repo: Arc<Mutex<CacheRepository>>) -> io::Result<usize> {
+    let mut buf = match tokio::fs::read(path).await {
+        Ok(buf) => BytesMut::from(&buf[..]),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+
+    let mut codec = RespCodec::requests();
+    let mut replayed = 0;
+    // commands of a transaction are only applied once its EXEC was read
+    let mut transaction: Option<Vec<RESPDatatypes>> = None;
+    while let Some(cmd) = codec.decode(&mut buf)? {
+        let cmds = match (parse_args(&cmd, &["multi", "exec"]), transaction.as_mut()) {
+            (Some((name, _)), None) if name == "multi" => {
+                transaction = Some(Vec::new());
+                continue;
+            }
+            (Some((name, _)), Some(_)) if name == "exec" => transaction.take().unwrap_or_default(),
+            (Some(_), _) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unbalanced MULTI/EXEC in append only file",
+                ))
+            }
+            (None, Some(queued)) => {
+                queued.push(cmd);
+                continue;
+            }
+            (None, None) => vec![cmd],
+        };
+
+        for cmd in cmds {
+            if let Err(err) = apply_command(cmd, cache_repo.clone()).await {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid command in append only file: {}", err),
+                ));
+            }
+            replayed += 1;
+        }
+    }
+    if let Some(queued) = transaction {
+        println!(
+            "ignoring a transaction of {} commands without EXEC at the end of {}",
+            queued.len(),
+            path.display()
+        );
+    }
+    if !buf.is_empty() {
+        println!(
+            "ignoring {} bytes of a truncated command at the end of {}",
+            buf.len(),
+            path.display()
+        );
+    }
+    Ok(replayed)
+}
+
+/// Collections are rewritten in batches of this many elements per command, so a huge one
+/// doesn't become a single huge command.
+const REWRITE_ITEMS_PER_CMD: usize = 64;
+
+/// The smallest sequence of commands that recreates `entries`. Deadlines are written as
+/// absolute times so replaying the log later doesn't extend them.
+pub fn rewrite_commands(entries: &[RdbEntry]) -> Vec<u8> {
+    let now = instant_to_unix_millis(std::time::Instant::now());
+    let mut buf = Vec::new();
+    for entry in entries {
+        if entry.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            continue;
+        }
+        for cmd in value_commands(&entry.key, &entry.value) {
+            buf.extend(RESPDatatypes::Array(cmd).encode());
+        }
+
+        if let Some(expires_at) = entry.expires_at {
+            let pexpireat = vec![
+                RESPDatatypes::BulkString("PEXPIREAT".to_string()),
+                RESPDatatypes::BufBulk(entry.key.as_bytes().to_vec()),
+                RESPDatatypes::BulkString(format!("{}", expires_at)),
+            ];
+            buf.extend(RESPDatatypes::Array(pexpireat).encode());
+        }
+    }
+    buf
+}
+
+/// The writes that build `value` under `key`.
+fn value_commands(key: &str, value: &Value) -> Vec<Vec<RESPDatatypes>> {
+    let (name, args): (&str, Vec<Vec<Vec<u8>>>) = match value {
+        Value::String(buff) => ("SET", vec![vec![buff.to_vec()]]),
+        Value::List(list) => (
+            "RPUSH",
+            list.iter().map(|elem| vec![elem.to_vec()]).collect(),
+        ),
+        Value::Set(set) => (
+            "SADD",
+            set.iter().map(|member| vec![member.to_vec()]).collect(),
+        ),
+        Value::Hash(hash) => (
+            "HSET",
+            hash.iter()
+                .map(|(field, value)| vec![field.to_vec(), value.to_vec()])
+                .collect(),
+        ),
+        Value::SortedSet(zset) => (
+            "ZADD",
+            zset.iter()
+                .map(|(member, score)| vec![score.to_string().into_bytes(), member.to_vec()])
+                .collect(),
+        ),
+        Value::Stream(stream) => return stream_commands(key, stream),
+    };
+
+    args.chunks(REWRITE_ITEMS_PER_CMD)
+        .map(|items| {
+            let mut cmd = vec![
+                RESPDatatypes::BulkString(name.to_string()),
+                RESPDatatypes::BufBulk(key.as_bytes().to_vec()),
+            ];
+            cmd.extend(items.iter().flatten().cloned().map(RESPDatatypes::BufBulk));
+            cmd
+        })
+        .collect()
+}
+
+/// The writes that build `stream`: an `XADD` per entry since each has its own id, then its
+/// last id and counters, then its groups with their consumers and pending entries.
+fn stream_commands(key: &str, stream: &Stream) -> Vec<Vec<RESPDatatypes>> {
+    let arg = |arg: &str| RESPDatatypes::BulkString(arg.to_string());
+    let buf = |buf: &[u8]| RESPDatatypes::BufBulk(buf.to_vec());
+
+    let mut cmds = Vec::new();
+    if stream.is_empty() {
+        // an entry trimmed right away leaves the stream empty, `XSETID` then rolls its id back
+        cmds.push(vec![
+            arg("XADD"),
+            arg(key),
+            arg("MAXLEN"),
+            arg("0"),
+            arg("0-1"),
+            arg("x"),
+            arg("y"),
+        ]);
+    }
+    for (id, fields) in stream.entries.iter() {
+        let mut cmd = vec![arg("XADD"), arg(key), arg(&id.to_string())];
+        cmd.extend(
+            fields
+                .iter()
+                .flat_map(|(field, value)| [buf(field), buf(value)]),
+        );
+        cmds.push(cmd);
+    }
+    cmds.push(vec![
+        arg("XSETID"),
+        arg(key),
+        arg(&stream.last_id.to_string()),
+        arg("ENTRIESADDED"),
+        arg(&stream.entries_added.to_string()),
+        arg("MAXDELETEDID"),
+        arg(&stream.max_deleted_id.to_string()),
+    ]);
+
+    for (name, group) in stream.groups.iter() {
+        let entries_read = group.entries_read.map_or(-1, |read| read as i64);
+        cmds.push(vec![
+            arg("XGROUP"),
+            arg("CREATE"),
+            arg(key),
+            buf(name),
+            arg(&group.last_delivered.to_string()),
+            arg("ENTRIESREAD"),
+            arg(&entries_read.to_string()),
+        ]);
+        for consumer in group.consumers.keys() {
+            cmds.push(vec![
+                arg("XGROUP"),
+                arg("CREATECONSUMER"),
+                arg(key),
+                buf(name),
+                buf(consumer),
+            ]);
+        }
+        for (id, pending) in group.pending.iter() {
+            cmds.push(vec![
+                arg("XCLAIM"),
+                arg(key),
+                buf(name),
+                buf(&pending.consumer),
+                arg("0"),
+                arg(&id.to_string()),
+                arg("TIME"),
+                arg(&pending.delivered_at.to_string()),
+                arg("RETRYCOUNT"),
+                arg(&pending.deliveries.to_string()),
+                arg("FORCE"),
+                arg("JUSTID"),
+            ]);
+        }
+    }
+    cmds
+}
+
+/// Writes the rewritten log to a temporary file and swaps it in. When the log is enabled,
+/// `Aof::start_rewrite` must have been called before `entries` were copied so no write made
+/// in between is lost.
+pub async fn rewrite(
+    entries: Vec<RdbEntry>,
+    cmdq: Arc<Mutex<CmdQueue>>,
+    path: PathBuf,
+) -> io::Result<()> {
+    let mut tmp_path = path.clone();
+    tmp_path.set_extension(format!("rewrite-{}", std::process::id()));
+
+    if let Err(err) = tokio::fs::write(&tmp_path, rewrite_commands(&entries)).await {
+        if let Some(aof) = cmdq.lock().await.aof.as_mut() {
+            aof.abort_rewrite();
+        }
+        return Err(err);
+    }
+
+    let mut cmd_queue = cmdq.lock().await;
+    match cmd_queue.aof.as_mut() {
+        Some(aof) => aof.finish_rewrite(&tmp_path),
+        None => std::fs::rename(&tmp_path, &path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::blocking::encode_command;
+
+    #[tokio::test]
+    async fn replay_drops_a_transaction_missing_its_exec() {
+        let path = std::env::temp_dir().join(format!("replay-tnx-{}.aof", std::process::id()));
+        let mut log = encode_command(&["SET", "before", "1"]);
+        log.extend(encode_command(&["MULTI"]));
+        log.extend(encode_command(&["SET", "a", "1"]));
+        log.extend(encode_command(&["EXEC"]));
+        log.extend(encode_command(&["MULTI"]));
+        log.extend(encode_command(&["SET", "b", "1"]));
+        std::fs::write(&path, &log).unwrap();
+
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        assert_eq!(replay(&path, repo.clone()).await.unwrap(), 2);
+        let cache = repo.lock().await;
+        assert!(cache.exists("before".to_string()).await);
+        assert!(cache.exists("a".to_string()).await);
+        assert!(!cache.exists("b".to_string()).await);
+        drop(cache);
+        let _ = std::fs::remove_file(&path);
+    }
+}
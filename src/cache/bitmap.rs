@@ -0,0 +1,382 @@
+use std::io::{self, Error};
+
+use crate::utils::range::clamp_range;
+
+use super::core::MAX_STRING_SIZE;
+
+/// The highest bit a string can have, bits counting from the most significant one of the
+/// first byte.
+pub const MAX_BIT_OFFSET: u64 = MAX_STRING_SIZE as u64 * 8 - 1;
+
+pub fn invalid_bit_offset() -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        "ERR bit offset is not an integer or out of range",
+    )
+}
+
+/// Parses the offset of a bit, or of a `BITFIELD` field of type `field`, which can also be
+/// given as `#n` for the n-th field of its width.
+pub fn parse_bit_offset(arg: &[u8], field: Option<&BitFieldType>) -> io::Result<u64> {
+    let (arg, width) = match (arg.strip_prefix(b"#"), field) {
+        (Some(idx), Some(field)) => (idx, field.bits as u64),
+        _ => (arg, 1),
+    };
+    let bits = field.map_or(1, |field| field.bits as u64);
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|offset| offset.parse::<u64>().ok())
+        .and_then(|offset| offset.checked_mul(width))
+        .filter(|offset| offset.saturating_add(bits - 1) <= MAX_BIT_OFFSET)
+        .ok_or_else(invalid_bit_offset)
+}
+
+pub fn get_bit(value: &[u8], offset: u64) -> bool {
+    value
+        .get((offset / 8) as usize)
+        .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+}
+
+/// Sets a bit, growing the string with zero bytes to reach it. Returns what the bit was.
+pub fn set_bit(value: &mut Vec<u8>, offset: u64, bit: bool) -> bool {
+    let idx = (offset / 8) as usize;
+    if value.len() <= idx {
+        value.resize(idx + 1, 0);
+    }
+    let mask = 0x80 >> (offset % 8);
+    let old = value[idx] & mask != 0;
+    match bit {
+        true => value[idx] |= mask,
+        false => value[idx] &= !mask,
+    }
+    old
+}
+
+/// What the indexes of `BITCOUNT` and `BITPOS` ranges count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeUnit {
+    Byte,
+    Bit,
+}
+
+impl RangeUnit {
+    pub fn parse(arg: &[u8]) -> Option<RangeUnit> {
+        match arg.to_ascii_lowercase().as_slice() {
+            b"byte" => Some(RangeUnit::Byte),
+            b"bit" => Some(RangeUnit::Bit),
+            _ => None,
+        }
+    }
+
+    /// The inclusive range of bits `start..=end` selects in `value`, `None` when it is empty.
+    pub fn bit_range(&self, start: i64, end: i64, value: &[u8]) -> Option<(u64, u64)> {
+        match self {
+            RangeUnit::Byte => clamp_range(start, end, value.len())
+                .map(|(start, end)| (start as u64 * 8, end as u64 * 8 + 7)),
+            RangeUnit::Bit => clamp_range(start, end, value.len() * 8)
+                .map(|(start, end)| (start as u64, end as u64)),
+        }
+    }
+}
+
+/// The bits of `value[idx]` that lie within `start..=end`.
+fn byte_mask(idx: u64, start: u64, end: u64) -> u8 {
+    let first = if idx == start / 8 { start % 8 } else { 0 };
+    let last = if idx == end / 8 { end % 8 } else { 7 };
+    (0xff >> first) & (0xff << (7 - last))
+}
+
+/// How many bits are set within `start..=end`.
+pub fn count_bits(value: &[u8], start: u64, end: u64) -> u64 {
+    (start / 8..=end / 8)
+        .map(|idx| (value[idx as usize] & byte_mask(idx, start, end)).count_ones() as u64)
+        .sum()
+}
+
+/// The first bit within `start..=end` that is `bit`, skipping whole bytes that can't hold it.
+pub fn find_bit(value: &[u8], bit: bool, start: u64, end: u64) -> Option<u64> {
+    (start / 8..=end / 8).find_map(|idx| {
+        let mask = byte_mask(idx, start, end);
+        // searching for a zero is searching for a one in the flipped byte
+        let byte = match bit {
+            true => value[idx as usize],
+            false => !value[idx as usize],
+        } & mask;
+        (byte != 0).then(|| idx * 8 + byte.leading_zeros() as u64)
+    })
+}
+
+/// How `BITOP` combines the strings it is given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+impl BitOp {
+    pub fn parse(arg: &[u8]) -> Option<BitOp> {
+        match arg.to_ascii_lowercase().as_slice() {
+            b"and" => Some(BitOp::And),
+            b"or" => Some(BitOp::Or),
+            b"xor" => Some(BitOp::Xor),
+            b"not" => Some(BitOp::Not),
+            _ => None,
+        }
+    }
+
+    /// Combines `values` byte by byte, the shorter ones padded with zero bytes to the length
+    /// of the longest.
+    pub fn combine(&self, values: Vec<Vec<u8>>) -> Vec<u8> {
+        let len = values.iter().map(|value| value.len()).max().unwrap_or(0);
+        let mut values = values.into_iter();
+        let mut result = values.next().unwrap_or_default();
+        result.resize(len, 0);
+        if *self == BitOp::Not {
+            result.iter_mut().for_each(|byte| *byte = !*byte);
+            return result;
+        }
+        for value in values {
+            for (idx, byte) in result.iter_mut().enumerate() {
+                let other = value.get(idx).copied().unwrap_or(0);
+                match self {
+                    BitOp::And => *byte &= other,
+                    BitOp::Or => *byte |= other,
+                    BitOp::Xor => *byte ^= other,
+                    BitOp::Not => unreachable!(),
+                }
+            }
+        }
+        result
+    }
+}
+
+/// An integer type `BITFIELD` reads and writes, like `i5` or `u16`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitFieldType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+impl BitFieldType {
+    /// Unsigned fields stop at 63 bits so their values fit the integers replies carry.
+    pub fn parse(arg: &[u8]) -> io::Result<BitFieldType> {
+        let invalid = || {
+            Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not \
+                 supported but i64 is.",
+            )
+        };
+        let (signed, bits) = match arg.split_first() {
+            Some((b'i' | b'I', bits)) => (true, bits),
+            Some((b'u' | b'U', bits)) => (false, bits),
+            _ => return Err(invalid()),
+        };
+        let bits = std::str::from_utf8(bits)
+            .ok()
+            .and_then(|bits| bits.parse::<u32>().ok())
+            .filter(|bits| (1..=63 + signed as u32).contains(bits))
+            .ok_or_else(invalid)?;
+        Ok(BitFieldType { signed, bits })
+    }
+
+    fn min(&self) -> i128 {
+        match self.signed {
+            true => -(1 << (self.bits - 1)),
+            false => 0,
+        }
+    }
+
+    fn max(&self) -> i128 {
+        match self.signed {
+            true => (1 << (self.bits - 1)) - 1,
+            false => (1 << self.bits) - 1,
+        }
+    }
+
+    /// Reads the field starting at bit `offset`, bits past the end of the string being zeros.
+    pub fn get(&self, value: &[u8], offset: u64) -> i64 {
+        let raw = (0..self.bits as u64).fold(0u64, |raw, idx| {
+            (raw << 1) | get_bit(value, offset + idx) as u64
+        });
+        match self.signed {
+            // moves the sign bit up to the top of the u64 and back down to extend it
+            true => ((raw << (64 - self.bits)) as i64) >> (64 - self.bits),
+            false => raw as i64,
+        }
+    }
+
+    /// Writes the low bits of `val` to the field starting at bit `offset`.
+    pub fn set(&self, value: &mut Vec<u8>, offset: u64, val: i64) {
+        for idx in 0..self.bits as u64 {
+            let bit = (val as u64 >> (self.bits as u64 - 1 - idx)) & 1 == 1;
+            set_bit(value, offset + idx, bit);
+        }
+    }
+
+    /// Fits `val` into the field the way `overflow` says, `None` when it fails.
+    pub fn fit(&self, val: i128, overflow: Overflow) -> Option<i64> {
+        if (self.min()..=self.max()).contains(&val) {
+            return Some(val as i64);
+        }
+        match overflow {
+            Overflow::Wrap => {
+                let wrapped = val.rem_euclid(1 << self.bits);
+                Some(match self.signed && wrapped > self.max() {
+                    true => wrapped - (1 << self.bits),
+                    false => wrapped,
+                } as i64)
+            }
+            Overflow::Sat => Some(val.clamp(self.min(), self.max()) as i64),
+            Overflow::Fail => None,
+        }
+    }
+}
+
+/// What `BITFIELD` does when a `SET` or `INCRBY` goes past what the field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overflow {
+    #[default]
+    Wrap,
+    Sat,
+    Fail,
+}
+
+impl Overflow {
+    pub fn parse(arg: &[u8]) -> io::Result<Overflow> {
+        match arg.to_ascii_lowercase().as_slice() {
+            b"wrap" => Ok(Overflow::Wrap),
+            b"sat" => Ok(Overflow::Sat),
+            b"fail" => Ok(Overflow::Fail),
+            _ => Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR Invalid OVERFLOW type specified",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(arg: &str) -> BitFieldType {
+        BitFieldType::parse(arg.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parses_field_types() {
+        assert_eq!(
+            field("i64"),
+            BitFieldType {
+                signed: true,
+                bits: 64
+            }
+        );
+        assert_eq!(
+            field("U63"),
+            BitFieldType {
+                signed: false,
+                bits: 63
+            }
+        );
+        for invalid in ["u64", "i65", "i0", "u", "x8", "i-1", "i4294967297"] {
+            assert!(
+                BitFieldType::parse(invalid.as_bytes()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn bounds_bit_offsets() {
+        let max = MAX_BIT_OFFSET.to_string();
+        assert_eq!(
+            parse_bit_offset(max.as_bytes(), None).unwrap(),
+            MAX_BIT_OFFSET
+        );
+        let past = (MAX_BIT_OFFSET + 1).to_string();
+        assert!(parse_bit_offset(past.as_bytes(), None).is_err());
+        assert!(parse_bit_offset(b"-1", None).is_err());
+
+        let u8 = field("u8");
+        assert_eq!(parse_bit_offset(b"#2", Some(&u8)).unwrap(), 16);
+        // the whole field has to fit, not only its first bit
+        let last = (MAX_BIT_OFFSET - 6).to_string();
+        assert!(parse_bit_offset(last.as_bytes(), Some(&u8)).is_err());
+        let last = (MAX_BIT_OFFSET - 7).to_string();
+        assert!(parse_bit_offset(last.as_bytes(), Some(&u8)).is_ok());
+        // `#n` times the width must not overflow
+        assert!(parse_bit_offset(b"#18446744073709551615", Some(&field("i64"))).is_err());
+        assert!(parse_bit_offset(b"#2", None).is_err());
+    }
+
+    #[test]
+    fn reads_and_writes_unaligned_fields() {
+        let mut value = vec![];
+        field("i5").set(&mut value, 3, -3);
+        assert_eq!(value, [0b0001_1101]);
+        assert_eq!(field("i5").get(&value, 3), -3);
+        assert_eq!(field("u5").get(&value, 3), 29);
+
+        field("u12").set(&mut value, 6, 0xabc);
+        assert_eq!(field("u12").get(&value, 6), 0xabc);
+        assert_eq!(value.len(), 3);
+        // reading past the end sees zeros
+        assert_eq!(field("u8").get(&value, 14), 0b1100_0000);
+        assert_eq!(field("i64").get(&[], 1000), 0);
+
+        let mut value = vec![];
+        field("i64").set(&mut value, 1, i64::MIN);
+        assert_eq!(field("i64").get(&value, 1), i64::MIN);
+        field("u63").set(&mut value, 0, i64::MAX);
+        assert_eq!(field("u63").get(&value, 0), i64::MAX);
+    }
+
+    #[test]
+    fn fits_overflowing_values() {
+        let i8 = field("i8");
+        assert_eq!(i8.fit(127, Overflow::Fail), Some(127));
+        assert_eq!(i8.fit(128, Overflow::Wrap), Some(-128));
+        assert_eq!(i8.fit(-129, Overflow::Wrap), Some(127));
+        assert_eq!(i8.fit(300, Overflow::Wrap), Some(44));
+        assert_eq!(i8.fit(128, Overflow::Sat), Some(127));
+        assert_eq!(i8.fit(-1000, Overflow::Sat), Some(-128));
+        assert_eq!(i8.fit(128, Overflow::Fail), None);
+
+        let u2 = field("u2");
+        assert_eq!(u2.fit(4, Overflow::Wrap), Some(0));
+        assert_eq!(u2.fit(-1, Overflow::Wrap), Some(3));
+        assert_eq!(u2.fit(-1, Overflow::Sat), Some(0));
+        assert_eq!(u2.fit(-1, Overflow::Fail), None);
+
+        let i64 = field("i64");
+        let past = i64::MAX as i128 + 1;
+        assert_eq!(i64.fit(past, Overflow::Wrap), Some(i64::MIN));
+        assert_eq!(i64.fit(past, Overflow::Sat), Some(i64::MAX));
+        assert_eq!(i64.fit(i64::MIN as i128 * 2, Overflow::Sat), Some(i64::MIN));
+        assert_eq!(i64.fit(past, Overflow::Fail), None);
+
+        let u63 = field("u63");
+        assert_eq!(u63.fit(i64::MAX as i128 + 1, Overflow::Wrap), Some(0));
+        assert_eq!(u63.fit(i64::MAX as i128 + 1, Overflow::Sat), Some(i64::MAX));
+    }
+
+    #[test]
+    fn counts_and_finds_bits_within_ranges() {
+        let value = [0b1111_0000, 0b0000_1111, 0xff];
+        assert_eq!(count_bits(&value, 0, 23), 16);
+        assert_eq!(count_bits(&value, 2, 13), 4);
+        assert_eq!(count_bits(&value, 4, 11), 0);
+        assert_eq!(find_bit(&value, true, 4, 23), Some(12));
+        assert_eq!(find_bit(&value, false, 0, 23), Some(4));
+        assert_eq!(find_bit(&value, false, 16, 23), None);
+
+        assert_eq!(RangeUnit::Byte.bit_range(1, -1, &value), Some((8, 23)));
+        assert_eq!(RangeUnit::Bit.bit_range(-5, -1, &value), Some((19, 23)));
+        assert_eq!(RangeUnit::Byte.bit_range(2, 1, &value), None);
+    }
+}
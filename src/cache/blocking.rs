@@ -0,0 +1,518 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::pending,
+    io,
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::sync::{oneshot, Mutex};
+
+use crate::{connections::connection::Connection, resp::core::RESPDatatypes};
+
+use super::{
+    core::{wrong_type, CacheRepository},
+    list::{as_list, as_list_mut, as_list_or_create, pop, push, Side},
+    sorted_set::{as_zset_mut, as_zset_or_create},
+};
+
+/// What a blocked client does with the element it is handed.
+#[derive(Debug, Clone)]
+pub enum BlockedOp {
+    Pop(Side),
+    Move {
+        from: Side,
+        destination: String,
+        to: Side,
+    },
+    /// pops the lowest scored member of a sorted set, or the highest when `max`
+    ZPop {
+        max: bool,
+    },
+    /// waits for entries added to a stream, which it reads by itself once woken
+    XRead,
+    /// like `XRead`, reading as a consumer of a group, which changes the stream
+    XReadGroup,
+}
+
+impl BlockedOp {
+    /// Whether the client can be served from a key holding the kind of value `TYPE` names.
+    fn serves(&self, type_name: &str) -> bool {
+        match self {
+            BlockedOp::Pop(_) | BlockedOp::Move { .. } => type_name == "list",
+            BlockedOp::ZPop { .. } => type_name == "zset",
+            BlockedOp::XRead | BlockedOp::XReadGroup => type_name == "stream",
+        }
+    }
+
+    /// Whether serving the client changes the keyspace.
+    fn writes(&self) -> bool {
+        !matches!(self, BlockedOp::XRead)
+    }
+}
+
+/// What a blocked client got and the key it came from. Only sorted set members have a score,
+/// stream readers get nothing but the key.
+#[derive(Debug)]
+pub struct Served {
+    pub key: String,
+    pub elem: Vec<u8>,
+    pub score: Option<f64>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    keys: Vec<String>,
+    op: BlockedOp,
+    tx: oneshot::Sender<io::Result<Served>>,
+}
+
+/// Clients parked by a blocking pop, queued per key in the order they blocked.
+#[derive(Debug, Default)]
+pub struct BlockedClients {
+    next_id: u64,
+    waiters: HashMap<u64, Waiter>,
+    queues: HashMap<String, VecDeque<u64>>,
+}
+
+impl BlockedClients {
+    /// Parks a client on `keys` until one of them gets an element for it.
+    pub fn block(
+        &mut self,
+        keys: Vec<String>,
+        op: BlockedOp,
+    ) -> (u64, oneshot::Receiver<io::Result<Served>>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        for key in keys.iter() {
+            self.queues
+                .entry(key.to_string())
+                .or_default()
+                .push_back(id);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.waiters.insert(id, Waiter { keys, op, tx });
+        (id, rx)
+    }
+
+    /// Forgets a client that gave up waiting. Returns false when it was served meanwhile.
+    pub fn unblock(&mut self, id: u64) -> bool {
+        self.take(id).is_some()
+    }
+
+    fn take(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+        for key in waiter.keys.iter() {
+            if let Some(queue) = self.queues.get_mut(key) {
+                queue.retain(|queued| *queued != id);
+                if queue.is_empty() {
+                    self.queues.remove(key);
+                }
+            }
+        }
+        Some(waiter)
+    }
+
+    /// The longest waiting client on `key` that can take from the kind of value it holds,
+    /// only among the ones that don't write unless `writable`.
+    fn first_waiter(&self, key: &str, type_name: &str, writable: bool) -> Option<u64> {
+        self.queues
+            .get(key)?
+            .iter()
+            .find(|id| {
+                let op = &self.waiters[id].op;
+                op.serves(type_name) && (writable || !op.writes())
+            })
+            .copied()
+    }
+}
+
+impl CacheRepository {
+    /// Hands the elements just pushed to `key` to the clients blocked on it, longest waiting
+    /// first. Returns the pops made for them, in the non blocking form replicas apply. Unless
+    /// `writable`, only the clients that merely read are woken.
+    pub async fn serve_blocked(&self, key: String, writable: bool) -> Vec<Vec<u8>> {
+        let mut propagated = Vec::new();
+        // moving an element can wake the clients blocked on the destination in turn
+        let mut ready = VecDeque::from([key]);
+        let mut blocked = self.blocked.lock().await;
+
+        while let Some(key) = ready.pop_front() {
+            // collections are deleted once empty, so a key that exists has something to take
+            while let Some(id) = match self.value_type(key.to_string()).await {
+                Some(type_name) => blocked.first_waiter(&key, type_name, writable),
+                None => None,
+            } {
+                let waiter = blocked.take(id).unwrap();
+                if waiter.tx.is_closed() {
+                    continue;
+                }
+                let (served, cmd) = match &waiter.op {
+                    BlockedOp::Pop(side) => {
+                        let elem = self.pop_from(key.to_string(), *side).await;
+                        let served = Served {
+                            key: key.to_string(),
+                            elem,
+                            score: None,
+                        };
+                        (
+                            Ok(served),
+                            Some(encode_command(&[side.pop_command(), &key])),
+                        )
+                    }
+                    BlockedOp::Move {
+                        from,
+                        destination,
+                        to,
+                    } => {
+                        let destination_is_list = self
+                            .view(destination.to_string(), |value| as_list(value).is_ok())
+                            .await;
+                        if destination_is_list {
+                            let elem = self.pop_from(key.to_string(), *from).await;
+                            self.push_to(destination.to_string(), *to, elem.to_vec())
+                                .await;
+                            let served = Served {
+                                key: key.to_string(),
+                                elem,
+                                score: None,
+                            };
+                            let cmd = encode_command(&[
+                                "LMOVE",
+                                &key,
+                                destination,
+                                from.name(),
+                                to.name(),
+                            ]);
+                            (Ok(served), Some(cmd))
+                        } else {
+                            (Err(wrong_type()), None)
+                        }
+                    }
+                    BlockedOp::ZPop { max } => {
+                        let (elem, score) = self
+                            .modify(key.to_string(), |value| {
+                                as_zset_mut(value)
+                                    .ok()
+                                    .flatten()
+                                    .and_then(|zset| zset.pop(1, *max).pop())
+                            })
+                            .await
+                            .unwrap_or_default();
+                        let served = Served {
+                            key: key.to_string(),
+                            elem,
+                            score: Some(score),
+                        };
+                        let name = if *max { "ZPOPMAX" } else { "ZPOPMIN" };
+                        (Ok(served), Some(encode_command(&[name, &key])))
+                    }
+                    // stream readers are all woken, entries aren't taken by reading them
+                    BlockedOp::XRead | BlockedOp::XReadGroup => {
+                        let served = Served {
+                            key: key.to_string(),
+                            elem: vec![],
+                            score: None,
+                        };
+                        (Ok(served), None)
+                    }
+                };
+
+                match waiter.tx.send(served) {
+                    Ok(()) => {
+                        propagated.extend(cmd);
+                        if let BlockedOp::Move { destination, .. } = waiter.op {
+                            ready.push_back(destination);
+                        }
+                    }
+                    // the client went away since it was checked, nothing may be lost for it
+                    Err(Ok(served)) => self.undo_served(&waiter.op, served).await,
+                    Err(Err(_)) => {}
+                }
+            }
+        }
+        propagated
+    }
+
+    /// Puts back what was taken for a client that can't be handed it anymore, as if it was
+    /// never served.
+    async fn undo_served(&self, op: &BlockedOp, served: Served) {
+        match op {
+            BlockedOp::Pop(side) => self.push_to(served.key, *side, served.elem).await,
+            BlockedOp::Move {
+                from,
+                destination,
+                to,
+            } => {
+                self.pop_from(destination.to_string(), *to).await;
+                self.push_to(served.key, *from, served.elem).await;
+            }
+            BlockedOp::ZPop { .. } => {
+                self.modify(served.key, |value| {
+                    if let Ok(zset) = as_zset_or_create(value) {
+                        zset.insert(served.elem, served.score.unwrap_or_default());
+                    }
+                })
+                .await
+            }
+            BlockedOp::XRead | BlockedOp::XReadGroup => {}
+        }
+    }
+
+    async fn push_to(&self, key: String, side: Side, elem: Vec<u8>) {
+        self.modify(key, |value| {
+            if let Ok(list) = as_list_or_create(value) {
+                push(list, side, elem);
+            }
+        })
+        .await
+    }
+
+    async fn pop_from(&self, key: String, side: Side) -> Vec<u8> {
+        self.modify(key, |value| {
+            as_list_mut(value)
+                .ok()
+                .flatten()
+                .and_then(|list| pop(list, side))
+        })
+        .await
+        .unwrap_or_default()
+    }
+}
+
+/// Waits for a pusher to serve a blocked client, `None` once `timeout` passes first. No
+/// timeout waits forever. A client closing its connection stops waiting with
+/// `UnexpectedEof`, what it sent meanwhile is kept in its buffer.
+pub async fn wait_until_served(
+    cache_repo: Arc<Mutex<CacheRepository>>,
+    id: u64,
+    mut rx: oneshot::Receiver<io::Result<Served>>,
+    timeout: Option<Duration>,
+    conn: &mut Connection,
+) -> io::Result<Option<Served>> {
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => pending().await,
+        }
+    };
+    let closed = tokio::select! {
+        biased;
+        served = &mut rx => return served.map_or(Ok(None), |served| served.map(Some)),
+        _ = deadline => false,
+        _ = conn.read_until_closed() => {
+            // pushers skip a closed channel, and undo what they fail to send on it
+            rx.close();
+            true
+        }
+    };
+
+    // serving happens with the keyspace locked, so once unblocking fails the element is
+    // already in the channel
+    let unblocked = cache_repo.lock().await.blocked.lock().await.unblock(id);
+    if unblocked && closed {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "client closed the connection while blocked",
+        ));
+    }
+    if unblocked {
+        return Ok(None);
+    }
+    rx.await.map_or(Ok(None), |served| served.map(Some))
+}
+
+/// Encodes a command made of plain string arguments, the way it is propagated.
+pub fn encode_command(args: &[&str]) -> Vec<u8> {
+    RESPDatatypes::Array(
+        args.iter()
+            .map(|arg| RESPDatatypes::BulkString(arg.to_string()))
+            .collect(),
+    )
+    .encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::{net::TcpStream, sync::Mutex, task::JoinHandle};
+
+    use super::*;
+    use crate::command::core::test_support::{client, send};
+
+    async fn waiting(repo: &Arc<Mutex<CacheRepository>>) -> usize {
+        repo.lock().await.blocked.lock().await.waiters.len()
+    }
+
+    /// Runs a blocking command for a new client until it is parked. Returns its reply to
+    /// come, and the socket of the client, which closes when dropped.
+    async fn block_on(
+        repo: &Arc<Mutex<CacheRepository>>,
+        args: &[&str],
+    ) -> (JoinHandle<Vec<u8>>, TcpStream) {
+        let (mut conn, peer) = client(repo.clone(), None).await;
+        let parked = waiting(repo).await + 1;
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let task_repo = repo.clone();
+        let reply = tokio::spawn(async move {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            send(&mut conn, &task_repo, &args).await
+        });
+        while waiting(repo).await < parked {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        (reply, peer)
+    }
+
+    fn popped(key: &str, elem: &str) -> Vec<u8> {
+        RESPDatatypes::Array(vec![
+            RESPDatatypes::BulkString(key.to_string()),
+            RESPDatatypes::BulkString(elem.to_string()),
+        ])
+        .encode()
+    }
+
+    #[tokio::test]
+    async fn serves_waiters_in_the_order_they_blocked() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (first, _first) = block_on(&repo, &["BLPOP", "jobs", "0"]).await;
+        let (second, _second) = block_on(&repo, &["BRPOP", "other", "jobs", "0"]).await;
+
+        let (mut pusher, _peer) = client(repo.clone(), None).await;
+        send(&mut pusher, &repo, &["RPUSH", "jobs", "a", "b"]).await;
+        assert_eq!(first.await.unwrap(), popped("jobs", "a"));
+        assert_eq!(second.await.unwrap(), popped("jobs", "b"));
+        assert_eq!(waiting(&repo).await, 0);
+
+        // replicas see the pops that were made, they never block
+        let mut expected = encode_command(&["RPUSH", "jobs", "a", "b"]);
+        expected.extend(encode_command(&["LPOP", "jobs"]));
+        expected.extend(encode_command(&["RPOP", "jobs"]));
+        let streamed = pusher.cmdq.lock().await.backlog.read_from(0).unwrap();
+        assert_eq!(streamed, expected);
+    }
+
+    #[tokio::test]
+    async fn times_out_with_a_null_reply() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), None).await;
+        let reply = send(&mut conn, &repo, &["BLPOP", "jobs", "0.01"]).await;
+        assert_eq!(reply, b"*-1\r\n");
+        let reply = send(&mut conn, &repo, &["BZPOPMIN", "zset", "0.01"]).await;
+        assert_eq!(reply, b"*-1\r\n");
+        assert_eq!(waiting(&repo).await, 0);
+    }
+
+    #[tokio::test]
+    async fn moved_elements_wake_the_waiters_of_the_destination() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mover, _mover) =
+            block_on(&repo, &["BLMOVE", "src", "dst", "LEFT", "RIGHT", "0"]).await;
+        let (popper, _popper) = block_on(&repo, &["BLPOP", "dst", "0"]).await;
+
+        let (mut pusher, _peer) = client(repo.clone(), None).await;
+        send(&mut pusher, &repo, &["RPUSH", "src", "job"]).await;
+        assert_eq!(mover.await.unwrap(), b"$3\r\njob\r\n");
+        assert_eq!(popper.await.unwrap(), popped("dst", "job"));
+
+        let mut expected = encode_command(&["RPUSH", "src", "job"]);
+        expected.extend(encode_command(&["LMOVE", "src", "dst", "LEFT", "RIGHT"]));
+        expected.extend(encode_command(&["LPOP", "dst"]));
+        let streamed = pusher.cmdq.lock().await.backlog.read_from(0).unwrap();
+        assert_eq!(streamed, expected);
+        let cache = repo.lock().await;
+        assert_eq!(cache.value_type("src".to_string()).await, None);
+        assert_eq!(cache.value_type("dst".to_string()).await, None);
+    }
+
+    #[tokio::test]
+    async fn clients_that_disconnect_stop_waiting() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (gone, peer) = block_on(&repo, &["BLPOP", "jobs", "0"]).await;
+        let (gone_zset, zset_peer) = block_on(&repo, &["BZPOPMIN", "zset", "0"]).await;
+        drop(peer);
+        drop(zset_peer);
+        assert!(gone.await.unwrap().is_empty());
+        assert!(gone_zset.await.unwrap().is_empty());
+        assert_eq!(waiting(&repo).await, 0);
+
+        let (mut pusher, _peer) = client(repo.clone(), None).await;
+        send(&mut pusher, &repo, &["RPUSH", "jobs", "job"]).await;
+        send(&mut pusher, &repo, &["ZADD", "zset", "1", "m"]).await;
+        assert_eq!(send(&mut pusher, &repo, &["LLEN", "jobs"]).await, b":1\r\n");
+        let score = send(&mut pusher, &repo, &["ZSCORE", "zset", "m"]).await;
+        assert_eq!(score, b"$1\r\n1\r\n");
+    }
+
+    #[tokio::test]
+    async fn commands_sent_while_blocked_run_afterwards() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, mut peer) = client(repo.clone(), None).await;
+        let task_repo = repo.clone();
+        let blocked = tokio::spawn(async move {
+            let reply = send(&mut conn, &task_repo, &["BLPOP", "jobs", "0"]).await;
+            (conn, reply)
+        });
+        while waiting(&repo).await == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        tokio::io::AsyncWriteExt::write_all(&mut peer, &encode_command(&["PING"]))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let (mut pusher, _peer) = client(repo.clone(), None).await;
+        send(&mut pusher, &repo, &["LPUSH", "jobs", "job"]).await;
+        let (conn, reply) = blocked.await.unwrap();
+        assert_eq!(reply, popped("jobs", "job"));
+        assert!(!conn.closed);
+        assert_eq!(&conn.buffer[..], encode_command(&["PING"]));
+    }
+
+    #[tokio::test]
+    async fn undoing_a_serve_puts_the_element_back() {
+        let repo = CacheRepository::default();
+        repo.push_to("dst".to_string(), Side::Right, b"old".to_vec())
+            .await;
+        repo.push_to("dst".to_string(), Side::Right, b"job".to_vec())
+            .await;
+        let served = Served {
+            key: "src".to_string(),
+            elem: b"job".to_vec(),
+            score: None,
+        };
+        let op = BlockedOp::Move {
+            from: Side::Left,
+            destination: "dst".to_string(),
+            to: Side::Right,
+        };
+        repo.undo_served(&op, served).await;
+        let list = |key: &str| {
+            repo.view(key.to_string(), |value| {
+                as_list(value)
+                    .unwrap()
+                    .map(|list| list.iter().cloned().collect::<Vec<_>>())
+            })
+        };
+        assert_eq!(list("src").await, Some(vec![b"job".to_vec()]));
+        assert_eq!(list("dst").await, Some(vec![b"old".to_vec()]));
+
+        let served = Served {
+            key: "zset".to_string(),
+            elem: b"m".to_vec(),
+            score: Some(2.5),
+        };
+        repo.undo_served(&BlockedOp::ZPop { max: false }, served)
+            .await;
+        let score = repo
+            .view("zset".to_string(), |value| {
+                crate::cache::sorted_set::as_zset(value)
+                    .unwrap()
+                    .and_then(|zset| zset.score(b"m"))
+            })
+            .await;
+        assert_eq!(score, Some(2.5));
+    }
+}
@@ -1,18 +1,152 @@
 use std::{
-    collections::{HashMap, HashSet},
-    time::{Duration, Instant},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use tokio::sync::{Mutex, RwLock};
 
-type RespositoryTuple = (Vec<u8>, Option<Instant>);
+use crate::errors::wrong_type::WrongType;
+
+use super::{blocking::BlockedClients, sorted_set::SortedSet, stream::Stream};
+
+/// The largest value a string may grow to, redis' `proto-max-bulk-len`.
+pub const MAX_STRING_SIZE: usize = 512 * 1024 * 1024;
+
+/// What a key holds. Commands only operate on the kind of value they are made for and fail
+/// with `WRONGTYPE` on the others.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Hash(HashMap<Vec<u8>, Vec<u8>>),
+    Set(HashSet<Vec<u8>>),
+    SortedSet(SortedSet),
+    Stream(Stream),
+}
+
+impl Value {
+    /// The name `TYPE` reports.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::SortedSet(_) => "zset",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    /// Collections are deleted once their last element is removed, like redis does.
+    pub fn is_empty_collection(&self) -> bool {
+        match self {
+            Value::String(_) | Value::Stream(_) => false,
+            Value::List(list) => list.is_empty(),
+            Value::Hash(hash) => hash.is_empty(),
+            Value::Set(set) => set.is_empty(),
+            Value::SortedSet(zset) => zset.is_empty(),
+        }
+    }
+}
+
+pub fn wrong_type() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, WrongType)
+}
+
+pub type RespositoryTuple = (Value, Option<Instant>);
 type Repository = RwLock<HashMap<String, RespositoryTuple>>;
 
 #[derive(Debug)]
 pub struct CacheRepository {
     pub repo: Repository,
     pub expiry_map: RwLock<HashMap<Instant, HashSet<String>>>,
+    /// every key by its `scan_hash`, so `SCAN` continues from a cursor without sorting
+    /// the keyspace. Locked after `repo`
+    pub scan_index: RwLock<BTreeMap<u64, HashSet<String>>>,
     pub curr_transaction_id: Mutex<Option<String>>,
+    pub last_save: RwLock<u64>,
+    pub bgsave_in_progress: Mutex<bool>,
+    pub blocked: Mutex<BlockedClients>,
+}
+
+/// Converts a deadline into milliseconds since the unix epoch, as persisted in RDB files.
+pub fn instant_to_unix_millis(instant: Instant) -> u64 {
+    let now = Instant::now();
+    let wall_clock = SystemTime::now();
+    let at = if instant >= now {
+        wall_clock + (instant - now)
+    } else {
+        wall_clock - (now - instant)
+    };
+    at.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Converts milliseconds since the unix epoch into a deadline. Moments already in the past
+/// map to `Instant::now()`.
+pub fn unix_millis_to_instant(millis: u64) -> Instant {
+    let at = UNIX_EPOCH + Duration::from_millis(millis);
+    let now = Instant::now();
+    match at.duration_since(SystemTime::now()) {
+        Ok(remaining) => now + remaining,
+        Err(_) => now,
+    }
+}
+
+/// A hash that is the same for every run, so `SCAN` cursors stay valid.
+pub fn scan_hash<T: Hash + ?Sized>(item: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Pages through `items` in the order of their `scan_hash`, starting at `cursor`. Items
+/// sharing a hash land on the same page, so none is skipped. A next cursor of 0 means the
+/// scan is over.
+///
+/// Only the page is kept while going through `items`, the groups past `count` items are
+/// dropped as smaller hashes come in.
+pub fn scan_page<T>(
+    items: impl IntoIterator<Item = (u64, T)>,
+    cursor: u64,
+    count: usize,
+) -> (u64, Vec<T>) {
+    let count = count.max(1);
+    let mut page: BTreeMap<u64, Vec<T>> = BTreeMap::new();
+    let mut len = 0;
+    let mut next_cursor = 0;
+    for (hash, item) in items {
+        if hash < cursor || (next_cursor != 0 && hash >= next_cursor) {
+            continue;
+        }
+        page.entry(hash).or_default().push(item);
+        len += 1;
+        while let Some(last) = page.last_entry() {
+            if len - last.get().len() < count {
+                break;
+            }
+            len -= last.get().len();
+            next_cursor = *last.key();
+            last.remove();
+        }
+    }
+    (next_cursor, page.into_values().flatten().collect())
+}
+
+pub fn unix_time_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+pub fn unix_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
 
 impl Default for CacheRepository {
@@ -20,7 +154,11 @@ impl Default for CacheRepository {
         CacheRepository {
             repo: RwLock::new(HashMap::new()),
             expiry_map: RwLock::new(HashMap::new()),
+            scan_index: RwLock::new(BTreeMap::new()),
             curr_transaction_id: Mutex::new(None),
+            last_save: RwLock::new(unix_time_secs()),
+            bgsave_in_progress: Mutex::new(false),
+            blocked: Mutex::new(BlockedClients::default()),
         }
     }
 }
@@ -30,31 +168,82 @@ impl CacheRepository {
         Instant::now()
     }
 
-    pub async fn get(&self, key: String) -> Option<Vec<u8>> {
+    /// The string held by a key, failing with `WRONGTYPE` when it holds another kind of value.
+    pub async fn get(&self, key: String) -> std::io::Result<Option<Vec<u8>>> {
+        match self.get_value(key).await {
+            Some(Value::String(buff)) => Ok(Some(buff)),
+            Some(_) => Err(wrong_type()),
+            None => Ok(None),
+        }
+    }
+
+    /// A copy of whatever a key holds.
+    pub async fn get_value(&self, key: String) -> Option<Value> {
+        self.repo
+            .read()
+            .await
+            .get(&key)
+            .filter(|entry| self.is_live(entry))
+            .map(|(value, _)| value.clone())
+    }
+
+    /// Gives `f` the value of a key to read, `None` when there is no such key.
+    pub async fn view<T>(&self, key: String, f: impl FnOnce(Option<&Value>) -> T) -> T {
         let repo = self.repo.read().await;
-        if let Some((vec, ttl_option)) = repo.get(&key) {
-            if let Some(interval) = ttl_option.as_ref() {
-                if *interval < self.now() {
-                    return None;
+        f(repo
+            .get(&key)
+            .filter(|entry| self.is_live(entry))
+            .map(|(value, _)| value))
+    }
+
+    /// Gives `f` the value of a key to change in place, `None` when there is no such key.
+    /// Setting it to `None` or emptying a collection deletes the key, a value created for a
+    /// missing key never expires and an existing one keeps its deadline.
+    pub async fn modify<T>(&self, key: String, f: impl FnOnce(&mut Option<Value>) -> T) -> T {
+        let mut repo = self.repo.write().await;
+        let (mut value, expiry, stale_expiry) = match repo.remove(&key) {
+            Some(entry) if self.is_live(&entry) => (Some(entry.0), entry.1, None),
+            Some((_, expiry)) => (None, None, expiry),
+            None => (None, None, None),
+        };
+
+        let existed = value.is_some() || stale_expiry.is_some();
+        let out = f(&mut value);
+        let dropped_expiry = match value {
+            Some(value) if !value.is_empty_collection() => {
+                if !existed {
+                    self.index_key(&key).await;
+                }
+                repo.insert(key.to_string(), (value, expiry));
+                stale_expiry
+            }
+            _ => {
+                if existed {
+                    self.unindex_key(&key).await;
                 }
+                stale_expiry.or(expiry)
             }
+        };
+        drop(repo);
 
-            return Some(vec.to_vec());
+        if let Some(expiry) = dropped_expiry {
+            self.remove_key_from_ttl_set_if_exists(key, expiry).await;
         }
-        None
+        out
+    }
+
+    /// The kind of value a key holds, as `TYPE` names it.
+    pub async fn value_type(&self, key: String) -> Option<&'static str> {
+        self.repo
+            .read()
+            .await
+            .get(&key)
+            .filter(|entry| self.is_live(entry))
+            .map(|(value, _)| value.type_name())
     }
 
     pub async fn set(&self, key: String, buff: Vec<u8>) -> std::io::Result<()> {
-        if let Some((_, Some(ttl))) = {
-            self.repo
-                .write()
-                .await
-                .insert(key.to_string(), (buff.to_vec(), None))
-        } {
-            self.remove_key_from_ttl_set_if_exists(key.to_string(), ttl)
-                .await;
-        }
-        Ok(())
+        self.insert(key, Value::String(buff), None).await
     }
 
     pub async fn set_with_expiry(
@@ -64,42 +253,265 @@ impl CacheRepository {
         ttl: u64,
     ) -> std::io::Result<()> {
         let expiry = self.now() + Duration::from_millis(ttl);
-        if let Some((_, Some(ttl))) = {
-            self.repo
+        self.set_with_expiry_at(key, buff, expiry).await
+    }
+
+    pub async fn set_with_expiry_at(
+        &self,
+        key: String,
+        buff: Vec<u8>,
+        expiry: Instant,
+    ) -> std::io::Result<()> {
+        self.insert(key, Value::String(buff), Some(expiry)).await
+    }
+
+    /// Stores any kind of value under a key, replacing whatever it held before.
+    pub async fn insert(
+        &self,
+        key: String,
+        value: Value,
+        expiry: Option<Instant>,
+    ) -> std::io::Result<()> {
+        let previous = {
+            let mut repo = self.repo.write().await;
+            let previous = repo.insert(key.to_string(), (value, expiry));
+            if previous.is_none() {
+                self.index_key(&key).await;
+            }
+            previous
+        };
+        if let Some((_, Some(ttl))) = previous {
+            self.remove_key_from_ttl_set_if_exists(key.to_string(), ttl)
+                .await;
+        }
+
+        if let Some(expiry) = expiry {
+            self.expiry_map
                 .write()
                 .await
-                .insert(key.to_string(), (buff.to_vec(), Some(expiry)))
-        } {
-            self.remove_key_from_ttl_set_if_exists(key.to_string(), ttl)
+                .entry(expiry)
+                .or_default()
+                .insert(key);
+        }
+        Ok(())
+    }
+
+    /// Replaces the value of a key without touching its deadline, as commands that modify a
+    /// value in place do.
+    pub async fn set_keep_ttl(&self, key: String, buff: Vec<u8>) -> std::io::Result<()> {
+        match self.expiry(key.to_string()).await.flatten() {
+            Some(expiry) => self.set_with_expiry_at(key, buff, expiry).await,
+            None => self.set(key, buff).await,
+        }
+    }
+
+    /// Copies every key that hasn't expired yet, for persistence and full resyncs.
+    pub async fn entries(&self) -> Vec<(String, RespositoryTuple)> {
+        let now = self.now();
+        self.repo
+            .read()
+            .await
+            .iter()
+            .filter(|(_, (_, expiry))| expiry.is_none_or(|expiry| expiry >= now))
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    fn is_live(&self, (_, expiry): &RespositoryTuple) -> bool {
+        expiry.is_none_or(|expiry| expiry >= self.now())
+    }
+
+    pub async fn exists(&self, key: String) -> bool {
+        self.repo
+            .read()
+            .await
+            .get(&key)
+            .is_some_and(|entry| self.is_live(entry))
+    }
+
+    /// Deletes a key, returning whether there was one to delete.
+    pub async fn remove(&self, key: String) -> bool {
+        let removed = {
+            let mut repo = self.repo.write().await;
+            let removed = repo.remove(&key);
+            if removed.is_some() {
+                self.unindex_key(&key).await;
+            }
+            removed
+        };
+        match removed {
+            Some(entry) => {
+                if let (_, Some(expiry)) = entry {
+                    self.remove_key_from_ttl_set_if_exists(key, expiry).await;
+                }
+                self.is_live(&entry)
+            }
+            None => false,
+        }
+    }
+
+    /// Moves a value along with its expiry to another key, replacing whatever that held.
+    /// Returns false when there is nothing to move.
+    pub async fn rename(&self, from: String, to: String) -> std::io::Result<bool> {
+        let entry = self.repo.read().await.get(&from).cloned();
+        let Some((value, expiry)) = entry.filter(|entry| self.is_live(entry)) else {
+            return Ok(false);
+        };
+        if from == to {
+            return Ok(true);
+        }
+
+        self.remove(from).await;
+        self.insert(to, value, expiry).await?;
+        Ok(true)
+    }
+
+    /// The deadline of a key, `None` when there is no such key and `Some(None)` when it
+    /// never expires.
+    pub async fn expiry(&self, key: String) -> Option<Option<Instant>> {
+        self.repo
+            .read()
+            .await
+            .get(&key)
+            .filter(|entry| self.is_live(entry))
+            .map(|(_, expiry)| *expiry)
+    }
+
+    /// Moves the deadline of an existing key. Returns false when there is no such key.
+    pub async fn expire_at(&self, key: String, expiry: Instant) -> bool {
+        let previous = {
+            let mut repo = self.repo.write().await;
+            let Some(entry) = repo.get_mut(&key).filter(|entry| self.is_live(entry)) else {
+                return false;
+            };
+            entry.1.replace(expiry)
+        };
+        if let Some(previous) = previous {
+            self.remove_key_from_ttl_set_if_exists(key.to_string(), previous)
                 .await;
         }
 
-        let mut expiry_map = self.expiry_map.write().await;
+        self.expiry_map
+            .write()
+            .await
+            .entry(expiry)
+            .or_default()
+            .insert(key);
+        true
+    }
 
-        expiry_map.entry(expiry).or_insert(HashSet::new());
-        expiry_map.get_mut(&expiry).unwrap().insert(key.to_string());
+    /// Makes a key live forever, returning whether it had a deadline to remove.
+    pub async fn persist(&self, key: String) -> bool {
+        let previous = {
+            let mut repo = self.repo.write().await;
+            let Some(entry) = repo.get_mut(&key).filter(|entry| self.is_live(entry)) else {
+                return false;
+            };
+            entry.1.take()
+        };
+        match previous {
+            Some(previous) => {
+                self.remove_key_from_ttl_set_if_exists(key, previous).await;
+                true
+            }
+            None => false,
+        }
+    }
 
-        Ok(())
+    /// Every key that hasn't expired yet.
+    pub async fn keys(&self) -> Vec<String> {
+        self.repo
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| self.is_live(entry))
+            .map(|(key, _)| key.to_string())
+            .collect()
+    }
+
+    /// Returns up to `count` keys starting at `cursor` along with the cursor to continue from,
+    /// 0 once every key was returned.
+    ///
+    /// Keys are visited in the order of a hash that doesn't depend on the map, so a key that
+    /// exists for the whole scan is returned exactly once, however the map grows or shrinks in
+    /// between calls. Keys sharing a hash are always returned together.
+    pub async fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<String>) {
+        let repo = self.repo.read().await;
+        let index = self.scan_index.read().await;
+        let mut page = vec![];
+        let mut groups = index.range(cursor..);
+        for (_, keys) in groups.by_ref() {
+            page.extend(
+                keys.iter()
+                    .filter(|key| repo.get(*key).is_some_and(|entry| self.is_live(entry)))
+                    .cloned(),
+            );
+            if page.len() >= count {
+                break;
+            }
+        }
+        let next_cursor = groups.next().map_or(0, |(hash, _)| *hash);
+        (next_cursor, page)
+    }
+
+    /// Drops every key, as a replica does before loading the snapshot of a full resync.
+    pub async fn clear(&self) {
+        let mut repo = self.repo.write().await;
+        repo.clear();
+        self.scan_index.write().await.clear();
+        self.expiry_map.write().await.clear();
+    }
+
+    /// Adds a key that wasn't in `repo` before to the scan index, with `repo` locked.
+    async fn index_key(&self, key: &str) {
+        self.scan_index
+            .write()
+            .await
+            .entry(scan_hash(key))
+            .or_default()
+            .insert(key.to_string());
+    }
+
+    /// Removes a key deleted from `repo` from the scan index, with `repo` locked.
+    async fn unindex_key(&self, key: &str) {
+        let mut index = self.scan_index.write().await;
+        let hash = scan_hash(key);
+        if let Some(keys) = index.get_mut(&hash) {
+            keys.remove(key);
+            if keys.is_empty() {
+                index.remove(&hash);
+            }
+        }
     }
 
     async fn remove_key_from_ttl_set_if_exists(&self, key: String, expiry: Instant) {
-        if let Some(key_set) = self.expiry_map.write().await.get_mut(&expiry) {
-            key_set.remove(&key.to_string());
+        let mut map = self.expiry_map.write().await;
+        if let Some(key_set) = map.get_mut(&expiry) {
+            key_set.remove(&key);
+            if key_set.is_empty() {
+                map.remove(&expiry);
+            }
         }
     }
 
+    /// Deletes the keys whose deadline passed and forgets the deadlines. A key is only
+    /// deleted while that deadline is still its own, it may have been written again since.
     pub async fn actively_remove_expired_keys(&self) {
-        let map = self.expiry_map.write().await;
-        let expired_key_instants: Vec<&Instant> =
-            map.keys().filter(|expiry| **expiry < self.now()).collect();
-
-        for expired_key_instant in expired_key_instants {
-            if let Some(expired_keys) = map.clone().get_mut(expired_key_instant) {
-                for key in expired_keys.iter() {
-                    self.repo.write().await.remove(&key.to_string());
+        let now = self.now();
+        let mut repo = self.repo.write().await;
+        let mut map = self.expiry_map.write().await;
+        for (expiry, keys) in map.iter().filter(|(expiry, _)| **expiry < now) {
+            for key in keys.iter() {
+                if repo
+                    .get(key)
+                    .is_some_and(|(_, current)| *current == Some(*expiry))
+                {
+                    repo.remove(key);
+                    self.unindex_key(key).await;
                 }
             }
         }
+        map.retain(|expiry, _| *expiry >= now);
     }
 
     pub async fn set_transaction(&self, id: String) {
@@ -122,3 +534,173 @@ impl CacheRepository {
 
 unsafe impl Send for CacheRepository {}
 unsafe impl Sync for CacheRepository {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn past() -> Instant {
+        Instant::now() - Duration::from_millis(10)
+    }
+
+    async fn deadlines(cache: &CacheRepository) -> usize {
+        cache.expiry_map.read().await.len()
+    }
+
+    #[tokio::test]
+    async fn removes_expired_keys_and_their_deadlines() {
+        let cache = CacheRepository::default();
+        let later = Instant::now() + Duration::from_secs(60);
+        cache
+            .set_with_expiry_at("gone".to_string(), b"v".to_vec(), past())
+            .await
+            .unwrap();
+        cache
+            .set_with_expiry_at("kept".to_string(), b"v".to_vec(), later)
+            .await
+            .unwrap();
+
+        cache.actively_remove_expired_keys().await;
+        assert!(!cache.repo.read().await.contains_key("gone"));
+        assert_eq!(cache.expiry("kept".to_string()).await, Some(Some(later)));
+        assert_eq!(deadlines(&cache).await, 1);
+    }
+
+    #[tokio::test]
+    async fn keeps_keys_written_again_after_expiring() {
+        let cache = CacheRepository::default();
+        let expiry = past();
+        cache
+            .set_with_expiry_at("k".to_string(), b"v".to_vec(), expiry)
+            .await
+            .unwrap();
+        cache.actively_remove_expired_keys().await;
+        cache
+            .set("k".to_string(), b"persistent".to_vec())
+            .await
+            .unwrap();
+        cache.actively_remove_expired_keys().await;
+        assert_eq!(
+            cache.get("k".to_string()).await.unwrap(),
+            Some(b"persistent".to_vec())
+        );
+
+        // a deadline that was never cleaned up doesn't delete the key once it moved on
+        cache
+            .expiry_map
+            .write()
+            .await
+            .entry(expiry)
+            .or_default()
+            .insert("k".to_string());
+        let later = Instant::now() + Duration::from_secs(60);
+        cache.expire_at("k".to_string(), later).await;
+        cache.actively_remove_expired_keys().await;
+        assert_eq!(cache.expiry("k".to_string()).await, Some(Some(later)));
+        assert_eq!(deadlines(&cache).await, 1);
+    }
+
+    #[tokio::test]
+    async fn forgets_deadlines_of_overwritten_keys() {
+        let mut cache = CacheRepository::default();
+        for ttl in 1..=100 {
+            cache
+                .set_with_expiry("k".to_string(), b"v".to_vec(), ttl * 1000)
+                .await
+                .unwrap();
+        }
+        assert_eq!(deadlines(&cache).await, 1);
+        cache.persist("k".to_string()).await;
+        assert_eq!(deadlines(&cache).await, 0);
+    }
+
+    #[tokio::test]
+    async fn scan_returns_keys_present_throughout_exactly_once() {
+        let cache = CacheRepository::default();
+        for idx in 0..200 {
+            cache
+                .set(format!("stable:{}", idx), b"v".to_vec())
+                .await
+                .unwrap();
+            cache
+                .set(format!("gone:{}", idx), b"v".to_vec())
+                .await
+                .unwrap();
+        }
+
+        let mut seen = HashMap::new();
+        let (mut cursor, mut page) = cache.scan(0, 10).await;
+        let mut round = 0;
+        loop {
+            for key in page {
+                *seen.entry(key).or_insert(0) += 1;
+            }
+            if cursor == 0 {
+                break;
+            }
+            // the keyspace changes between every two pages
+            cache.remove(format!("gone:{}", round)).await;
+            cache
+                .modify(format!("gone:{}", round + 1), |value| value.take())
+                .await;
+            cache
+                .set(format!("new:{}", round), b"v".to_vec())
+                .await
+                .unwrap();
+            round += 2;
+            (cursor, page) = cache.scan(cursor, 10).await;
+        }
+
+        assert!(round > 10);
+        for idx in 0..200 {
+            assert_eq!(
+                seen.get(&format!("stable:{}", idx)),
+                Some(&1),
+                "stable:{}",
+                idx
+            );
+        }
+        assert!(seen.values().all(|count| *count == 1));
+        assert!(cache
+            .scan_index
+            .read()
+            .await
+            .values()
+            .all(|keys| !keys.is_empty()));
+        let indexed: usize = cache
+            .scan_index
+            .read()
+            .await
+            .values()
+            .map(HashSet::len)
+            .sum();
+        assert_eq!(indexed, cache.repo.read().await.len());
+    }
+
+    #[tokio::test]
+    async fn scan_skips_and_unindexes_expired_keys() {
+        let cache = CacheRepository::default();
+        cache.set("live".to_string(), b"v".to_vec()).await.unwrap();
+        cache
+            .set_with_expiry_at("dead".to_string(), b"v".to_vec(), past())
+            .await
+            .unwrap();
+
+        assert_eq!(cache.scan(0, 10).await, (0, vec!["live".to_string()]));
+        cache.actively_remove_expired_keys().await;
+        assert_eq!(cache.scan_index.read().await.len(), 1);
+        cache.clear().await;
+        assert!(cache.scan_index.read().await.is_empty());
+    }
+
+    #[test]
+    fn scan_page_keeps_groups_sharing_a_hash_together() {
+        let items = vec![(5, 'c'), (1, 'a'), (3, 'b'), (3, 'B'), (9, 'd')];
+
+        let (cursor, mut page) = scan_page(items.clone(), 0, 2);
+        page.sort();
+        assert_eq!((cursor, page), (5, vec!['B', 'a', 'b']));
+        assert_eq!(scan_page(items.clone(), 5, 2), (0, vec!['c', 'd']));
+        assert_eq!(scan_page(items, 6, 0), (0, vec!['d']));
+    }
+}
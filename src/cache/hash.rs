@@ -0,0 +1,85 @@
+use std::{collections::HashMap, io};
+
+use super::core::{wrong_type, Value};
+
+pub type Hash = HashMap<Vec<u8>, Vec<u8>>;
+
+/// The hash a key holds, `WRONGTYPE` when it holds something else.
+pub fn as_hash(value: Option<&Value>) -> io::Result<Option<&Hash>> {
+    match value {
+        Some(Value::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_hash`, for changing the hash in place.
+pub fn as_hash_mut(value: &mut Option<Value>) -> io::Result<Option<&mut Hash>> {
+    match value {
+        Some(Value::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_hash_mut`, creating an empty hash when the key is missing.
+pub fn as_hash_or_create(value: &mut Option<Value>) -> io::Result<&mut Hash> {
+    let value = value.get_or_insert_with(|| Value::Hash(Hash::new()));
+    match value {
+        Value::Hash(hash) => Ok(hash),
+        _ => Err(wrong_type()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{
+        aof::core::replay,
+        cache::{
+            blocking::encode_command,
+            core::{CacheRepository, Value},
+        },
+        command::core::test_support::{aof_path, client, send},
+    };
+
+    #[tokio::test]
+    async fn transactions_propagate_hash_writes() {
+        let path = aof_path("hash-transaction");
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), Some(path.clone())).await;
+
+        send(&mut conn, &repo, &["MULTI"]).await;
+        send(
+            &mut conn,
+            &repo,
+            &["HSET", "h", "a", "1", "b", "2", "c", "3"],
+        )
+        .await;
+        send(&mut conn, &repo, &["HINCRBY", "h", "a", "10"]).await;
+        send(&mut conn, &repo, &["HINCRBYFLOAT", "h", "b", "0.5"]).await;
+        send(&mut conn, &repo, &["HDEL", "h", "c"]).await;
+        send(&mut conn, &repo, &["EXEC"]).await;
+
+        // replicas are sent exactly what is appended to the log
+        let streamed = conn.cmdq.lock().await.backlog.read_from(0).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), streamed);
+        assert!(streamed.starts_with(&encode_command(&["MULTI"])));
+        assert!(streamed.ends_with(&encode_command(&["EXEC"])));
+
+        let restarted = Arc::new(Mutex::new(CacheRepository::default()));
+        replay(&path, restarted.clone()).await.unwrap();
+        let hash = restarted.lock().await.get_value("h".to_string()).await;
+        assert_eq!(hash, repo.lock().await.get_value("h".to_string()).await);
+        let Some(Value::Hash(hash)) = hash else {
+            panic!("expected a hash, got {:?}", hash);
+        };
+        assert_eq!(hash.get(b"a".as_slice()), Some(&b"11".to_vec()));
+        assert_eq!(hash.get(b"b".as_slice()), Some(&b"2.5".to_vec()));
+        assert!(!hash.contains_key(b"c".as_slice()));
+        let _ = std::fs::remove_file(&path);
+    }
+}
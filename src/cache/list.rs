@@ -0,0 +1,81 @@
+use std::{collections::VecDeque, io};
+
+use crate::resp::deserialize::bytes_to_string;
+
+use super::core::{wrong_type, Value};
+
+pub type List = VecDeque<Vec<u8>>;
+
+/// The end of a list an element is pushed to or popped from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Parses the `LEFT` or `RIGHT` argument of `LMOVE` and `BLMOVE`.
+    pub fn parse(arg: &[u8]) -> Option<Side> {
+        match bytes_to_string(arg).ok()?.to_lowercase().as_str() {
+            "left" => Some(Side::Left),
+            "right" => Some(Side::Right),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Side::Left => "LEFT",
+            Side::Right => "RIGHT",
+        }
+    }
+
+    /// The non blocking pop that takes from this end.
+    pub fn pop_command(&self) -> &'static str {
+        match self {
+            Side::Left => "LPOP",
+            Side::Right => "RPOP",
+        }
+    }
+}
+
+pub fn push(list: &mut List, side: Side, elem: Vec<u8>) {
+    match side {
+        Side::Left => list.push_front(elem),
+        Side::Right => list.push_back(elem),
+    }
+}
+
+pub fn pop(list: &mut List, side: Side) -> Option<Vec<u8>> {
+    match side {
+        Side::Left => list.pop_front(),
+        Side::Right => list.pop_back(),
+    }
+}
+
+/// The list a key holds, `WRONGTYPE` when it holds something else.
+pub fn as_list(value: Option<&Value>) -> io::Result<Option<&List>> {
+    match value {
+        Some(Value::List(list)) => Ok(Some(list)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_list`, for changing the list in place.
+pub fn as_list_mut(value: &mut Option<Value>) -> io::Result<Option<&mut List>> {
+    match value {
+        Some(Value::List(list)) => Ok(Some(list)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_list_mut`, creating an empty list when the key is missing.
+pub fn as_list_or_create(value: &mut Option<Value>) -> io::Result<&mut List> {
+    let value = value.get_or_insert_with(|| Value::List(List::new()));
+    match value {
+        Value::List(list) => Ok(list),
+        _ => Err(wrong_type()),
+    }
+}
@@ -1 +1,8 @@
+pub mod bitmap;
+pub mod blocking;
 pub mod core;
+pub mod hash;
+pub mod list;
+pub mod set;
+pub mod sorted_set;
+pub mod stream;
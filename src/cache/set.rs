@@ -0,0 +1,73 @@
+use std::{collections::HashSet, io};
+
+use super::core::{wrong_type, CacheRepository, Value};
+
+pub type Set = HashSet<Vec<u8>>;
+
+/// The set a key holds, `WRONGTYPE` when it holds something else.
+pub fn as_set(value: Option<&Value>) -> io::Result<Option<&Set>> {
+    match value {
+        Some(Value::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_set`, for changing the set in place.
+pub fn as_set_mut(value: &mut Option<Value>) -> io::Result<Option<&mut Set>> {
+    match value {
+        Some(Value::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_set_mut`, creating an empty set when the key is missing.
+pub fn as_set_or_create(value: &mut Option<Value>) -> io::Result<&mut Set> {
+    let value = value.get_or_insert_with(|| Value::Set(Set::new()));
+    match value {
+        Value::Set(set) => Ok(set),
+        _ => Err(wrong_type()),
+    }
+}
+
+/// How `SINTER`, `SUNION` and `SDIFF` combine the sets they are given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl SetOp {
+    /// Combines `sets` in order, a missing key counting as an empty set.
+    pub fn combine(&self, sets: Vec<Set>) -> Set {
+        let mut sets = sets.into_iter();
+        let mut result = sets.next().unwrap_or_default();
+        for set in sets {
+            match self {
+                SetOp::Inter => result.retain(|member| set.contains(member)),
+                SetOp::Union => result.extend(set),
+                SetOp::Diff => result.retain(|member| !set.contains(member)),
+            }
+        }
+        result
+    }
+}
+
+impl CacheRepository {
+    /// Copies of the sets held by `keys`, empty for missing keys and `WRONGTYPE` as soon as
+    /// one holds something else.
+    pub async fn sets(&self, keys: &[String]) -> io::Result<Vec<Set>> {
+        let mut sets = Vec::with_capacity(keys.len());
+        for key in keys {
+            let set = self
+                .view(key.to_string(), |value| {
+                    as_set(value).map(|set| set.cloned().unwrap_or_default())
+                })
+                .await?;
+            sets.push(set);
+        }
+        Ok(sets)
+    }
+}
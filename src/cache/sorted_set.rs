@@ -0,0 +1,737 @@
+use std::{cmp::Ordering, collections::HashMap, io};
+
+use rand::Rng;
+
+use super::core::{wrong_type, Value};
+
+/// Levels a skiplist node can have, enough for 4^32 members.
+const MAX_LEVEL: usize = 32;
+/// The chance a node makes it to the next level.
+const LEVEL_PROBABILITY: f64 = 0.25;
+/// Index of the header node, which holds no member.
+const HEAD: usize = 0;
+
+/// The order of a sorted set, by score and then lexicographically by member.
+pub fn compare(a: (&[u8], f64), b: (&[u8], f64)) -> Ordering {
+    a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0))
+}
+
+#[derive(Debug, Clone)]
+struct Level {
+    forward: Option<usize>,
+    /// how many nodes the forward link skips over, which is what ranks are counted with
+    span: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    member: Vec<u8>,
+    score: f64,
+    backward: Option<usize>,
+    levels: Vec<Level>,
+}
+
+/// A skiplist whose links count the nodes they skip, so finding the rank of a member or the
+/// member at a rank takes O(log n). Nodes live in an arena and link to each other by index.
+#[derive(Debug, Clone)]
+struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    tail: Option<usize>,
+    level: usize,
+    len: usize,
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        SkipList {
+            nodes: vec![Node {
+                member: vec![],
+                score: 0.0,
+                backward: None,
+                levels: vec![
+                    Level {
+                        forward: None,
+                        span: 0,
+                    };
+                    MAX_LEVEL
+                ],
+            }],
+            free: vec![],
+            tail: None,
+            level: 1,
+            len: 0,
+        }
+    }
+}
+
+impl SkipList {
+    fn random_level() -> usize {
+        let mut rng = rand::thread_rng();
+        let mut level = 1;
+        while level < MAX_LEVEL && rng.gen_bool(LEVEL_PROBABILITY) {
+            level += 1;
+        }
+        level
+    }
+
+    fn entry(&self, idx: usize) -> (&[u8], f64) {
+        let node = &self.nodes[idx];
+        (&node.member, node.score)
+    }
+
+    fn forward(&self, idx: usize, level: usize) -> Option<usize> {
+        self.nodes[idx].levels[level].forward
+    }
+
+    fn span(&self, idx: usize, level: usize) -> usize {
+        self.nodes[idx].levels[level].span
+    }
+
+    fn next(&self, idx: usize) -> Option<usize> {
+        self.forward(idx, 0)
+    }
+
+    fn prev(&self, idx: usize) -> Option<usize> {
+        self.nodes[idx].backward
+    }
+
+    fn first(&self) -> Option<usize> {
+        self.next(HEAD)
+    }
+
+    /// The last node on every level that sorts before `target`, and the rank reached there.
+    fn predecessors(&self, target: (&[u8], f64)) -> ([usize; MAX_LEVEL], [usize; MAX_LEVEL]) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i + 1 == self.level { 0 } else { rank[i + 1] };
+            while let Some(next) = self.forward(x, i) {
+                if compare(self.entry(next), target) != Ordering::Less {
+                    break;
+                }
+                rank[i] += self.span(x, i);
+                x = next;
+            }
+            update[i] = x;
+        }
+        (update, rank)
+    }
+
+    /// Inserts a member that isn't in the list yet.
+    fn insert(&mut self, member: Vec<u8>, score: f64) {
+        let (mut update, mut rank) = self.predecessors((&member, score));
+
+        let level = Self::random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let node = Node {
+            member,
+            score,
+            backward: (update[0] != HEAD).then_some(update[0]),
+            levels: vec![
+                Level {
+                    forward: None,
+                    span: 0,
+                };
+                level
+            ],
+        };
+        let new = match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx] = node;
+                idx
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+
+        for i in 0..level {
+            let prev = update[i];
+            let skipped = rank[0] - rank[i];
+            self.nodes[new].levels[i] = Level {
+                forward: self.forward(prev, i),
+                span: self.span(prev, i) - skipped,
+            };
+            self.nodes[prev].levels[i] = Level {
+                forward: Some(new),
+                span: skipped + 1,
+            };
+        }
+        for (i, prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[*prev].levels[i].span += 1;
+        }
+
+        match self.next(new) {
+            Some(next) => self.nodes[next].backward = Some(new),
+            None => self.tail = Some(new),
+        }
+        self.len += 1;
+    }
+
+    /// Removes a member with the score it was inserted with. Returns whether it was there.
+    fn remove(&mut self, member: &[u8], score: f64) -> bool {
+        let (update, _) = self.predecessors((member, score));
+        let Some(x) = self.next(update[0]) else {
+            return false;
+        };
+        if compare(self.entry(x), (member, score)) != Ordering::Equal {
+            return false;
+        }
+
+        for (i, prev) in update.iter().enumerate().take(self.level) {
+            if self.forward(*prev, i) == Some(x) {
+                let span = self.span(*prev, i) + self.span(x, i) - 1;
+                self.nodes[*prev].levels[i] = Level {
+                    forward: self.forward(x, i),
+                    span,
+                };
+            } else {
+                self.nodes[*prev].levels[i].span -= 1;
+            }
+        }
+        match self.next(x) {
+            Some(next) => self.nodes[next].backward = self.prev(x),
+            None => self.tail = self.prev(x),
+        }
+        while self.level > 1 && self.forward(HEAD, self.level - 1).is_none() {
+            self.level -= 1;
+        }
+
+        self.nodes[x].member = vec![];
+        self.nodes[x].levels = vec![];
+        self.free.push(x);
+        self.len -= 1;
+        true
+    }
+
+    /// The 0 based rank of a member with its score.
+    fn rank(&self, member: &[u8], score: f64) -> Option<usize> {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if compare(self.entry(next), (member, score)) == Ordering::Greater {
+                    break;
+                }
+                rank += self.span(x, i);
+                x = next;
+            }
+            if x != HEAD && compare(self.entry(x), (member, score)) == Ordering::Equal {
+                return Some(rank - 1);
+            }
+        }
+        None
+    }
+
+    /// The node at a 0 based rank.
+    fn by_rank(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut traversed = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if traversed + self.span(x, i) > target {
+                    break;
+                }
+                traversed += self.span(x, i);
+                x = next;
+            }
+            if traversed == target {
+                return Some(x);
+            }
+        }
+        None
+    }
+
+    /// The first node `before` doesn't hold for, with its rank. `before` has to hold for a
+    /// prefix of the list.
+    fn first_not(&self, before: impl Fn((&[u8], f64)) -> bool) -> Option<(usize, usize)> {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if !before(self.entry(next)) {
+                    break;
+                }
+                rank += self.span(x, i);
+                x = next;
+            }
+        }
+        self.next(x).map(|next| (next, rank))
+    }
+
+    /// The last node `upto` holds for, with its rank. `upto` has to hold for a prefix of the
+    /// list.
+    fn last(&self, upto: impl Fn((&[u8], f64)) -> bool) -> Option<(usize, usize)> {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if !upto(self.entry(next)) {
+                    break;
+                }
+                rank += self.span(x, i);
+                x = next;
+            }
+        }
+        (x != HEAD).then(|| (x, rank - 1))
+    }
+}
+
+/// One end of a score range, `(` making it exclusive.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreBound {
+    pub score: f64,
+    pub exclusive: bool,
+}
+
+impl ScoreBound {
+    /// Parses `1.5`, `(1.5`, `-inf` or `+inf`.
+    pub fn parse(arg: &[u8]) -> io::Result<ScoreBound> {
+        let (exclusive, score) = match arg.strip_prefix(b"(") {
+            Some(score) => (true, score),
+            None => (false, arg),
+        };
+        std::str::from_utf8(score)
+            .ok()
+            .and_then(|score| score.parse::<f64>().ok())
+            .filter(|score| !score.is_nan())
+            .map(|score| ScoreBound { score, exclusive })
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "ERR min or max is not a float")
+            })
+    }
+}
+
+/// One end of a lexicographical range.
+#[derive(Debug, Clone)]
+pub enum LexBound {
+    Min,
+    Max,
+    Inclusive(Vec<u8>),
+    Exclusive(Vec<u8>),
+}
+
+impl LexBound {
+    /// Parses `-`, `+`, `[member` or `(member`.
+    pub fn parse(arg: &[u8]) -> io::Result<LexBound> {
+        match arg.split_first() {
+            Some((b'-', [])) => Ok(LexBound::Min),
+            Some((b'+', [])) => Ok(LexBound::Max),
+            Some((b'[', member)) => Ok(LexBound::Inclusive(member.to_vec())),
+            Some((b'(', member)) => Ok(LexBound::Exclusive(member.to_vec())),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR min or max not valid string range item",
+            )),
+        }
+    }
+}
+
+/// A range of members by score or, when they all share a score, by member.
+#[derive(Debug, Clone)]
+pub enum Range {
+    Score(ScoreBound, ScoreBound),
+    Lex(LexBound, LexBound),
+}
+
+impl Range {
+    /// Whether an entry sorts before the start of the range.
+    fn before_start(&self, (member, score): (&[u8], f64)) -> bool {
+        match self {
+            Range::Score(min, _) => match min.exclusive {
+                true => score <= min.score,
+                false => score < min.score,
+            },
+            Range::Lex(min, _) => match min {
+                LexBound::Min => false,
+                LexBound::Max => true,
+                LexBound::Inclusive(min) => member < min.as_slice(),
+                LexBound::Exclusive(min) => member <= min.as_slice(),
+            },
+        }
+    }
+
+    /// Whether an entry doesn't sort after the end of the range.
+    fn upto_end(&self, (member, score): (&[u8], f64)) -> bool {
+        match self {
+            Range::Score(_, max) => match max.exclusive {
+                true => score < max.score,
+                false => score <= max.score,
+            },
+            Range::Lex(_, max) => match max {
+                LexBound::Min => false,
+                LexBound::Max => true,
+                LexBound::Inclusive(max) => member <= max.as_slice(),
+                LexBound::Exclusive(max) => member < max.as_slice(),
+            },
+        }
+    }
+}
+
+/// Members with a score, ordered by score and then by member. Scores are looked up in a map
+/// and the order is kept by a skiplist.
+#[derive(Debug, Clone, Default)]
+pub struct SortedSet {
+    scores: HashMap<Vec<u8>, f64>,
+    list: SkipList,
+}
+
+impl PartialEq for SortedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.scores == other.scores
+    }
+}
+
+impl SortedSet {
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Adds a member or updates its score, returning whether it is new.
+    pub fn insert(&mut self, member: Vec<u8>, score: f64) -> bool {
+        match self.scores.insert(member.to_vec(), score) {
+            // -0 and 0 are apart in the list order
+            Some(old) if old.to_bits() == score.to_bits() => false,
+            Some(old) => {
+                self.list.remove(&member, old);
+                self.list.insert(member, score);
+                false
+            }
+            None => {
+                self.list.insert(member, score);
+                true
+            }
+        }
+    }
+
+    /// Removes a member, returning whether it was there.
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.list.remove(member, score),
+            None => false,
+        }
+    }
+
+    /// The 0 based rank of a member, counting from the highest score when `rev`.
+    pub fn rank(&self, member: &[u8], rev: bool) -> Option<usize> {
+        let rank = self.list.rank(member, self.score(member)?)?;
+        Some(if rev { self.len() - 1 - rank } else { rank })
+    }
+
+    /// Every member with its score, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], f64)> + '_ {
+        std::iter::successors(self.list.first(), |idx| self.list.next(*idx))
+            .map(|idx| self.list.entry(idx))
+    }
+
+    /// The members from rank `start` to `stop` inclusive, counting from the highest score
+    /// when `rev`.
+    pub fn range_by_rank(&self, start: usize, stop: usize, rev: bool) -> Vec<(Vec<u8>, f64)> {
+        if start > stop || stop >= self.len() {
+            return vec![];
+        }
+        let first = if rev { self.len() - 1 - start } else { start };
+        let step = |idx: &usize| match rev {
+            true => self.list.prev(*idx),
+            false => self.list.next(*idx),
+        };
+        std::iter::successors(self.list.by_rank(first), step)
+            .take(stop - start + 1)
+            .map(|idx| self.owned(idx))
+            .collect()
+    }
+
+    /// The members within `range`, from the highest when `rev`, skipping `offset` of them and
+    /// returning at most `limit`.
+    pub fn range(
+        &self,
+        range: &Range,
+        rev: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Vec<(Vec<u8>, f64)> {
+        let limit = limit.unwrap_or(usize::MAX);
+        if rev {
+            let start = self
+                .list
+                .last(|entry| range.upto_end(entry))
+                .map(|(idx, _)| idx);
+            std::iter::successors(start, |idx| self.list.prev(*idx))
+                .take_while(|idx| !range.before_start(self.list.entry(*idx)))
+                .skip(offset)
+                .take(limit)
+                .map(|idx| self.owned(idx))
+                .collect()
+        } else {
+            let start = self
+                .list
+                .first_not(|entry| range.before_start(entry))
+                .map(|(idx, _)| idx);
+            std::iter::successors(start, |idx| self.list.next(*idx))
+                .take_while(|idx| range.upto_end(self.list.entry(*idx)))
+                .skip(offset)
+                .take(limit)
+                .map(|idx| self.owned(idx))
+                .collect()
+        }
+    }
+
+    /// How many members are within `range`, found from their ranks.
+    pub fn count(&self, range: &Range) -> usize {
+        let first = self.list.first_not(|entry| range.before_start(entry));
+        let last = self.list.last(|entry| range.upto_end(entry));
+        match (first, last) {
+            (Some((_, first)), Some((_, last))) if first <= last => last - first + 1,
+            _ => 0,
+        }
+    }
+
+    /// Removes and returns up to `count` of the lowest members, or the highest when `max`.
+    pub fn pop(&mut self, count: usize, max: bool) -> Vec<(Vec<u8>, f64)> {
+        let mut popped = Vec::with_capacity(count.min(self.len()));
+        while popped.len() < count {
+            let end = if max {
+                self.list.tail
+            } else {
+                self.list.first()
+            };
+            let Some(idx) = end else {
+                break;
+            };
+            let (member, score) = self.owned(idx);
+            self.remove(&member);
+            popped.push((member, score));
+        }
+        popped
+    }
+
+    fn owned(&self, idx: usize) -> (Vec<u8>, f64) {
+        let (member, score) = self.list.entry(idx);
+        (member.to_vec(), score)
+    }
+}
+
+/// The sorted set a key holds, `WRONGTYPE` when it holds something else.
+pub fn as_zset(value: Option<&Value>) -> io::Result<Option<&SortedSet>> {
+    match value {
+        Some(Value::SortedSet(zset)) => Ok(Some(zset)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_zset`, for changing the sorted set in place.
+pub fn as_zset_mut(value: &mut Option<Value>) -> io::Result<Option<&mut SortedSet>> {
+    match value {
+        Some(Value::SortedSet(zset)) => Ok(Some(zset)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_zset_mut`, creating an empty sorted set when the key is missing.
+pub fn as_zset_or_create(value: &mut Option<Value>) -> io::Result<&mut SortedSet> {
+    let value = value.get_or_insert_with(|| Value::SortedSet(SortedSet::default()));
+    match value {
+        Value::SortedSet(zset) => Ok(zset),
+        _ => Err(wrong_type()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    /// Checks that every link points at the next node tall enough and that its span is the
+    /// number of nodes it skips, which is what ranks are counted from.
+    fn check_spans(list: &SkipList) {
+        let order: Vec<usize> =
+            std::iter::successors(list.first(), |idx| list.next(*idx)).collect();
+        assert_eq!(order.len(), list.len);
+        let position = |idx: usize| order.iter().position(|x| *x == idx).unwrap() + 1;
+        for (pos, idx) in std::iter::once(HEAD)
+            .chain(order.iter().copied())
+            .enumerate()
+        {
+            for i in 0..list.nodes[idx].levels.len().min(list.level) {
+                let expected = order[pos..]
+                    .iter()
+                    .copied()
+                    .find(|next| list.nodes[*next].levels.len() > i);
+                assert_eq!(list.forward(idx, i), expected, "level {}", i);
+                if let Some(next) = expected {
+                    assert_eq!(list.span(idx, i), position(next) - pos, "level {}", i);
+                }
+            }
+        }
+        assert_eq!(list.tail, order.last().copied());
+    }
+
+    fn members(zset: &SortedSet) -> Vec<(Vec<u8>, f64)> {
+        zset.iter()
+            .map(|(member, score)| (member.to_vec(), score))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_spans_and_ranks_through_random_updates() {
+        let mut rng = rand::thread_rng();
+        let mut zset = SortedSet::default();
+        let mut model: Vec<(Vec<u8>, f64)> = vec![];
+        for _ in 0..2000 {
+            let member = format!("m{}", rng.gen_range(0..300)).into_bytes();
+            if rng.gen_bool(0.7) {
+                let score = rng.gen_range(0..50) as f64;
+                zset.insert(member.clone(), score);
+                model.retain(|(m, _)| *m != member);
+                model.push((member, score));
+            } else {
+                let existed = model.iter().any(|(m, _)| *m == member);
+                assert_eq!(zset.remove(&member), existed);
+                model.retain(|(m, _)| *m != member);
+            }
+        }
+        model.sort_by(|a, b| compare((&a.0, a.1), (&b.0, b.1)));
+
+        check_spans(&zset.list);
+        assert_eq!(members(&zset), model);
+        for (rank, (member, _)) in model.iter().enumerate() {
+            assert_eq!(zset.rank(member, false), Some(rank));
+            assert_eq!(zset.rank(member, true), Some(model.len() - 1 - rank));
+            assert_eq!(
+                zset.range_by_rank(rank, rank, false),
+                vec![model[rank].clone()]
+            );
+        }
+        assert_eq!(zset.rank(b"missing", false), None);
+        assert_eq!(zset.list.by_rank(model.len()), None);
+    }
+
+    #[test]
+    fn reuses_freed_nodes() {
+        let mut zset = SortedSet::default();
+        for (idx, member) in ["a", "b", "c"].iter().enumerate() {
+            zset.insert(member.as_bytes().to_vec(), idx as f64);
+        }
+        assert!(zset.remove(b"b"));
+        assert!(!zset.remove(b"b"));
+        zset.insert(b"d".to_vec(), 0.5);
+        assert_eq!(zset.list.nodes.len(), 4, "the header and three members");
+        check_spans(&zset.list);
+        assert_eq!(zset.rank(b"d", false), Some(1));
+        assert_eq!(zset.rank(b"c", false), Some(2));
+    }
+
+    #[test]
+    fn updating_a_score_moves_the_member() {
+        let mut zset = SortedSet::default();
+        assert!(zset.insert(b"a".to_vec(), 1.0));
+        assert!(zset.insert(b"b".to_vec(), 2.0));
+        assert!(!zset.insert(b"a".to_vec(), 3.0));
+        check_spans(&zset.list);
+        assert_eq!(zset.score(b"a"), Some(3.0));
+        assert_eq!(zset.rank(b"a", false), Some(1));
+        assert_eq!(zset.len(), 2);
+    }
+
+    #[test]
+    fn ranges_by_rank_from_either_end() {
+        let mut zset = SortedSet::default();
+        for score in 0..10 {
+            zset.insert(format!("m{}", score).into_bytes(), score as f64);
+        }
+        let names = |range: Vec<(Vec<u8>, f64)>| -> Vec<String> {
+            range
+                .into_iter()
+                .map(|(member, _)| String::from_utf8(member).unwrap())
+                .collect()
+        };
+        assert_eq!(names(zset.range_by_rank(2, 4, false)), ["m2", "m3", "m4"]);
+        assert_eq!(names(zset.range_by_rank(0, 1, true)), ["m9", "m8"]);
+        assert!(zset.range_by_rank(3, 2, false).is_empty());
+        assert!(zset.range_by_rank(0, 10, false).is_empty());
+    }
+
+    #[test]
+    fn ranges_and_counts_by_score_and_lex() {
+        let mut zset = SortedSet::default();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 2.0), ("d", 3.0)] {
+            zset.insert(member.as_bytes().to_vec(), score);
+        }
+        let score = |min: &str, max: &str| {
+            Range::Score(
+                ScoreBound::parse(min.as_bytes()).unwrap(),
+                ScoreBound::parse(max.as_bytes()).unwrap(),
+            )
+        };
+        let lex = |min: &str, max: &str| {
+            Range::Lex(
+                LexBound::parse(min.as_bytes()).unwrap(),
+                LexBound::parse(max.as_bytes()).unwrap(),
+            )
+        };
+
+        assert_eq!(zset.count(&score("-inf", "+inf")), 4);
+        assert_eq!(zset.count(&score("2", "2")), 2);
+        assert_eq!(zset.count(&score("(1", "(3")), 2);
+        assert_eq!(zset.count(&score("(2", "(3")), 0);
+        assert_eq!(zset.count(&score("5", "1")), 0);
+        assert_eq!(zset.count(&lex("[b", "(d")), 2);
+        assert_eq!(zset.count(&lex("+", "-")), 0);
+
+        let range = zset.range(&score("2", "+inf"), false, 1, Some(1));
+        assert_eq!(range, vec![(b"c".to_vec(), 2.0)]);
+        let range = zset.range(&score("-inf", "(3"), true, 0, None);
+        assert_eq!(
+            range,
+            vec![
+                (b"c".to_vec(), 2.0),
+                (b"b".to_vec(), 2.0),
+                (b"a".to_vec(), 1.0)
+            ]
+        );
+        let range = zset.range(&lex("(a", "[c"), true, 0, None);
+        assert_eq!(range, vec![(b"c".to_vec(), 2.0), (b"b".to_vec(), 2.0)]);
+
+        assert!(ScoreBound::parse(b"nan").is_err());
+        assert!(LexBound::parse(b"b").is_err());
+    }
+
+    #[test]
+    fn pops_from_either_end() {
+        let mut zset = SortedSet::default();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 3.0)] {
+            zset.insert(member.as_bytes().to_vec(), score);
+        }
+        assert_eq!(zset.pop(1, true), vec![(b"c".to_vec(), 3.0)]);
+        assert_eq!(
+            zset.pop(5, false),
+            vec![(b"a".to_vec(), 1.0), (b"b".to_vec(), 2.0)]
+        );
+        assert!(zset.is_empty());
+        check_spans(&zset.list);
+        assert_eq!(zset.list.level, 1);
+    }
+}
@@ -0,0 +1,811 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io::{self, Error},
+};
+
+use crate::resp::deserialize::bytes_to_string;
+
+use super::core::{wrong_type, Value};
+
+/// The id of a stream entry, `<milliseconds>-<sequence>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl Display for StreamId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+pub fn invalid_id() -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        "ERR Invalid stream ID specified as stream command argument",
+    )
+}
+
+/// The error of the group commands naming a key or a group that doesn't exist.
+pub fn no_such_key_or_group(key: &str, group: &[u8]) -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "NOGROUP No such key '{}' or consumer group '{}'",
+            key,
+            String::from_utf8_lossy(group)
+        ),
+    )
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    /// Parses `<ms>-<seq>`, or a bare `<ms>` taking `missing_seq` as its sequence.
+    pub fn parse(arg: &[u8], missing_seq: u64) -> io::Result<StreamId> {
+        let arg = bytes_to_string(arg).map_err(|_| invalid_id())?;
+        let (ms, seq) = match arg.split_once('-') {
+            Some((ms, seq)) => (ms, Some(seq)),
+            None => (arg.as_str(), None),
+        };
+        let ms = ms.parse().map_err(|_| invalid_id())?;
+        let seq = match seq {
+            Some(seq) => seq.parse().map_err(|_| invalid_id())?,
+            None => missing_seq,
+        };
+        Ok(StreamId { ms, seq })
+    }
+
+    /// Parses a bound of an id range: `-` and `+` for the smallest and greatest ids, an id
+    /// that bare milliseconds extend to the whole millisecond, or one after `(` to exclude it.
+    pub fn parse_bound(arg: &[u8], end: bool) -> io::Result<StreamId> {
+        let missing_seq = if end { u64::MAX } else { 0 };
+        match arg {
+            b"-" => Ok(StreamId::MIN),
+            b"+" => Ok(StreamId::MAX),
+            [b'(', id @ ..] => {
+                let id = StreamId::parse(id, missing_seq)?;
+                let excluded = if end { id.prev() } else { id.next() };
+                excluded.ok_or_else(|| {
+                    let side = if end { "end" } else { "start" };
+                    Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("ERR invalid {} ID for the interval", side),
+                    )
+                })
+            }
+            id => StreamId::parse(id, missing_seq),
+        }
+    }
+
+    pub fn next(self) -> Option<StreamId> {
+        match (self.ms, self.seq) {
+            (_, seq) if seq < u64::MAX => Some(StreamId {
+                seq: seq + 1,
+                ..self
+            }),
+            (ms, _) if ms < u64::MAX => Some(StreamId { ms: ms + 1, seq: 0 }),
+            _ => None,
+        }
+    }
+
+    pub fn prev(self) -> Option<StreamId> {
+        match (self.ms, self.seq) {
+            (_, seq) if seq > 0 => Some(StreamId {
+                seq: seq - 1,
+                ..self
+            }),
+            (ms, _) if ms > 0 => Some(StreamId {
+                ms: ms - 1,
+                seq: u64::MAX,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The big endian form snapshots store ids in.
+    pub fn to_be_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.ms.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.seq.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_be_bytes(bytes: &[u8]) -> Option<StreamId> {
+        if bytes.len() != 16 {
+            return None;
+        }
+        Some(StreamId {
+            ms: u64::from_be_bytes(bytes[..8].try_into().unwrap()),
+            seq: u64::from_be_bytes(bytes[8..].try_into().unwrap()),
+        })
+    }
+}
+
+/// The field value pairs of one entry.
+pub type StreamFields = Vec<(Vec<u8>, Vec<u8>)>;
+
+pub type StreamEntry = (StreamId, StreamFields);
+
+/// How `XADD` picks the id of a new entry.
+#[derive(Debug, Clone, Copy)]
+pub enum NewId {
+    /// `*`, the current time
+    Auto,
+    /// `<ms>-*`, the next sequence of that millisecond
+    AutoSeq(u64),
+    /// `<ms>-<seq>`, or a bare `<ms>` for its sequence 0
+    Explicit(StreamId),
+}
+
+impl NewId {
+    pub fn parse(arg: &[u8]) -> io::Result<NewId> {
+        if arg == b"*" {
+            return Ok(NewId::Auto);
+        }
+        match arg.strip_suffix(b"-*") {
+            Some(ms) if !ms.contains(&b'-') => Ok(NewId::AutoSeq(StreamId::parse(ms, 0)?.ms)),
+            _ => Ok(NewId::Explicit(StreamId::parse(arg, 0)?)),
+        }
+    }
+}
+
+/// Which of the oldest entries trimming evicts.
+#[derive(Debug, Clone, Copy)]
+pub enum TrimStrategy {
+    /// down to this many entries
+    MaxLen(usize),
+    /// the ones below this id
+    MinId(StreamId),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Trim {
+    pub strategy: TrimStrategy,
+    /// the most entries evicted at once, only for approximate trimming
+    pub limit: Option<usize>,
+}
+
+/// An entry delivered to a consumer of a group that hasn't acknowledged it yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEntry {
+    pub consumer: Vec<u8>,
+    /// unix milliseconds of the last delivery
+    pub delivered_at: u64,
+    pub deliveries: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Consumer {
+    /// unix milliseconds of its last attempt to read or claim
+    pub seen_at: u64,
+    /// unix milliseconds of its last read or claim that got something
+    pub active_at: Option<u64>,
+}
+
+/// Consumers sharing the entries of a stream, each entry delivered to only one of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsumerGroup {
+    pub last_delivered: StreamId,
+    /// how many entries the group read, unknown once its last id was set past deletions
+    pub entries_read: Option<u64>,
+    pub pending: BTreeMap<StreamId, PendingEntry>,
+    pub consumers: BTreeMap<Vec<u8>, Consumer>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_delivered: StreamId, entries_read: Option<u64>) -> Self {
+        ConsumerGroup {
+            last_delivered,
+            entries_read,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    /// Records that `name` was seen, creating it on first use. Returns whether it was created.
+    pub fn touch_consumer(&mut self, name: &[u8], now: u64) -> bool {
+        match self.consumers.get_mut(name) {
+            Some(consumer) => {
+                consumer.seen_at = now;
+                false
+            }
+            None => {
+                self.consumers.insert(
+                    name.to_vec(),
+                    Consumer {
+                        seen_at: now,
+                        active_at: None,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    /// The pending entries delivered to `consumer`, by id.
+    pub fn pending_of<'a>(
+        &'a self,
+        consumer: &'a [u8],
+    ) -> impl Iterator<Item = (&'a StreamId, &'a PendingEntry)> + 'a {
+        self.pending
+            .iter()
+            .filter(move |(_, pending)| pending.consumer == consumer)
+    }
+}
+
+/// How claiming changes a pending entry.
+#[derive(Debug, Default)]
+pub struct ClaimOptions {
+    /// entries delivered more recently than this many milliseconds ago are left alone
+    pub min_idle: u64,
+    /// unix milliseconds recorded as the delivery time instead of now
+    pub delivered_at: Option<u64>,
+    /// the delivery count to record instead of incrementing it
+    pub retry_count: Option<u64>,
+    /// also claims entries of the stream nobody has pending
+    pub force: bool,
+    /// claims without counting a delivery
+    pub just_id: bool,
+}
+
+/// What claiming one entry did.
+#[derive(Debug)]
+pub enum Claim {
+    /// handed to the consumer, with the fields of the entry and its delivery as recorded
+    Claimed(StreamFields, PendingEntry),
+    /// deleted from the stream meanwhile, so it was dropped from the pending entries
+    Deleted,
+    Skipped,
+}
+
+/// An append only log of field value pairs, ordered by id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stream {
+    pub entries: BTreeMap<StreamId, StreamFields>,
+    /// the greatest id the stream ever had, which new entries must be above
+    pub last_id: StreamId,
+    pub max_deleted_id: StreamId,
+    /// how many entries were ever added, evicted ones included
+    pub entries_added: u64,
+    pub groups: BTreeMap<Vec<u8>, ConsumerGroup>,
+}
+
+fn id_too_small() -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        "ERR The ID specified in XADD is equal or smaller than the target stream top item",
+    )
+}
+
+impl Stream {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn first_id(&self) -> StreamId {
+        self.entries
+            .first_key_value()
+            .map_or(StreamId::MIN, |(id, _)| *id)
+    }
+
+    pub fn first_entry(&self) -> Option<StreamEntry> {
+        self.entries
+            .first_key_value()
+            .map(|(id, fields)| (*id, fields.to_vec()))
+    }
+
+    pub fn last_entry(&self) -> Option<StreamEntry> {
+        self.entries
+            .last_key_value()
+            .map(|(id, fields)| (*id, fields.to_vec()))
+    }
+
+    /// The id a new entry gets, which has to be above every id the stream ever had.
+    pub fn next_id(&self, new_id: NewId, now: u64) -> io::Result<StreamId> {
+        let id = match new_id {
+            NewId::Auto if now > self.last_id.ms => StreamId { ms: now, seq: 0 },
+            NewId::Auto => self.last_id.next().ok_or_else(|| {
+                Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR The stream has exhausted the last possible ID, unable to add more items",
+                )
+            })?,
+            NewId::AutoSeq(ms) if ms == self.last_id.ms => {
+                if self.last_id.seq == u64::MAX {
+                    return Err(id_too_small());
+                }
+                StreamId {
+                    ms,
+                    seq: self.last_id.seq + 1,
+                }
+            }
+            NewId::AutoSeq(ms) => StreamId { ms, seq: 0 },
+            NewId::Explicit(StreamId::MIN) => {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR The ID specified in XADD must be greater than 0-0",
+                ));
+            }
+            NewId::Explicit(id) => id,
+        };
+        if id <= self.last_id {
+            return Err(id_too_small());
+        }
+        Ok(id)
+    }
+
+    /// Appends an entry, `id` must come from `next_id`.
+    pub fn add(&mut self, id: StreamId, fields: StreamFields) {
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+    }
+
+    /// Evicts the oldest entries `trim` selects. Returns how many were evicted.
+    pub fn trim(&mut self, trim: &Trim) -> usize {
+        let mut evicted = 0;
+        while let Some((&first, _)) = self.entries.first_key_value() {
+            let evict = match trim.strategy {
+                TrimStrategy::MaxLen(max_len) => self.entries.len() > max_len,
+                TrimStrategy::MinId(min_id) => first < min_id,
+            };
+            if !evict || trim.limit.is_some_and(|limit| evicted >= limit) {
+                break;
+            }
+            self.entries.pop_first();
+            evicted += 1;
+        }
+        evicted
+    }
+
+    /// The entries from `start` to `end` included, from the end when `rev`.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        rev: bool,
+        count: Option<usize>,
+    ) -> Vec<StreamEntry> {
+        if start > end {
+            return vec![];
+        }
+        let range = self.entries.range(start..=end);
+        let entries: Box<dyn Iterator<Item = _>> = if rev {
+            Box::new(range.rev())
+        } else {
+            Box::new(range)
+        };
+        entries
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, fields.to_vec()))
+            .collect()
+    }
+
+    /// Whether entries were deleted from `start` on, which stops counting entries by id.
+    fn has_deletions_from(&self, start: StreamId) -> bool {
+        !self.entries.is_empty()
+            && self.max_deleted_id != StreamId::MIN
+            && self.max_deleted_id >= start
+    }
+
+    /// How many entries were ever added up to `id` included, when that can still be told.
+    pub fn entries_added_until(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        if self.entries.is_empty() && id <= self.last_id {
+            return Some(self.entries_added);
+        }
+        if id >= self.last_id {
+            return (id == self.last_id).then_some(self.entries_added);
+        }
+        // without deletions, the entries still there are the last ones ever added
+        let first_id = self.first_id();
+        if self.max_deleted_id == StreamId::MIN || self.max_deleted_id < first_id {
+            let evicted = self.entries_added - self.entries.len() as u64;
+            if id < first_id {
+                return Some(evicted);
+            }
+            if id == first_id {
+                return Some(evicted + 1);
+            }
+        }
+        None
+    }
+
+    /// How many entries `group` has yet to read, when that can be told.
+    pub fn lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        let entries_read = match group.entries_read {
+            Some(entries_read) if !self.has_deletions_from(group.last_delivered) => entries_read,
+            _ => self.entries_added_until(group.last_delivered)?,
+        };
+        Some(self.entries_added.saturating_sub(entries_read))
+    }
+
+    /// Delivers the entries after the last one `group` got to `consumer`, which then has them
+    /// pending unless `noack`. The group must exist.
+    pub fn read_group(
+        &mut self,
+        group: &[u8],
+        consumer: &[u8],
+        count: Option<usize>,
+        noack: bool,
+        now: u64,
+    ) -> Vec<StreamEntry> {
+        let Some(last_delivered) = self.groups.get(group).map(|group| group.last_delivered) else {
+            return vec![];
+        };
+        let entries = match last_delivered.next() {
+            Some(start) => self.range(start, StreamId::MAX, false, count),
+            None => vec![],
+        };
+
+        let mut entries_read = self.groups[group].entries_read;
+        for (id, _) in entries.iter() {
+            entries_read = match entries_read {
+                Some(entries_read) if !self.has_deletions_from(*id) => Some(entries_read + 1),
+                _ => self.entries_added_until(*id),
+            };
+        }
+
+        let group = self.groups.get_mut(group).unwrap();
+        let Some((last, _)) = entries.last() else {
+            return entries;
+        };
+        group.last_delivered = *last;
+        group.entries_read = entries_read;
+        if let Some(consumer) = group.consumers.get_mut(consumer) {
+            consumer.active_at = Some(now);
+        }
+        if !noack {
+            for (id, _) in entries.iter() {
+                group.pending.insert(
+                    *id,
+                    PendingEntry {
+                        consumer: consumer.to_vec(),
+                        delivered_at: now,
+                        deliveries: 1,
+                    },
+                );
+            }
+        }
+        entries
+    }
+
+    /// The entries pending for `consumer` after `after`, with `None` for the ones deleted from
+    /// the stream since.
+    pub fn consumer_history(
+        &self,
+        group: &ConsumerGroup,
+        consumer: &[u8],
+        after: StreamId,
+        count: Option<usize>,
+    ) -> Vec<(StreamId, Option<StreamFields>)> {
+        group
+            .pending_of(consumer)
+            .filter(|(id, _)| **id > after)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, _)| (*id, self.entries.get(id).cloned()))
+            .collect()
+    }
+
+    /// Hands the pending entry `id` of `group` to `consumer`, when it was idle for long
+    /// enough. The group must exist.
+    pub fn claim(
+        &mut self,
+        group: &[u8],
+        consumer: &[u8],
+        id: StreamId,
+        options: &ClaimOptions,
+        now: u64,
+    ) -> Claim {
+        let fields = self.entries.get(&id).cloned();
+        let Some(group) = self.groups.get_mut(group) else {
+            return Claim::Skipped;
+        };
+        let forced = !group.pending.contains_key(&id) && options.force && fields.is_some();
+        if forced {
+            group.pending.insert(
+                id,
+                PendingEntry {
+                    consumer: consumer.to_vec(),
+                    delivered_at: now,
+                    deliveries: 1,
+                },
+            );
+        }
+        let Some(pending) = group.pending.get_mut(&id) else {
+            return Claim::Skipped;
+        };
+        let Some(fields) = fields else {
+            group.pending.remove(&id);
+            return Claim::Deleted;
+        };
+        if !forced && now.saturating_sub(pending.delivered_at) < options.min_idle {
+            return Claim::Skipped;
+        }
+
+        pending.consumer = consumer.to_vec();
+        pending.delivered_at = options.delivered_at.unwrap_or(now);
+        match options.retry_count {
+            Some(retry_count) => pending.deliveries = retry_count,
+            None if !options.just_id => pending.deliveries += 1,
+            None => {}
+        }
+        let claimed = pending.clone();
+        if let Some(consumer) = group.consumers.get_mut(consumer) {
+            consumer.active_at = Some(now);
+        }
+        Claim::Claimed(fields, claimed)
+    }
+}
+
+/// The stream a key holds, `WRONGTYPE` when it holds something else.
+pub fn as_stream(value: Option<&Value>) -> io::Result<Option<&Stream>> {
+    match value {
+        Some(Value::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_stream`, for changing the stream in place.
+pub fn as_stream_mut(value: &mut Option<Value>) -> io::Result<Option<&mut Stream>> {
+    match value {
+        Some(Value::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+    }
+}
+
+/// Like `as_stream_mut`, creating an empty stream when the key is missing.
+pub fn as_stream_or_create(value: &mut Option<Value>) -> io::Result<&mut Stream> {
+    let value = value.get_or_insert_with(|| Value::Stream(Stream::default()));
+    match value {
+        Value::Stream(stream) => Ok(stream),
+        _ => Err(wrong_type()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    fn stream(ids: &[(u64, u64)]) -> Stream {
+        let mut stream = Stream::default();
+        for (ms, seq) in ids {
+            stream.add(id(*ms, *seq), vec![(b"f".to_vec(), b"v".to_vec())]);
+        }
+        stream
+    }
+
+    fn ids(entries: Vec<StreamEntry>) -> Vec<StreamId> {
+        entries.into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn parses_ids() {
+        assert_eq!(StreamId::parse(b"5-3", 0).unwrap(), id(5, 3));
+        assert_eq!(StreamId::parse(b"5", 7).unwrap(), id(5, 7));
+        assert_eq!(
+            StreamId::parse(b"18446744073709551615-18446744073709551615", 0).unwrap(),
+            StreamId::MAX
+        );
+        for invalid in [
+            &b""[..],
+            b"abc",
+            b"5-",
+            b"-5",
+            b"5-3-1",
+            b"5--3",
+            b" 5",
+            b"18446744073709551616",
+            b"1-18446744073709551616",
+        ] {
+            assert!(StreamId::parse(invalid, 0).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_range_bounds() {
+        assert_eq!(StreamId::parse_bound(b"-", false).unwrap(), StreamId::MIN);
+        assert_eq!(StreamId::parse_bound(b"+", true).unwrap(), StreamId::MAX);
+        assert_eq!(StreamId::parse_bound(b"5", false).unwrap(), id(5, 0));
+        assert_eq!(StreamId::parse_bound(b"5", true).unwrap(), id(5, u64::MAX));
+        assert_eq!(StreamId::parse_bound(b"(5-3", false).unwrap(), id(5, 4));
+        assert_eq!(StreamId::parse_bound(b"(5-3", true).unwrap(), id(5, 2));
+        assert_eq!(StreamId::parse_bound(b"(5", false).unwrap(), id(5, 1));
+        assert_eq!(
+            StreamId::parse_bound(b"(5-0", true).unwrap(),
+            id(4, u64::MAX)
+        );
+
+        let err = StreamId::parse_bound(b"(0-0", true).unwrap_err();
+        assert_eq!(err.to_string(), "ERR invalid end ID for the interval");
+        let max = b"(18446744073709551615-18446744073709551615";
+        let err = StreamId::parse_bound(max, false).unwrap_err();
+        assert_eq!(err.to_string(), "ERR invalid start ID for the interval");
+        assert!(StreamId::parse_bound(b"(-", false).is_err());
+    }
+
+    #[test]
+    fn steps_across_milliseconds() {
+        assert_eq!(id(1, u64::MAX).next(), Some(id(2, 0)));
+        assert_eq!(id(2, 0).prev(), Some(id(1, u64::MAX)));
+        assert_eq!(StreamId::MAX.next(), None);
+        assert_eq!(StreamId::MIN.prev(), None);
+    }
+
+    #[test]
+    fn round_trips_big_endian_ids() {
+        let bytes = id(1_700_000_000_000, 42).to_be_bytes();
+        assert_eq!(
+            StreamId::from_be_bytes(&bytes),
+            Some(id(1_700_000_000_000, 42))
+        );
+        assert!(bytes[..] < id(1_700_000_000_001, 0).to_be_bytes()[..]);
+        assert_eq!(StreamId::from_be_bytes(&bytes[1..]), None);
+    }
+
+    #[test]
+    fn picks_ids_above_the_last_one() {
+        let mut stream = stream(&[(5, 3)]);
+        let next =
+            |stream: &Stream, arg: &[u8], now| stream.next_id(NewId::parse(arg).unwrap(), now);
+
+        assert_eq!(next(&stream, b"*", 10).unwrap(), id(10, 0));
+        assert_eq!(next(&stream, b"*", 1).unwrap(), id(5, 4));
+        assert_eq!(next(&stream, b"5-*", 0).unwrap(), id(5, 4));
+        assert_eq!(next(&stream, b"6-*", 0).unwrap(), id(6, 0));
+        assert_eq!(next(&stream, b"6", 0).unwrap(), id(6, 0));
+        assert!(next(&stream, b"5-3", 0).is_err());
+        assert!(next(&stream, b"4-*", 0).is_err());
+        assert!(NewId::parse(b"-*").is_err());
+        assert!(NewId::parse(b"1-2-*").is_err());
+
+        let err = Stream::default().next_id(NewId::parse(b"0-0").unwrap(), 0);
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "ERR The ID specified in XADD must be greater than 0-0"
+        );
+
+        stream.add(StreamId::MAX, vec![]);
+        assert!(next(&stream, b"*", u64::MAX).is_err());
+        assert!(next(&stream, b"18446744073709551615-*", 0).is_err());
+    }
+
+    #[test]
+    fn ranges_over_ids() {
+        let stream = stream(&[(1, 0), (1, 1), (2, 0), (3, 5)]);
+        let bound = |arg: &[u8], end| StreamId::parse_bound(arg, end).unwrap();
+
+        let all = ids(stream.range(bound(b"-", false), bound(b"+", true), false, None));
+        assert_eq!(all, [id(1, 0), id(1, 1), id(2, 0), id(3, 5)]);
+        let ms = ids(stream.range(bound(b"1", false), bound(b"1", true), false, None));
+        assert_eq!(ms, [id(1, 0), id(1, 1)]);
+        let exclusive = ids(stream.range(bound(b"(1-0", false), bound(b"(3-5", true), false, None));
+        assert_eq!(exclusive, [id(1, 1), id(2, 0)]);
+        let rev = ids(stream.range(bound(b"-", false), bound(b"+", true), true, Some(2)));
+        assert_eq!(rev, [id(3, 5), id(2, 0)]);
+        assert!(stream.range(id(3, 0), id(2, 0), false, None).is_empty());
+        assert!(stream
+            .range(id(4, 0), StreamId::MAX, false, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn trims_the_oldest_entries() {
+        let mut trimmed = stream(&[(1, 0), (2, 0), (3, 0), (4, 0)]);
+        let max_len = |len, limit| Trim {
+            strategy: TrimStrategy::MaxLen(len),
+            limit,
+        };
+        assert_eq!(trimmed.trim(&max_len(1, Some(2))), 2);
+        assert_eq!(trimmed.first_id(), id(3, 0));
+        assert_eq!(trimmed.trim(&max_len(1, None)), 1);
+        assert_eq!(trimmed.trim(&max_len(1, None)), 0);
+        assert_eq!(
+            ids(trimmed.range(StreamId::MIN, StreamId::MAX, false, None)),
+            [id(4, 0)]
+        );
+        assert_eq!(trimmed.entries_added, 4);
+        assert_eq!(trimmed.last_id, id(4, 0));
+
+        let mut trimmed = stream(&[(1, 0), (2, 0), (3, 0)]);
+        let min_id = Trim {
+            strategy: TrimStrategy::MinId(id(2, 0)),
+            limit: None,
+        };
+        assert_eq!(trimmed.trim(&min_id), 1);
+        assert_eq!(trimmed.first_id(), id(2, 0));
+    }
+
+    #[test]
+    fn counts_entries_read_and_lag() {
+        let mut stream = stream(&[(1, 0), (2, 0), (3, 0)]);
+        stream
+            .groups
+            .insert(b"g".to_vec(), ConsumerGroup::new(StreamId::MIN, Some(0)));
+        assert_eq!(stream.lag(&stream.groups[&b"g"[..]]), Some(3));
+
+        let read = stream.read_group(b"g", b"c", Some(2), false, 100);
+        assert_eq!(ids(read), [id(1, 0), id(2, 0)]);
+        let group = &stream.groups[&b"g"[..]];
+        assert_eq!(group.last_delivered, id(2, 0));
+        assert_eq!(group.entries_read, Some(2));
+        assert_eq!(group.pending.len(), 2);
+        assert_eq!(stream.lag(group), Some(1));
+
+        // past a deletion only the ids of the first and last entries tell what was added
+        stream.entries.remove(&id(2, 0));
+        stream.max_deleted_id = id(2, 0);
+        assert_eq!(stream.entries_added_until(id(1, 0)), None);
+        assert_eq!(stream.entries_added_until(id(3, 0)), Some(3));
+        assert_eq!(stream.entries_added_until(id(4, 0)), None);
+        let read = stream.read_group(b"g", b"c", None, true, 100);
+        assert_eq!(ids(read), [id(3, 0)]);
+        let group = &stream.groups[&b"g"[..]];
+        assert_eq!(group.entries_read, Some(3));
+        assert_eq!(group.pending.len(), 2, "NOACK leaves nothing pending");
+        assert_eq!(stream.lag(group), Some(0));
+    }
+
+    #[test]
+    fn claims_idle_entries() {
+        let mut stream = stream(&[(1, 0), (2, 0)]);
+        stream
+            .groups
+            .insert(b"g".to_vec(), ConsumerGroup::new(StreamId::MIN, Some(0)));
+        stream.read_group(b"g", b"alice", None, false, 100);
+        let idle = ClaimOptions {
+            min_idle: 50,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            stream.claim(b"g", b"bob", id(1, 0), &idle, 120),
+            Claim::Skipped
+        ));
+        match stream.claim(b"g", b"bob", id(1, 0), &idle, 200) {
+            Claim::Claimed(_, pending) => {
+                assert_eq!(pending.consumer, b"bob");
+                assert_eq!(pending.deliveries, 2);
+                assert_eq!(pending.delivered_at, 200);
+            }
+            claim => panic!("{:?}", claim),
+        }
+
+        stream.entries.remove(&id(2, 0));
+        assert!(matches!(
+            stream.claim(b"g", b"bob", id(2, 0), &idle, 200),
+            Claim::Deleted
+        ));
+        let group = &stream.groups[&b"g"[..]];
+        assert_eq!(
+            group.pending.keys().copied().collect::<Vec<_>>(),
+            [id(1, 0)]
+        );
+        assert!(matches!(
+            stream.claim(b"g", b"bob", id(3, 0), &idle, 200),
+            Claim::Skipped
+        ));
+    }
+}
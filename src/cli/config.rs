@@ -1,14 +1,34 @@
+use std::{path::PathBuf, sync::Arc, time::Instant};
+
+use tokio::{sync::RwLock, task::AbortHandle};
+
 use super::core::Roles;
 
 pub trait ToConfigString {
     fn to_config_string(&self) -> String;
 }
 
+/// Replication state that changes while the server runs, shared by every connection.
 #[derive(Debug, Clone)]
 pub struct ReplicationConfig {
     pub role: Roles,
-    pub master_repl_id: Option<String>,
-    pub master_repl_offset: Option<i32>,
+    /// id of the replication stream, a replica takes it from its master on sync
+    pub master_repl_id: String,
+    /// bytes of the replication stream produced so far, or applied so far on a replica
+    pub master_repl_offset: u64,
+    pub master_link: MasterLinkState,
+    /// whether a replica refuses writes from its own clients
+    pub replica_read_only: bool,
+}
+
+/// How a replica's link to its master is doing.
+#[derive(Debug, Clone, Default)]
+pub struct MasterLinkState {
+    pub up: bool,
+    pub sync_in_progress: bool,
+    pub last_io: Option<Instant>,
+    /// the task supervising the link, aborted when the server stops replicating this master
+    pub task: Option<AbortHandle>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,16 +36,60 @@ pub struct ServerConfig {
     pub port: u16,
 }
 
+/// When the append only file is flushed to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FsyncPolicy {
+    /// after every write, slowest but loses nothing
+    Always,
+    /// once per second, losing at most a second of writes
+    #[value(name = "everysec")]
+    EverySec,
+    /// whenever the operating system decides to
+    No,
+}
+
+#[derive(Debug, Clone)]
+pub struct PersistenceConfig {
+    pub dir: String,
+    pub dbfilename: String,
+    pub appendonly: bool,
+    pub appendfilename: String,
+    pub appendfsync: FsyncPolicy,
+}
+
+impl PersistenceConfig {
+    pub fn rdb_path(&self) -> PathBuf {
+        PathBuf::from(&self.dir).join(&self.dbfilename)
+    }
+
+    pub fn aof_path(&self) -> PathBuf {
+        PathBuf::from(&self.dir).join(&self.appendfilename)
+    }
+}
+
 impl ReplicationConfig {
     fn convert_role_to_string(&self) -> String {
-        match &self.role {
-            Roles::Master(id, offset) => format!(
-                "role:master\nmaster_replid:{}\nmaster_repl_offset:{}",
-                id.clone(),
-                offset
-            ),
-            Roles::Slave(_) => "role:slave".to_string(),
-        }
+        let role = match &self.role {
+            Roles::Master => "role:master".to_string(),
+            Roles::Slave(addr) => {
+                let mut addr = addr.split_whitespace();
+                let link = &self.master_link;
+                format!(
+                    "role:slave\nmaster_host:{}\nmaster_port:{}\nmaster_link_status:{}\nmaster_last_io_seconds_ago:{}\nmaster_sync_in_progress:{}",
+                    addr.next().unwrap_or(""),
+                    addr.next().unwrap_or(""),
+                    if link.up { "up" } else { "down" },
+                    link.last_io
+                        .map(|last_io| last_io.elapsed().as_secs() as i64)
+                        .unwrap_or(-1),
+                    link.sync_in_progress as u8,
+                )
+            }
+        };
+        format!(
+            "{}\nmaster_replid:{}\nmaster_repl_offset:{}",
+            role, self.master_repl_id, self.master_repl_offset
+        )
     }
 }
 
@@ -38,5 +102,6 @@ impl ToConfigString for ReplicationConfig {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub server_config: ServerConfig,
-    pub replication_config: ReplicationConfig,
+    pub replication_config: Arc<RwLock<ReplicationConfig>>,
+    pub persistence_config: PersistenceConfig,
 }
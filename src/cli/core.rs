@@ -1,6 +1,8 @@
 use clap::Parser;
 use rand::{distributions::Alphanumeric, Rng};
 
+use super::config::FsyncPolicy;
+
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about=None)]
 pub struct BaseCliArgs {
@@ -8,11 +10,23 @@ pub struct BaseCliArgs {
     port: Option<u16>,
     #[arg(short, long)]
     replicaof: Option<String>,
+    #[arg(long, default_value = ".")]
+    dir: String,
+    #[arg(long, default_value = "dump.rdb")]
+    dbfilename: String,
+    #[arg(long, default_value = "no", value_parser = ["yes", "no"])]
+    appendonly: String,
+    #[arg(long, default_value = "appendonly.aof")]
+    appendfilename: String,
+    #[arg(long, value_enum, default_value_t = FsyncPolicy::EverySec)]
+    appendfsync: FsyncPolicy,
+    #[arg(long, default_value = "yes", value_parser = ["yes", "no"])]
+    replica_read_only: String,
 }
 
 #[derive(Debug, Clone)]
 pub enum Roles {
-    Master(String, u8),
+    Master,
     Slave(String),
 }
 
@@ -25,18 +39,47 @@ impl BaseCliArgs {
         default_port
     }
 
+    pub fn get_dir(&self) -> String {
+        self.dir.to_string()
+    }
+
+    pub fn get_dbfilename(&self) -> String {
+        self.dbfilename.to_string()
+    }
+
+    pub fn is_appendonly(&self) -> bool {
+        self.appendonly == "yes"
+    }
+
+    pub fn get_appendfilename(&self) -> String {
+        self.appendfilename.to_string()
+    }
+
+    pub fn get_appendfsync(&self) -> FsyncPolicy {
+        self.appendfsync
+    }
+
+    pub fn is_replica_read_only(&self) -> bool {
+        self.replica_read_only == "yes"
+    }
+
     pub fn get_role(&self) -> Roles {
         if let Some(addr) = self.replicaof.as_ref() {
             return Roles::Slave(addr.to_string());
         }
-        Roles::Master(self.generate_master_id(), 0)
+        Roles::Master
     }
 
     pub fn generate_master_id(&self) -> String {
-        rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(40)
-            .map(char::from)
-            .collect()
+        generate_repl_id()
     }
 }
+
+/// A fresh replication id, for a server that starts a new history as a master.
+pub fn generate_repl_id() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect()
+}
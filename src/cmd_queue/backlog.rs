@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+/// How much of the replication stream is kept around for replicas that reconnect, the
+/// same default as redis.
+pub const REPL_BACKLOG_SIZE: usize = 1024 * 1024;
+
+/// A fixed size window over the tail of the replication stream.
+///
+/// `offset` counts every byte ever written to the stream, so a replica that knows how far it
+/// got can pick up from there, as long as those bytes haven't been overwritten yet.
+#[derive(Debug)]
+pub struct Backlog {
+    buf: VecDeque<u8>,
+    capacity: usize,
+    offset: u64,
+}
+
+impl Default for Backlog {
+    fn default() -> Self {
+        Backlog::new(REPL_BACKLOG_SIZE)
+    }
+}
+
+impl Backlog {
+    pub fn new(capacity: usize) -> Self {
+        Backlog {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+            offset: 0,
+        }
+    }
+
+    /// Offset of the last byte written.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Offset right before the oldest byte still held.
+    pub fn start_offset(&self) -> u64 {
+        self.offset - self.buf.len() as u64
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend(bytes);
+        self.offset += bytes.len() as u64;
+
+        let overflow = self.buf.len().saturating_sub(self.capacity);
+        self.buf.drain(..overflow);
+    }
+
+    /// Drops everything held and continues from `offset`, for a replica that starts over
+    /// from its master's snapshot.
+    pub fn reset(&mut self, offset: u64) {
+        self.buf.clear();
+        self.offset = offset;
+    }
+
+    /// Returns everything written after `offset`, or `None` when some of it is no longer held.
+    pub fn read_from(&self, offset: u64) -> Option<Vec<u8>> {
+        if offset < self.start_offset() || offset > self.offset {
+            return None;
+        }
+        let skip = (offset - self.start_offset()) as usize;
+        Some(self.buf.iter().skip(skip).copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_what_came_after_an_offset() {
+        let mut backlog = Backlog::new(8);
+        backlog.push(b"abc");
+        backlog.push(b"de");
+
+        assert_eq!(backlog.read_from(0), Some(b"abcde".to_vec()));
+        assert_eq!(backlog.read_from(3), Some(b"de".to_vec()));
+        // a replica that has everything gets nothing, one that claims more is refused
+        assert_eq!(backlog.read_from(5), Some(vec![]));
+        assert_eq!(backlog.read_from(6), None);
+    }
+
+    #[test]
+    fn forgets_what_slid_out_of_the_window() {
+        let mut backlog = Backlog::new(8);
+        backlog.push(b"0123456789");
+        backlog.push(b"ab");
+
+        assert_eq!(backlog.offset(), 12);
+        assert_eq!(backlog.start_offset(), 4);
+        assert_eq!(backlog.read_from(4), Some(b"456789ab".to_vec()));
+        assert_eq!(backlog.read_from(3), None);
+        assert_eq!(backlog.read_from(11), Some(b"b".to_vec()));
+    }
+
+    #[test]
+    fn wraps_around_past_its_size() {
+        let mut backlog = Backlog::default();
+        let chunk: Vec<u8> = (0..=255).collect();
+        let chunks = REPL_BACKLOG_SIZE / chunk.len() + 3;
+        for _ in 0..chunks {
+            backlog.push(&chunk);
+        }
+
+        let written = (chunks * chunk.len()) as u64;
+        assert_eq!(backlog.offset(), written);
+        assert_eq!(backlog.start_offset(), written - REPL_BACKLOG_SIZE as u64);
+        let held = backlog.read_from(backlog.start_offset()).unwrap();
+        assert_eq!(held.len(), REPL_BACKLOG_SIZE);
+        assert_eq!(held[..256], chunk[..]);
+        assert_eq!(backlog.read_from(backlog.start_offset() - 1), None);
+    }
+
+    #[test]
+    fn reset_continues_from_another_offset() {
+        let mut backlog = Backlog::new(8);
+        backlog.push(b"abc");
+        backlog.reset(100);
+
+        assert_eq!(backlog.read_from(3), None);
+        assert_eq!(backlog.read_from(100), Some(vec![]));
+        backlog.push(b"xy");
+        assert_eq!(backlog.read_from(101), Some(b"y".to_vec()));
+    }
+}
@@ -1,72 +1,207 @@
-use std::{collections::VecDeque, sync::Arc, time::Duration};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Weak,
+};
 
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex, RwLock};
 
-use super::node::Node;
+use crate::{
+    aof::core::Aof,
+    cache::blocking::encode_command,
+    cli::{config::ReplicationConfig, core::Roles},
+    resp::core::RESPDatatypes,
+};
 
-#[derive(Debug, Default)]
+use super::backlog::Backlog;
+
+/// Everything written to the keyspace goes through here on its way to the append only file
+/// and the replicas.
+#[derive(Debug)]
 pub struct CmdQueue {
-    pub queue: Arc<Mutex<VecDeque<Node>>>,
+    pub backlog: Backlog,
+    pub aof: Option<Aof>,
+    replication_config: Arc<RwLock<ReplicationConfig>>,
+    offset_tx: watch::Sender<u64>,
+    // acknowledged offsets of the attached replicas, owned by their connections
+    replicas: Vec<Weak<AtomicU64>>,
+    ack_tx: watch::Sender<()>,
 }
 
 impl CmdQueue {
-    pub async fn add(&mut self, cmd: Vec<u8>) {
-        let mut queue = self.queue.lock().await;
-        queue.push_back(Node::new(cmd));
-    }
-
-    pub async fn get_all_cmds_after_id(
-        &self,
-        id: Option<String>,
-    ) -> Option<(String, Vec<Vec<u8>>)> {
-        let mut result = vec![];
-        let mut last_id = String::new();
-        {
-            let queue = self.queue.lock().await;
-            if queue.is_empty() {
-                return None;
-            }
-            if let Some(cmd_id) = id.as_ref() {
-                if let Some(end) = queue.back() {
-                    if end.id == *cmd_id {
-                        return Some((cmd_id.to_string(), result));
-                    }
-                }
-                let mut flag = true;
-
-                for element in queue.iter() {
-                    if flag && element.id == *cmd_id {
-                        flag = false;
-                        continue;
-                    } else if flag {
-                        continue;
-                    }
-                    result.push(element.cmd.to_vec());
-                    last_id = element.id.to_string();
-                }
-            } else {
-                for element in queue.iter() {
-                    result.push(element.cmd.to_vec());
-                    last_id = element.id.to_string();
-                }
-            }
+    pub fn new(replication_config: Arc<RwLock<ReplicationConfig>>) -> Self {
+        CmdQueue {
+            backlog: Backlog::default(),
+            aof: None,
+            replication_config,
+            offset_tx: watch::Sender::new(0),
+            replicas: Vec::new(),
+            ack_tx: watch::Sender::new(()),
+        }
+    }
+
+    /// Persists and replicates a write, returning the replication offset right after it.
+    pub async fn add(&mut self, cmd: Vec<u8>) -> u64 {
+        self.append_to_aof(&cmd);
+
+        // a replica's backlog holds nothing but its master's stream, writes its own clients
+        // make are kept local like in redis
+        if let Roles::Slave(_) = self.replication_config.read().await.role {
+            return self.backlog.offset();
         }
-        Some((last_id, result))
+        self.feed(&cmd).await
+    }
+
+    /// Persists a write a replica received from its master and passes it on to the
+    /// sub-replicas exactly as it arrived in `raw`.
+    pub async fn forward(&mut self, cmd: &[u8], raw: &[u8]) -> u64 {
+        self.append_to_aof(cmd);
+        self.feed(raw).await
     }
 
-    pub async fn remove_all_expired_node(&mut self) {
-        loop {
-            tokio::time::sleep(Duration::from_millis(2)).await;
-            {
-                let mut queue = self.queue.lock().await;
-                while let Some(front) = queue.front() {
-                    if !front.has_expired() {
-                        break;
-                    }
+    /// Sends `REPLCONF GETACK *` down the replication stream, it isn't a write so it is
+    /// left out of the append only file.
+    pub async fn request_acks(&mut self) {
+        let getack = RESPDatatypes::Array(vec![
+            RESPDatatypes::BulkString("REPLCONF".to_string()),
+            RESPDatatypes::BulkString("GETACK".to_string()),
+            RESPDatatypes::BulkString("*".to_string()),
+        ])
+        .encode();
+        self.feed(&getack).await;
+    }
 
-                    queue.pop_front();
-                }
+    fn append_to_aof(&mut self, cmd: &[u8]) {
+        if let Some(aof) = self.aof.as_mut() {
+            if let Err(err) = aof.append(cmd) {
+                println!("unable to write to the append only file: {}", err);
             }
         }
     }
+
+    /// Appends to the replication stream without persisting anything.
+    pub async fn feed(&mut self, bytes: &[u8]) -> u64 {
+        self.backlog.push(bytes);
+        self.replication_config.write().await.master_repl_offset = self.backlog.offset();
+        self.offset_tx.send_replace(self.backlog.offset());
+        self.backlog.offset()
+    }
+
+    /// Restarts the replication stream at `offset`, after a replica loaded its master's
+    /// snapshot. Sub-replicas can't continue from anything held before.
+    pub async fn reset_backlog(&mut self, offset: u64) {
+        self.backlog.reset(offset);
+        self.replication_config.write().await.master_repl_offset = offset;
+        self.offset_tx.send_replace(offset);
+    }
+
+    /// Notifies the receiver every time the backlog grows. Subscribing while holding the
+    /// queue means no write made after what was read from the backlog can be missed.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.offset_tx.subscribe()
+    }
+
+    /// Tracks the offset a replica acknowledges for `WAIT`. It is dropped from the count
+    /// once its connection lets go of `ack_offset`.
+    pub fn register_replica(&mut self, ack_offset: &Arc<AtomicU64>) {
+        self.replicas.push(Arc::downgrade(ack_offset));
+    }
+
+    /// Called after a replica acknowledged a new offset.
+    pub fn acknowledge(&self) {
+        self.ack_tx.send_replace(());
+    }
+
+    /// Notifies the receiver every time a replica acknowledges an offset.
+    pub fn subscribe_acks(&self) -> watch::Receiver<()> {
+        self.ack_tx.subscribe()
+    }
+
+    /// Counts the attached replicas that acknowledged at least `offset`.
+    pub fn count_acked(&mut self, offset: u64) -> usize {
+        self.replicas.retain(|replica| replica.strong_count() > 0);
+        self.replicas
+            .iter()
+            .filter_map(|replica| replica.upgrade())
+            .filter(|ack_offset| ack_offset.load(Ordering::Acquire) >= offset)
+            .count()
+    }
+}
+
+/// How write commands hand themselves to the [`CmdQueue`]. It remembers the offset of the
+/// connection's last write, which is what `WAIT` waits for.
+#[derive(Debug, Clone)]
+pub struct Propagator {
+    cmdq: Arc<Mutex<CmdQueue>>,
+    write_offset: Arc<AtomicU64>,
+    // the frame being applied from the master, when the write comes through a master link
+    upstream_frame: Option<Vec<u8>>,
+    // writes of the transaction being executed, propagated together once it ran
+    transaction: Option<Arc<Mutex<Vec<u8>>>>,
+}
+
+impl Propagator {
+    pub fn new(
+        cmdq: Arc<Mutex<CmdQueue>>,
+        write_offset: Arc<AtomicU64>,
+        upstream_frame: Option<Vec<u8>>,
+    ) -> Self {
+        Propagator {
+            cmdq,
+            write_offset,
+            upstream_frame,
+            transaction: None,
+        }
+    }
+
+    /// A propagator collecting the writes of a transaction instead of propagating them one by
+    /// one, [`Propagator::commit`] sends them on wrapped in `MULTI` and `EXEC`.
+    pub fn transaction(&self) -> Self {
+        Propagator {
+            transaction: Some(Arc::new(Mutex::new(Vec::new()))),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the writes are collected for a transaction. Commands run by `EXEC` never block.
+    pub fn is_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    /// Whether the write being propagated was applied from this server's master.
+    pub fn is_upstream(&self) -> bool {
+        self.upstream_frame.is_some()
+    }
+
+    pub async fn propagate(&self, cmd: Vec<u8>) {
+        match self.transaction.as_ref() {
+            Some(writes) => writes.lock().await.extend_from_slice(&cmd),
+            None => self.send(cmd).await,
+        }
+    }
+
+    /// Propagates the writes collected for a transaction as one block, so the append only
+    /// file and the replicas apply all of them or none. Nothing is sent when it wrote nothing.
+    pub async fn commit(&self) {
+        let Some(writes) = self.transaction.as_ref() else {
+            return;
+        };
+        let writes = std::mem::take(&mut *writes.lock().await);
+        if writes.is_empty() {
+            return;
+        }
+
+        let mut cmd = encode_command(&["MULTI"]);
+        cmd.extend_from_slice(&writes);
+        cmd.extend_from_slice(&encode_command(&["EXEC"]));
+        self.send(cmd).await;
+    }
+
+    async fn send(&self, cmd: Vec<u8>) {
+        let mut cmdq = self.cmdq.lock().await;
+        let offset = match self.upstream_frame.as_ref() {
+            Some(raw) => cmdq.forward(&cmd, raw).await,
+            None => cmdq.add(cmd).await,
+        };
+        self.write_offset.store(offset, Ordering::Release);
+    }
 }
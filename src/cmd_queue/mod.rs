@@ -1,2 +1,2 @@
+pub mod backlog;
 pub mod core;
-pub mod node;
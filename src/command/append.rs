@@ -0,0 +1,63 @@
+use std::io::{self, Error};
+
+use crate::{cache::core::MAX_STRING_SIZE, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `APPEND key value`
+#[derive(Debug, Default)]
+pub struct Append {
+    pub cmd: Vec<u8>,
+    pub args: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Command for Append {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["append"]) else {
+            return false;
+        };
+        if let [key, value] = args.as_slice() {
+            self.args = Some((key.to_vec(), value.to_vec()));
+        }
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some((key, suffix)) = self.args.clone() else {
+            return fail(wrong_arity("append"));
+        };
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let cache = cache_repo.lock().await;
+            let mut value = cache.get(key.to_string()).await?.unwrap_or_default();
+            if value.len() + suffix.len() > MAX_STRING_SIZE {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR string exceeds maximum allowed size (proto-max-bulk-len)",
+                ));
+            }
+            value.extend(suffix);
+            let len = value.len();
+            cache.set_keep_ttl(key, value).await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
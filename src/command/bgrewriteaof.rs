@@ -0,0 +1,84 @@
+use std::io::{self, Error};
+
+use crate::{
+    aof::core::rewrite,
+    rdb::core::snapshot,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::Command;
+
+#[derive(Default)]
+pub struct BgRewriteAof;
+
+impl Command for BgRewriteAof {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            if vec.len() != 1 {
+                return false;
+            }
+            let cmd = vec.first().unwrap();
+            match cmd {
+                RESPDatatypes::BufBulk(vec)
+                    if bytes_to_string(vec)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        == "bgrewriteaof" =>
+                {
+                    return true;
+                }
+                _ => {
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        let Some(conn) = conn else {
+            return Box::pin(async move {
+                Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR BGREWRITEAOF is not allowed in this context",
+                ))
+            });
+        };
+        let path = conn.server_config.persistence_config.aof_path();
+        let cmdq = conn.cmdq.clone();
+
+        Box::pin(async move {
+            let entries = {
+                // writes lock the keyspace before the queue, so holding both gives a copy
+                // that lines up exactly with where the rewrite buffer starts
+                let repo = cache_repo.lock().await;
+                let mut cmd_queue = cmdq.lock().await;
+                if let Some(aof) = cmd_queue.aof.as_mut() {
+                    if aof.is_rewriting() {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR Background append only file rewriting already in progress",
+                        ));
+                    }
+                    aof.start_rewrite();
+                }
+                snapshot(&repo).await
+            };
+
+            tokio::spawn(async move {
+                match rewrite(entries, cmdq, path).await {
+                    Ok(_) => println!("background append only file rewrite finished"),
+                    Err(err) => println!("background append only file rewrite failed: {}", err),
+                }
+            });
+
+            Ok(RESPDatatypes::SimpleString(
+                "Background append only file rewriting started".to_string(),
+            ))
+        })
+    }
+}
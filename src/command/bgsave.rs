@@ -0,0 +1,83 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::core::unix_time_secs,
+    rdb::core::{save_to_file, snapshot},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::Command;
+
+#[derive(Default)]
+pub struct BgSave;
+
+impl Command for BgSave {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            if vec.len() != 1 {
+                return false;
+            }
+            let cmd = vec.first().unwrap();
+            match cmd {
+                RESPDatatypes::BufBulk(vec)
+                    if bytes_to_string(vec)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        == "bgsave" =>
+                {
+                    return true;
+                }
+                _ => {
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        let Some(conn) = conn else {
+            return Box::pin(async move {
+                Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR BGSAVE is not allowed in this context",
+                ))
+            });
+        };
+        let path = conn.server_config.persistence_config.rdb_path();
+
+        Box::pin(async move {
+            let entries = {
+                let repo = cache_repo.lock().await;
+                let mut in_progress = repo.bgsave_in_progress.lock().await;
+                if *in_progress {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ERR Background save already in progress",
+                    ));
+                }
+                *in_progress = true;
+                snapshot(&repo).await
+            };
+
+            // the copy is written out without holding the keyspace lock
+            tokio::spawn(async move {
+                let res = save_to_file(&path, &entries).await;
+                let repo = cache_repo.lock().await;
+                match res {
+                    Ok(_) => *repo.last_save.write().await = unix_time_secs(),
+                    Err(err) => println!("background save failed: {}", err),
+                }
+                *repo.bgsave_in_progress.lock().await = false;
+            });
+
+            Ok(RESPDatatypes::SimpleString(
+                "Background saving started".to_string(),
+            ))
+        })
+    }
+}
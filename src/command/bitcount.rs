@@ -0,0 +1,63 @@
+use crate::{
+    cache::bitmap::{count_bits, RangeUnit},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `BITCOUNT key [start end [BYTE | BIT]]`
+#[derive(Debug, Default)]
+pub struct BitCount {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for BitCount {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["bitcount"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() {
+            return fail(wrong_arity("bitcount"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let range = match &self.args[1..] {
+                [] => None,
+                [start, end] => Some((parse_integer(start)?, parse_integer(end)?, RangeUnit::Byte)),
+                [start, end, unit] => Some((
+                    parse_integer(start)?,
+                    parse_integer(end)?,
+                    RangeUnit::parse(unit).ok_or_else(syntax_error)?,
+                )),
+                _ => return Err(syntax_error()),
+            };
+
+            let value = cache_repo.lock().await.get(key).await?.unwrap_or_default();
+            let (start, end, unit) = range.unwrap_or((0, -1, RangeUnit::Byte));
+            let count = match unit.bit_range(start, end, &value) {
+                Some((start, end)) => count_bits(&value, start, end),
+                None => 0,
+            };
+            Ok(RESPDatatypes::Integer(count as i64))
+        })
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::bitmap::{parse_bit_offset, BitFieldType, Overflow},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `BITFIELD key [GET type offset | [OVERFLOW WRAP | SAT | FAIL] SET type offset value |
+/// INCRBY type offset increment ...]` and `BITFIELD_RO`, which only takes `GET`s.
+#[derive(Debug, Default)]
+pub struct BitField {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+    /// registered apart from `BITFIELD`, as `BITFIELD_RO` never writes
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FieldOp {
+    Get,
+    Set(i64),
+    IncrBy(i64),
+}
+
+/// An operation on one field, with the overflow behaviour in effect when it was given.
+#[derive(Debug)]
+struct Operation {
+    op: FieldOp,
+    field: BitFieldType,
+    offset: u64,
+    overflow: Overflow,
+}
+
+fn parse_operations(args: &[Vec<u8>], read_only: bool) -> io::Result<Vec<Operation>> {
+    let mut operations = Vec::new();
+    let mut overflow = Overflow::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let subcommand = bytes_to_string(arg)
+            .unwrap_or("".to_string())
+            .to_lowercase();
+        if subcommand == "overflow" {
+            overflow = Overflow::parse(args.next().ok_or_else(syntax_error)?)?;
+            continue;
+        }
+        if !matches!(subcommand.as_str(), "get" | "set" | "incrby") {
+            return Err(syntax_error());
+        }
+        if read_only && subcommand != "get" {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR BITFIELD_RO only supports the GET subcommand",
+            ));
+        }
+
+        let (Some(field), Some(offset)) = (args.next(), args.next()) else {
+            return Err(syntax_error());
+        };
+        let field = BitFieldType::parse(field)?;
+        let offset = parse_bit_offset(offset, Some(&field))?;
+        let op = match subcommand.as_str() {
+            "get" => FieldOp::Get,
+            set_or_incr => {
+                let val = parse_integer(args.next().ok_or_else(syntax_error)?)?;
+                match set_or_incr {
+                    "set" => FieldOp::Set(val),
+                    _ => FieldOp::IncrBy(val),
+                }
+            }
+        };
+        operations.push(Operation {
+            op,
+            field,
+            offset,
+            overflow,
+        });
+    }
+    Ok(operations)
+}
+
+impl Operation {
+    /// Runs the operation on `value`, replying with the value read, the value replaced by a
+    /// `SET` or the value an `INCRBY` got to, and `Null` for a write that failed to fit.
+    fn apply(&self, value: &mut Vec<u8>) -> RESPDatatypes {
+        let Operation {
+            op,
+            field,
+            offset,
+            overflow,
+        } = *self;
+        let old = field.get(value, offset);
+        let new = match op {
+            FieldOp::Get => return RESPDatatypes::Integer(old),
+            // unsigned fields take the bits of the value, so negative ones overflow them
+            FieldOp::Set(val) if !field.signed => field.fit(val as u64 as i128, overflow),
+            FieldOp::Set(val) => field.fit(val as i128, overflow),
+            FieldOp::IncrBy(incr) => field.fit(old as i128 + incr as i128, overflow),
+        };
+        let Some(new) = new else {
+            return RESPDatatypes::Null;
+        };
+        field.set(value, offset, new);
+        match op {
+            FieldOp::Set(_) => RESPDatatypes::Integer(old),
+            _ => RESPDatatypes::Integer(new),
+        }
+    }
+}
+
+impl Command for BitField {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let name = if self.read_only {
+            "bitfield_ro"
+        } else {
+            "bitfield"
+        };
+        let Some((name, args)) = parse_args(cmd, &[name]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let read_only = self.name == "bitfield_ro";
+            let operations = parse_operations(&self.args[1..], read_only)?;
+            let writes = operations
+                .iter()
+                .any(|operation| !matches!(operation.op, FieldOp::Get));
+
+            let cache = cache_repo.lock().await;
+            let mut value = cache.get(key.to_string()).await?.unwrap_or_default();
+            let mut replies = Vec::with_capacity(operations.len());
+            for operation in operations.iter() {
+                // a write covers its field even when it fails to fit
+                if !matches!(operation.op, FieldOp::Get) {
+                    let end = (operation.offset + operation.field.bits as u64).div_ceil(8);
+                    if value.len() < end as usize {
+                        value.resize(end as usize, 0);
+                    }
+                }
+                replies.push(operation.apply(&mut value));
+            }
+            // only writes create the key
+            if !writes {
+                return Ok(RESPDatatypes::Array(replies));
+            }
+            cache.set_keep_ttl(key, value).await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Array(replies))
+        })
+    }
+}
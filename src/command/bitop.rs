@@ -0,0 +1,76 @@
+use std::io::{self, Error};
+
+use crate::{cache::bitmap::BitOp as Op, resp::core::RESPDatatypes};
+
+use super::core::{
+    fail, parse_args, parse_key, queued, syntax_error, wrong_arity, Command, RunResult,
+};
+
+/// `BITOP AND | OR | XOR | NOT destkey key [key ...]`, storing the result in `destkey` and
+/// deleting it when the result is empty.
+#[derive(Debug, Default)]
+pub struct BitOp {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for BitOp {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["bitop"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 3 {
+            return fail(wrong_arity("bitop"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let op = Op::parse(&self.args[0]).ok_or_else(syntax_error)?;
+            let destination = parse_key(&self.args[1])?;
+            let keys = &self.args[2..];
+            if op == Op::Not && keys.len() != 1 {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR BITOP NOT must be called with a single source key.",
+                ));
+            }
+
+            let cache = cache_repo.lock().await;
+            let mut values = Vec::with_capacity(keys.len());
+            for key in keys {
+                let key = parse_key(key)?;
+                values.push(cache.get(key).await?.unwrap_or_default());
+            }
+            let result = op.combine(values);
+            let len = result.len();
+            match len {
+                0 => {
+                    cache.remove(destination).await;
+                }
+                _ => cache.set(destination, result).await?,
+            }
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::bitmap::{find_bit, RangeUnit},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `BITPOS key bit [start [end [BYTE | BIT]]]`
+#[derive(Debug, Default)]
+pub struct BitPos {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for BitPos {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["bitpos"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("bitpos"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let bit = match parse_integer(&self.args[1])? {
+                0 => false,
+                1 => true,
+                _ => {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ERR The bit argument must be 1 or 0.",
+                    ));
+                }
+            };
+            let (start, end, unit) = match &self.args[2..] {
+                [] => (None, None, RangeUnit::Byte),
+                [start] => (Some(start), None, RangeUnit::Byte),
+                [start, end] => (Some(start), Some(end), RangeUnit::Byte),
+                [start, end, unit] => (
+                    Some(start),
+                    Some(end),
+                    RangeUnit::parse(unit).ok_or_else(syntax_error)?,
+                ),
+                _ => return Err(syntax_error()),
+            };
+            let start = start.map_or(Ok(0), |start| parse_integer(start))?;
+            let end_given = end.is_some();
+            let end = end.map_or(Ok(-1), |end| parse_integer(end))?;
+
+            let Some(value) = cache_repo.lock().await.get(key).await? else {
+                // a missing key is all zeros
+                return Ok(RESPDatatypes::Integer(if bit { -1 } else { 0 }));
+            };
+            let Some((start, end)) = unit.bit_range(start, end, &value) else {
+                return Ok(RESPDatatypes::Integer(-1));
+            };
+            let pos = match find_bit(&value, bit, start, end) {
+                Some(pos) => pos as i64,
+                // without an end, the zeros past the end of the string count
+                None if !bit && !end_given => end as i64 + 1,
+                None => -1,
+            };
+            Ok(RESPDatatypes::Integer(pos))
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    cache::blocking::{encode_command, wait_until_served, BlockedOp},
+    resp::core::RESPDatatypes,
+};
+
+use super::{
+    blpop::parse_timeout,
+    core::{fail, parse_args, parse_key, queued, wake_blocked, wrong_arity, Command, RunResult},
+    lmove::{move_element, parse_sides},
+};
+
+/// `BLMOVE source destination LEFT | RIGHT LEFT | RIGHT timeout`
+#[derive(Debug, Default)]
+pub struct BLMove {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for BLMove {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["blmove"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run<'a>(
+        &'a mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&'a mut crate::connections::connection::Connection>,
+    ) -> RunResult<'a> {
+        if self.args.len() != 5 {
+            return fail(wrong_arity("blmove"));
+        }
+        let conn = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            conn => conn,
+        };
+        let propagator = conn.as_ref().map(|conn| conn.propagator());
+
+        Box::pin(async move {
+            let source = parse_key(&self.args[0])?;
+            let destination = parse_key(&self.args[1])?;
+            let (from, to) = parse_sides(&self.args[2], &self.args[3])?;
+            let timeout = parse_timeout(&self.args[4])?;
+
+            let cache = cache_repo.lock().await;
+            let moved = move_element(
+                &cache,
+                source.to_string(),
+                destination.to_string(),
+                from,
+                to,
+            )
+            .await?;
+            if let Some(elem) = moved {
+                if let Some(propagator) = propagator.as_ref() {
+                    propagator
+                        .propagate(encode_command(&[
+                            "LMOVE",
+                            &source,
+                            &destination,
+                            from.name(),
+                            to.name(),
+                        ]))
+                        .await;
+                }
+                wake_blocked(&cache, destination, propagator.as_ref()).await;
+                return Ok(RESPDatatypes::BufBulk(elem));
+            }
+            // only a client waits, and not while EXEC runs its transaction
+            let waiting = propagator
+                .as_ref()
+                .is_some_and(|propagator| !propagator.is_transaction());
+            let Some(conn) = conn.filter(|_| waiting) else {
+                return Ok(RESPDatatypes::NullString);
+            };
+
+            let op = BlockedOp::Move {
+                from,
+                destination,
+                to,
+            };
+            let (id, rx) = cache.blocked.lock().await.block(vec![source], op);
+            drop(cache);
+
+            Ok(
+                match wait_until_served(cache_repo.clone(), id, rx, timeout, conn).await? {
+                    Some(served) => RESPDatatypes::BufBulk(served.elem),
+                    None => RESPDatatypes::NullArray,
+                },
+            )
+        })
+    }
+}
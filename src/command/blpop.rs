@@ -0,0 +1,129 @@
+use std::{
+    io::{self, Error},
+    time::Duration,
+};
+
+use crate::{
+    cache::{
+        blocking::{encode_command, wait_until_served, BlockedOp},
+        list::{as_list_mut, pop, Side},
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `BLPOP` and `BRPOP key [key ...] timeout`
+#[derive(Debug, Default)]
+pub struct BPop {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+/// Parses the timeout of a blocking command, in seconds. 0 blocks forever.
+pub fn parse_timeout(arg: &[u8]) -> io::Result<Option<Duration>> {
+    let timeout = bytes_to_string(arg)
+        .ok()
+        .and_then(|timeout| timeout.parse::<f64>().ok())
+        .filter(|timeout| timeout.is_finite())
+        .ok_or_else(|| {
+            Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR timeout is not a float or out of range",
+            )
+        })?;
+    if timeout < 0.0 {
+        return Err(Error::new(
+            io::ErrorKind::InvalidInput,
+            "ERR timeout is negative",
+        ));
+    }
+    Ok((timeout > 0.0).then(|| Duration::from_secs_f64(timeout)))
+}
+
+impl Command for BPop {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["blpop", "brpop"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run<'a>(
+        &'a mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&'a mut crate::connections::connection::Connection>,
+    ) -> RunResult<'a> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        // only a client can wait, inside a transaction it behaves like LPOP or RPOP
+        let conn = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            conn => conn,
+        };
+        let propagator = conn.as_ref().map(|conn| conn.propagator());
+
+        Box::pin(async move {
+            let (timeout, keys) = self.args.split_last().unwrap();
+            let timeout = parse_timeout(timeout)?;
+            let keys = keys
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<io::Result<Vec<_>>>()?;
+            let side = if self.name == "blpop" {
+                Side::Left
+            } else {
+                Side::Right
+            };
+
+            let cache = cache_repo.lock().await;
+            for key in keys.iter() {
+                let popped = cache
+                    .modify(key.to_string(), |value| {
+                        Ok::<_, io::Error>(as_list_mut(value)?.and_then(|list| pop(list, side)))
+                    })
+                    .await?;
+                if let Some(elem) = popped {
+                    // replicas apply the pop that happened, they never block
+                    if let Some(propagator) = propagator {
+                        propagator
+                            .propagate(encode_command(&[side.pop_command(), key]))
+                            .await;
+                    }
+                    return Ok(RESPDatatypes::Array(vec![
+                        RESPDatatypes::BulkString(key.to_string()),
+                        RESPDatatypes::BufBulk(elem),
+                    ]));
+                }
+            }
+            // only a client waits, and not while EXEC runs its transaction
+            let waiting = propagator
+                .as_ref()
+                .is_some_and(|propagator| !propagator.is_transaction());
+            let Some(conn) = conn.filter(|_| waiting) else {
+                return Ok(RESPDatatypes::NullArray);
+            };
+
+            // pushers serve blocked clients with the keyspace locked, so nothing can be
+            // pushed between the checks above and parking here
+            let (id, rx) = cache.blocked.lock().await.block(keys, BlockedOp::Pop(side));
+            drop(cache);
+
+            Ok(
+                match wait_until_served(cache_repo.clone(), id, rx, timeout, conn).await? {
+                    Some(served) => RESPDatatypes::Array(vec![
+                        RESPDatatypes::BulkString(served.key),
+                        RESPDatatypes::BufBulk(served.elem),
+                    ]),
+                    None => RESPDatatypes::NullArray,
+                },
+            )
+        })
+    }
+}
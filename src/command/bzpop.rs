@@ -0,0 +1,111 @@
+use std::io;
+
+use crate::{
+    cache::{
+        blocking::{encode_command, wait_until_served, BlockedOp},
+        sorted_set::as_zset_mut,
+    },
+    resp::core::RESPDatatypes,
+};
+
+use super::{
+    blpop::parse_timeout,
+    core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult},
+};
+
+/// `BZPOPMIN` and `BZPOPMAX key [key ...] timeout`
+#[derive(Debug, Default)]
+pub struct BZPop {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+fn popped_reply(key: String, member: Vec<u8>, score: f64) -> RESPDatatypes {
+    RESPDatatypes::Array(vec![
+        RESPDatatypes::BulkString(key),
+        RESPDatatypes::BufBulk(member),
+        RESPDatatypes::Double(score),
+    ])
+}
+
+impl Command for BZPop {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["bzpopmin", "bzpopmax"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run<'a>(
+        &'a mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&'a mut crate::connections::connection::Connection>,
+    ) -> RunResult<'a> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        // only a client can wait, inside a transaction it behaves like ZPOPMIN or ZPOPMAX
+        let conn = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            conn => conn,
+        };
+        let propagator = conn.as_ref().map(|conn| conn.propagator());
+
+        Box::pin(async move {
+            let (timeout, keys) = self.args.split_last().unwrap();
+            let timeout = parse_timeout(timeout)?;
+            let keys = keys
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<io::Result<Vec<_>>>()?;
+            let max = self.name == "bzpopmax";
+
+            let cache = cache_repo.lock().await;
+            for key in keys.iter() {
+                let popped = cache
+                    .modify(key.to_string(), |value| {
+                        Ok::<_, io::Error>(
+                            as_zset_mut(value)?.and_then(|zset| zset.pop(1, max).pop()),
+                        )
+                    })
+                    .await?;
+                if let Some((member, score)) = popped {
+                    // replicas apply the pop that happened, they never block
+                    if let Some(propagator) = propagator {
+                        let name = if max { "ZPOPMAX" } else { "ZPOPMIN" };
+                        propagator.propagate(encode_command(&[name, key])).await;
+                    }
+                    return Ok(popped_reply(key.to_string(), member, score));
+                }
+            }
+            // only a client waits, and not while EXEC runs its transaction
+            let waiting = propagator
+                .as_ref()
+                .is_some_and(|propagator| !propagator.is_transaction());
+            let Some(conn) = conn.filter(|_| waiting) else {
+                return Ok(RESPDatatypes::NullArray);
+            };
+
+            let (id, rx) = cache
+                .blocked
+                .lock()
+                .await
+                .block(keys, BlockedOp::ZPop { max });
+            drop(cache);
+
+            Ok(
+                match wait_until_served(cache_repo.clone(), id, rx, timeout, conn).await? {
+                    Some(served) => {
+                        popped_reply(served.key, served.elem, served.score.unwrap_or_default())
+                    }
+                    None => RESPDatatypes::NullArray,
+                },
+            )
+        })
+    }
+}
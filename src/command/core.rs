@@ -5,58 +5,335 @@ use std::{
     sync::Arc,
 };
 
+use bytes::BytesMut;
 use tokio::sync::Mutex;
+use tokio_util::codec::Decoder;
 
 pub type RunResult<'a> = Pin<Box<dyn Future<Output = Result<RESPDatatypes>> + Send + 'a>>;
 
 use crate::{
     cache::core::CacheRepository,
+    cli::core::Roles,
+    cmd_queue::core::Propagator,
     command::{
-        discard::Discard, echo::Echo, exec::Exec, get::Get, incr::Incr, info::Info, multi::Multi,
-        ping::Ping, psync::Psync, replconf::ReplConf, set::Set,
+        append::Append, bgrewriteaof::BgRewriteAof, bgsave::BgSave, bitcount::BitCount,
+        bitfield::BitField, bitop::BitOp, bitpos::BitPos, blmove::BLMove, blpop::BPop,
+        bzpop::BZPop, del::Del, discard::Discard, echo::Echo, exec::Exec, exists::Exists,
+        expire::Expire, get::Get, getbit::GetBit, getdel::GetDel, getex::GetEx, getrange::GetRange,
+        hdel::HDel, hello::Hello, hget::HGet, hgetall::HGetAll, hincrby::HIncrBy,
+        hincrbyfloat::HIncrByFloat, hlen::HLen, hmget::HMGet, hrandfield::HRandField, hscan::HScan,
+        hset::HSet, incr::Incr, incrbyfloat::IncrByFloat, info::Info, key_type::KeyType,
+        keys::Keys, lastsave::LastSave, lindex::LIndex, linsert::LInsert, llen::LLen, lmove::LMove,
+        lpos::LPos, lrange::LRange, lrem::LRem, lset::LSet, ltrim::LTrim, mget::MGet, mset::MSet,
+        multi::Multi, persist::Persist, ping::Ping, pop::Pop, psync::Psync, push::Push,
+        rename::Rename, replconf::ReplConf, replicaof::ReplicaOf, sadd::SAdd, save::Save,
+        scan::Scan, scard::SCard, set::Set, setbit::SetBit, setop::SetAlgebra, setrange::SetRange,
+        sintercard::SInterCard, sismember::SIsMember, smembers::SMembers, smove::SMove, spop::SPop,
+        srandmember::SRandMember, srem::SRem, sscan::SScan, strlen::StrLen, ttl::Ttl, wait::Wait,
+        xack::XAck, xadd::XAdd, xautoclaim::XAutoClaim, xclaim::XClaim, xgroup::XGroup,
+        xinfo::XInfo, xlen::XLen, xpending::XPending, xrange::XRange, xread::XRead, xsetid::XSetId,
+        zadd::ZAdd, zcount::ZCount, zincrby::ZIncrBy, zpop::ZPop, zrange::ZRange, zrank::ZRank,
+        zrem::ZRem, zscore::ZScore, zunionstore::ZStore,
     },
     connections::connection::Connection,
-    errors::command_not_found::CommandNotFoundError,
-    resp::{core::RESPDatatypes, deserialize::Deseralize},
+    errors::{
+        command_not_found::CommandNotFoundError, value_is_not_type::ValueIsNotType,
+        wrong_arity::WrongArity,
+    },
+    resp::{
+        core::RESPDatatypes,
+        deserialize::{bytes_to_string, bytes_to_type},
+    },
 };
 
 pub trait Command: std::marker::Sync + std::marker::Send {
     fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool;
-    fn run(
-        &mut self,
+    fn run<'a>(
+        &'a mut self,
         cache_repo: Arc<Mutex<CacheRepository>>,
-        conn: Option<&mut Connection>,
-    ) -> RunResult;
+        conn: Option<&'a mut Connection>,
+    ) -> RunResult<'a>;
+}
+
+/// Splits `cmd` into its lowercased name and the arguments after it, when it is an array of
+/// bulk strings naming one of `names`.
+pub fn parse_args(cmd: &RESPDatatypes, names: &[&str]) -> Option<(String, Vec<Vec<u8>>)> {
+    let RESPDatatypes::Array(vec) = cmd else {
+        return None;
+    };
+    let mut args = Vec::with_capacity(vec.len());
+    for elem in vec {
+        match elem {
+            RESPDatatypes::BufBulk(buf) => args.push(buf.to_vec()),
+            _ => return None,
+        }
+    }
+    if args.is_empty() {
+        return None;
+    }
+
+    let name = bytes_to_string(&args.remove(0))
+        .unwrap_or("".to_string())
+        .to_lowercase();
+    if !names.contains(&name.as_str()) {
+        return None;
+    }
+    Some((name, args))
+}
+
+pub fn queued<'a>() -> RunResult<'a> {
+    Box::pin(async move { Ok(RESPDatatypes::SimpleString("QUEUED".to_string())) })
+}
+
+/// Replies with `err` without running anything.
+pub fn fail<'a>(err: Error) -> RunResult<'a> {
+    Box::pin(async move { Err(err) })
+}
+
+pub fn wrong_arity(name: &str) -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        WrongArity {
+            cmd: name.to_string(),
+        },
+    )
+}
+
+pub fn syntax_error() -> Error {
+    Error::new(io::ErrorKind::InvalidInput, "ERR syntax error")
 }
 
-fn get_registered_commands() -> Vec<Box<dyn Command>> {
+/// Hands what was just pushed to `key` to the clients blocked on it, propagating the pops
+/// made for them after the push itself.
+pub async fn wake_blocked(cache: &CacheRepository, key: String, propagator: Option<&Propagator>) {
+    // a replica's blocked clients can't take elements its master will pop for its own, they
+    // can only be told about new stream entries
+    let upstream = propagator.is_some_and(|propagator| propagator.is_upstream());
+    for cmd in cache.serve_blocked(key, !upstream).await {
+        if let Some(propagator) = propagator {
+            propagator.propagate(cmd).await;
+        }
+    }
+}
+
+/// Decodes a key argument. Keys are stored as strings, so ones that aren't valid UTF-8 are
+/// refused rather than mixed up with each other.
+pub fn parse_key(arg: &[u8]) -> Result<String> {
+    String::from_utf8(arg.to_vec()).map_err(|_| {
+        Error::new(
+            io::ErrorKind::InvalidInput,
+            "ERR invalid key, keys must be valid UTF-8",
+        )
+    })
+}
+
+/// Parses an integer argument, failing the way redis does for anything that isn't one.
+pub fn parse_integer(arg: &[u8]) -> Result<i64> {
+    bytes_to_type(arg).map_err(|_| {
+        Error::new(
+            io::ErrorKind::InvalidInput,
+            ValueIsNotType {
+                type_name: "integer".to_string(),
+                can_be_out_of_range: Some(true),
+            },
+        )
+    })
+}
+
+/// Whether a command changes the keyspace. Read only replicas refuse writes from clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    Read,
+    Write,
+}
+
+fn get_registered_commands() -> Vec<(CommandKind, Box<dyn Command>)> {
+    use CommandKind::{Read, Write};
+
     vec![
-        Box::new(Ping::default()),
-        Box::new(Echo::default()),
-        Box::new(Set::default()),
-        Box::new(Get::default()),
-        Box::new(Incr::default()),
-        Box::new(Multi),
-        Box::new(Exec),
-        Box::new(Discard),
-        Box::new(Info::default()),
-        Box::new(ReplConf::default()),
-        Box::new(Psync),
+        (Read, Box::new(Ping::default())),
+        (Read, Box::new(Echo::default())),
+        (Write, Box::new(Set::default())),
+        (Read, Box::new(Get::default())),
+        (Write, Box::new(Incr::default())),
+        (Write, Box::new(IncrByFloat::default())),
+        (Read, Box::new(Multi)),
+        (Read, Box::new(Exec)),
+        (Read, Box::new(Discard)),
+        (Read, Box::new(Info::default())),
+        (Read, Box::new(Hello::default())),
+        (Read, Box::new(ReplConf::default())),
+        (Read, Box::new(Psync::default())),
+        (Read, Box::new(Save)),
+        (Read, Box::new(BgSave)),
+        (Read, Box::new(LastSave)),
+        (Read, Box::new(BgRewriteAof)),
+        (Read, Box::new(Wait::default())),
+        (Read, Box::new(ReplicaOf::default())),
+        (Write, Box::new(Del::default())),
+        (Read, Box::new(Exists::default())),
+        (Read, Box::new(KeyType::default())),
+        (Write, Box::new(Rename::default())),
+        (Read, Box::new(Keys::default())),
+        (Read, Box::new(Scan::default())),
+        (Write, Box::new(Expire::default())),
+        (Read, Box::new(Ttl::default())),
+        (Write, Box::new(Persist::default())),
+        (Write, Box::new(GetDel::default())),
+        (Write, Box::new(GetEx::default())),
+        (Write, Box::new(Append::default())),
+        (Read, Box::new(StrLen::default())),
+        (Read, Box::new(GetRange::default())),
+        (Write, Box::new(SetRange::default())),
+        (Write, Box::new(SetBit::default())),
+        (Read, Box::new(GetBit::default())),
+        (Read, Box::new(BitCount::default())),
+        (Read, Box::new(BitPos::default())),
+        (Write, Box::new(BitOp::default())),
+        (Write, Box::new(BitField::default())),
+        (
+            Read,
+            Box::new(BitField {
+                read_only: true,
+                ..Default::default()
+            }),
+        ),
+        (Read, Box::new(MGet::default())),
+        (Write, Box::new(MSet::default())),
+        (Write, Box::new(Push::default())),
+        (Write, Box::new(Pop::default())),
+        (Read, Box::new(LRange::default())),
+        (Read, Box::new(LLen::default())),
+        (Read, Box::new(LIndex::default())),
+        (Write, Box::new(LSet::default())),
+        (Write, Box::new(LRem::default())),
+        (Write, Box::new(LTrim::default())),
+        (Write, Box::new(LInsert::default())),
+        (Write, Box::new(LMove::default())),
+        (Read, Box::new(LPos::default())),
+        (Write, Box::new(BPop::default())),
+        (Write, Box::new(BLMove::default())),
+        (Write, Box::new(HSet::default())),
+        (Read, Box::new(HGet::default())),
+        (Read, Box::new(HMGet::default())),
+        (Write, Box::new(HDel::default())),
+        (Read, Box::new(HLen::default())),
+        (Read, Box::new(HGetAll::default())),
+        (Write, Box::new(HIncrBy::default())),
+        (Write, Box::new(HIncrByFloat::default())),
+        (Read, Box::new(HRandField::default())),
+        (Read, Box::new(HScan::default())),
+        (Write, Box::new(SAdd::default())),
+        (Write, Box::new(SRem::default())),
+        (Read, Box::new(SIsMember::default())),
+        (Read, Box::new(SMembers::default())),
+        (Read, Box::new(SCard::default())),
+        (Write, Box::new(SPop::default())),
+        (Read, Box::new(SRandMember::default())),
+        (Write, Box::new(SMove::default())),
+        (Read, Box::new(SetAlgebra::default())),
+        (
+            Write,
+            Box::new(SetAlgebra {
+                stores: true,
+                ..Default::default()
+            }),
+        ),
+        (Read, Box::new(SInterCard::default())),
+        (Read, Box::new(SScan::default())),
+        (Write, Box::new(ZAdd::default())),
+        (Write, Box::new(ZRem::default())),
+        (Read, Box::new(ZScore::default())),
+        (Write, Box::new(ZIncrBy::default())),
+        (Read, Box::new(ZRank::default())),
+        (Read, Box::new(ZRange::default())),
+        (
+            Write,
+            Box::new(ZRange {
+                stores: true,
+                ..Default::default()
+            }),
+        ),
+        (Read, Box::new(ZCount::default())),
+        (Write, Box::new(ZPop::default())),
+        (Write, Box::new(BZPop::default())),
+        (Write, Box::new(ZStore::default())),
+        (Write, Box::new(XAdd::default())),
+        (Read, Box::new(XRange::default())),
+        (Read, Box::new(XLen::default())),
+        (Read, Box::new(XRead::default())),
+        (
+            Write,
+            Box::new(XRead {
+                grouped: true,
+                ..Default::default()
+            }),
+        ),
+        (Write, Box::new(XGroup::default())),
+        (Write, Box::new(XSetId::default())),
+        (Write, Box::new(XAck::default())),
+        (Read, Box::new(XPending::default())),
+        (Write, Box::new(XClaim::default())),
+        (Write, Box::new(XAutoClaim::default())),
+        (Read, Box::new(XInfo::default())),
     ]
 }
 
+/// Writes reach a replica only through its master link, unless it was told to accept them.
+async fn refuses_writes(conn: &Connection) -> bool {
+    if conn.is_master {
+        return false;
+    }
+    let replication_config = conn.server_config.replication_config.read().await;
+    matches!(replication_config.role, Roles::Slave(_)) && replication_config.replica_read_only
+}
+
+/// Executes every complete command buffered in `input`, in order, and returns the
+/// concatenated replies. Bytes of a trailing partial command are left in `input`.
 pub async fn run(
-    input: &mut Vec<u8>,
+    input: &mut BytesMut,
     cache_repo: Arc<Mutex<CacheRepository>>,
     conn: &mut Connection,
 ) -> Vec<u8> {
-    let dslz = Deseralize {};
-    // println!("working");
-    match dslz.deseralize(input) {
-        Ok(cmd) => run_command(cmd, cache_repo, conn).await,
-        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Vec::new(),
-        Err(err) => RESPDatatypes::SimpleError(Box::new(err)).encode(),
+    let mut res = Vec::new();
+    loop {
+        match conn.codec.decode(input) {
+            Ok(Some(cmd)) => {
+                res.extend(run_command(cmd, cache_repo.clone(), conn).await);
+                // the connection turns into a replication stream after PSYNC
+                if conn.is_replica || conn.closed {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                input.clear();
+                res.extend(RESPDatatypes::SimpleError(Box::new(err)).encode());
+                break;
+            }
+        }
+    }
+    res
+}
+
+/// Executes a command without a client connection, as done when replaying persisted writes.
+pub async fn apply_command(
+    cmd: RESPDatatypes,
+    cache_repo: Arc<Mutex<CacheRepository>>,
+) -> Result<RESPDatatypes> {
+    let mut commands = get_registered_commands();
+    for (_, command) in commands.iter_mut() {
+        if command.can_execute(&cmd) {
+            return command.run(cache_repo, None).await;
+        }
     }
+
+    Err(Error::new(
+        io::ErrorKind::InvalidInput,
+        CommandNotFoundError {
+            cmd: "command not found".to_string(),
+        },
+    ))
 }
 
 pub async fn run_command(
@@ -66,11 +343,18 @@ pub async fn run_command(
 ) -> Vec<u8> {
     let mut commands = get_registered_commands();
     // println!("{:?}", cmd);
-    for command in commands.iter_mut() {
+    for (kind, command) in commands.iter_mut() {
         if command.can_execute(&cmd) {
+            if *kind == CommandKind::Write && refuses_writes(conn).await {
+                return RESPDatatypes::SimpleError(Box::new(Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "READONLY You can't write against a read only replica.",
+                )))
+                .encode();
+            }
             return match command.run(cache_repo, Some(conn)).await {
                 Ok(data) => {
-                    return data.encode();
+                    return data.encode_for(conn.codec.protocol());
                 }
                 Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
                     return Vec::new();
@@ -88,3 +372,143 @@ pub async fn run_command(
     )))
     .encode()
 }
+
+/// Fixtures the tests of commands share.
+#[cfg(test)]
+pub mod test_support {
+    use std::{path::PathBuf, sync::Arc};
+
+    use tokio::{
+        net::{TcpListener, TcpStream},
+        sync::{Mutex, RwLock},
+    };
+
+    use crate::{
+        aof::core::Aof,
+        cache::{blocking::encode_command, core::CacheRepository},
+        cli::{
+            config::{
+                Config, FsyncPolicy, MasterLinkState, PersistenceConfig, ReplicationConfig,
+                ServerConfig,
+            },
+            core::Roles,
+        },
+        cmd_queue::core::CmdQueue,
+        connections::connection::Connection,
+        resp::{core::RESPDatatypes, deserialize::Deseralize},
+    };
+
+    use super::{apply_command, run_command};
+
+    fn parse(args: &[&str]) -> RESPDatatypes {
+        Deseralize.parse(&encode_command(args)).unwrap().unwrap().0
+    }
+
+    /// Runs a command without a connection and returns its reply.
+    pub async fn run(repo: &Arc<Mutex<CacheRepository>>, args: &[&str]) -> RESPDatatypes {
+        apply_command(parse(args), repo.clone()).await.unwrap()
+    }
+
+    /// Runs a command for a client and returns its encoded reply.
+    pub async fn send(
+        conn: &mut Connection,
+        repo: &Arc<Mutex<CacheRepository>>,
+        args: &[&str],
+    ) -> Vec<u8> {
+        run_command(parse(args), repo.clone(), conn).await
+    }
+
+    /// A fresh path in the temporary directory for a log named after the test.
+    pub fn aof_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.aof", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// A master side connection of a client, with the socket the client holds. Writes are
+    /// appended to `aof` when given, and to the replication backlog.
+    pub async fn client(
+        repo: Arc<Mutex<CacheRepository>>,
+        aof: Option<PathBuf>,
+    ) -> (Connection, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let peer = TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let replication_config = Arc::new(RwLock::new(ReplicationConfig {
+            role: Roles::Master,
+            master_repl_id: "0".repeat(40),
+            master_repl_offset: 0,
+            master_link: MasterLinkState::default(),
+            replica_read_only: true,
+        }));
+        let mut cmdq = CmdQueue::new(replication_config.clone());
+        cmdq.aof = aof.map(|aof| Aof::open(aof, FsyncPolicy::Always).unwrap());
+        let config = Config {
+            server_config: ServerConfig { port: addr.port() },
+            replication_config,
+            persistence_config: PersistenceConfig {
+                dir: ".".to_string(),
+                dbfilename: "dump.rdb".to_string(),
+                appendonly: true,
+                appendfilename: "appendonly.aof".to_string(),
+                appendfsync: FsyncPolicy::Always,
+            },
+        };
+        let conn = Connection::new(
+            stream,
+            addr,
+            repo,
+            config,
+            Arc::new(Mutex::new(cmdq)),
+            false,
+        );
+        (conn, peer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{cache::core::CacheRepository, resp::core::RESPDatatypes};
+
+    use super::{apply_command, test_support::run};
+
+    fn command(args: &[&[u8]]) -> RESPDatatypes {
+        RESPDatatypes::Array(
+            args.iter()
+                .map(|arg| RESPDatatypes::BufBulk(arg.to_vec()))
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn refuses_keys_that_are_not_utf8() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        run(&repo, &["SET", "", "empty"]).await;
+
+        let invalid: &[u8] = b"\xff\xfe";
+        for args in [
+            vec![b"SET".as_slice(), invalid, b"v"],
+            vec![b"STRLEN", invalid],
+            vec![b"DEL", b"", invalid],
+            vec![b"MSET", b"", b"v", invalid, b"v"],
+            vec![b"RENAME", b"", invalid],
+            vec![b"LPUSH", invalid, b"v"],
+            vec![b"SADD", invalid, b"v"],
+        ] {
+            let err = apply_command(command(&args), repo.clone())
+                .await
+                .unwrap_err();
+            assert_eq!(err.to_string(), "ERR invalid key, keys must be valid UTF-8");
+        }
+        // nothing ran against the empty key the invalid ones used to turn into
+        let reply = run(&repo, &["STRLEN", ""]).await;
+        assert!(matches!(reply, RESPDatatypes::Integer(5)));
+        assert_eq!(repo.lock().await.keys().await, vec!["".to_string()]);
+    }
+}
@@ -0,0 +1,64 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `DEL key [key ...]`, and `UNLINK` which does the same here since values are freed
+/// synchronously either way.
+#[derive(Debug, Default)]
+pub struct Del {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl Command for Del {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["del", "unlink"]) else {
+            return false;
+        };
+        self.name = name;
+        self.keys = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.keys.is_empty() {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let keys = self
+                .keys
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            let cache = cache_repo.lock().await;
+            let mut deleted = 0;
+            for key in keys {
+                if cache.remove(key.to_string()).await {
+                    deleted += 1;
+                }
+            }
+
+            if deleted > 0 {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::Integer(deleted))
+        })
+    }
+}
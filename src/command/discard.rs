@@ -35,7 +35,7 @@ impl Command for Discard {
         &mut self,
         _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
         conn: Option<&mut crate::connections::connection::Connection>,
-    ) -> super::core::RunResult {
+    ) -> super::core::RunResult<'_> {
         if let Some(conn) = conn {
             if conn.is_in_transaction() {
                 conn.discard_transaction();
@@ -44,7 +44,7 @@ impl Command for Echo {
         &mut self,
         _cache_repo: Arc<Mutex<CacheRepository>>,
         _conn: Option<&mut Connection>,
-    ) -> RunResult {
+    ) -> RunResult<'_> {
         Box::pin(async move {
             if let Some(buf) = self.data.as_ref() {
                 return Ok(RESPDatatypes::BufBulk(buf.to_vec()));
@@ -31,11 +31,11 @@ impl Command for Exec {
         false
     }
 
-    fn run(
-        &mut self,
+    fn run<'a>(
+        &'a mut self,
         cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
-        conn: Option<&mut crate::connections::connection::Connection>,
-    ) -> super::core::RunResult {
+        conn: Option<&'a mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'a> {
         if let Some(conn) = conn {
             if conn.is_in_transaction() {
                 let tnx_id = conn.get_id().to_string();
@@ -50,22 +50,28 @@ impl Command for Exec {
 
                     let handler = tokio::spawn(async move {
                         tokio::time::sleep(Duration::from_millis(30)).await;
-                        match { handler_repo.lock().await.get_transaction_id().await } {
-                            Some(curr_id) if curr_id == tnx_id.to_string() => {
+                        match handler_repo.lock().await.get_transaction_id().await {
+                            Some(curr_id) if curr_id == tnx_id => {
                                 handler_repo.lock().await.unset_transaction().await;
                             }
                             _ => {}
                         }
                     });
+                    // the queued writes reach the append only file and the replicas as
+                    // one MULTI/EXEC block once all of them ran
+                    let propagator = conn.propagator().transaction();
+                    conn.tnx_propagator = Some(propagator.clone());
                     let mut resp = vec![];
                     for cmd in tnxs.iter_mut() {
                         let cache_repo = cache.clone();
                         resp.push(
-                            cmd.run(cache_repo, None)
+                            cmd.run(cache_repo, Some(&mut *conn))
                                 .await
                                 .unwrap_or_else(|err| RESPDatatypes::SimpleError(Box::new(err))),
                         );
                     }
+                    conn.tnx_propagator = None;
+                    propagator.commit().await;
                     cache.lock().await.unset_transaction().await;
 
                     handler.abort_handle().abort();
@@ -81,3 +87,60 @@ impl Command for Exec {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{
+        aof::core::replay,
+        cache::{blocking::encode_command, core::CacheRepository},
+        command::core::test_support::{aof_path, client, send},
+    };
+
+    #[tokio::test]
+    async fn exec_propagates_queued_writes_as_one_transaction() {
+        let path = aof_path("exec-propagates");
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), Some(path.clone())).await;
+
+        send(&mut conn, &repo, &["MULTI"]).await;
+        send(&mut conn, &repo, &["SET", "intx", "1"]).await;
+        send(&mut conn, &repo, &["GET", "intx"]).await;
+        send(&mut conn, &repo, &["EXEC"]).await;
+
+        let mut expected = encode_command(&["MULTI"]);
+        expected.extend(encode_command(&["SET", "intx", "1"]));
+        expected.extend(encode_command(&["EXEC"]));
+        let streamed = conn.cmdq.lock().await.backlog.read_from(0).unwrap();
+        assert_eq!(streamed, expected);
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+
+        let restarted = Arc::new(Mutex::new(CacheRepository::default()));
+        assert_eq!(replay(&path, restarted.clone()).await.unwrap(), 1);
+        let cache = restarted.lock().await;
+        assert_eq!(
+            cache.get("intx".to_string()).await.unwrap(),
+            Some(b"1".to_vec())
+        );
+        drop(cache);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn exec_without_writes_propagates_nothing() {
+        let path = aof_path("exec-read-only");
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), Some(path.clone())).await;
+
+        send(&mut conn, &repo, &["MULTI"]).await;
+        send(&mut conn, &repo, &["GET", "missing"]).await;
+        send(&mut conn, &repo, &["EXEC"]).await;
+
+        assert_eq!(conn.cmdq.lock().await.backlog.offset(), 0);
+        assert!(std::fs::read(&path).unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `EXISTS key [key ...]`, a key named more than once is counted every time.
+#[derive(Debug, Default)]
+pub struct Exists {
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl Command for Exists {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["exists"]) else {
+            return false;
+        };
+        self.keys = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.keys.is_empty() {
+            return fail(wrong_arity("exists"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let keys = self
+                .keys
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            let cache = cache_repo.lock().await;
+            let mut found = 0;
+            for key in keys {
+                if cache.exists(key.to_string()).await {
+                    found += 1;
+                }
+            }
+            Ok(RESPDatatypes::Integer(found))
+        })
+    }
+}
@@ -0,0 +1,169 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::core::{instant_to_unix_millis, unix_millis_to_instant, unix_time_millis},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `EXPIRE`, `PEXPIRE`, `EXPIREAT` and `PEXPIREAT key time [NX | XX | GT | LT]`.
+///
+/// Whatever the form, the write is propagated as `PEXPIREAT` with the absolute deadline, so
+/// replicas and the append only file don't drift by the time it took to get there.
+#[derive(Debug, Default)]
+pub struct Expire {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+enum Condition {
+    #[default]
+    Always,
+    Nx,
+    Xx,
+    Gt,
+    Lt,
+}
+
+impl Condition {
+    /// Whether a key whose deadline is `current` may be given `deadline`, a key without one
+    /// counts as expiring never.
+    fn allows(&self, current: Option<u64>, deadline: u64) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Nx => current.is_none(),
+            Condition::Xx => current.is_some(),
+            Condition::Gt => current.is_some_and(|current| deadline > current),
+            Condition::Lt => current.is_none_or(|current| deadline < current),
+        }
+    }
+}
+
+impl Expire {
+    fn parse_condition(&self) -> io::Result<Condition> {
+        let mut condition = Condition::Always;
+        for option in self.args.iter().skip(2) {
+            let option = bytes_to_string(option).unwrap_or("".to_string());
+            let next = match option.to_lowercase().as_str() {
+                "nx" => Condition::Nx,
+                "xx" => Condition::Xx,
+                "gt" => Condition::Gt,
+                "lt" => Condition::Lt,
+                _ => {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("ERR Unsupported option {}", option),
+                    ))
+                }
+            };
+            condition = match (condition, next) {
+                (Condition::Always, next) => next,
+                (current, next) if current == next => next,
+                (Condition::Gt, Condition::Lt) | (Condition::Lt, Condition::Gt) => {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ERR GT and LT options at the same time are not compatible",
+                    ))
+                }
+                _ => {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ERR NX and XX, GT or LT options at the same time are not compatible",
+                    ))
+                }
+            };
+        }
+        Ok(condition)
+    }
+
+    /// The deadline in milliseconds since the unix epoch, negative when it is long gone.
+    fn parse_deadline(&self) -> io::Result<i64> {
+        let time = parse_integer(&self.args[1])?;
+
+        let now = unix_time_millis() as i64;
+        let deadline = match self.name.as_str() {
+            "expire" => time
+                .checked_mul(1000)
+                .and_then(|time| time.checked_add(now)),
+            "pexpire" => time.checked_add(now),
+            "expireat" => time.checked_mul(1000),
+            _ => Some(time),
+        };
+        deadline.ok_or_else(|| {
+            Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("ERR invalid expire time in '{}' command", self.name),
+            )
+        })
+    }
+}
+
+impl Command for Expire {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["expire", "pexpire", "expireat", "pexpireat"])
+        else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let deadline = self.parse_deadline()?;
+            let condition = self.parse_condition()?;
+
+            let cache = cache_repo.lock().await;
+            let Some(current) = cache.expiry(key.to_string()).await else {
+                return Ok(RESPDatatypes::Integer(0));
+            };
+            let current = current.map(instant_to_unix_millis);
+            if !condition.allows(current, deadline.max(0) as u64) {
+                return Ok(RESPDatatypes::Integer(0));
+            }
+
+            // a deadline that already passed deletes the key, replicas are told so explicitly
+            let propagated = if deadline <= unix_time_millis() as i64 {
+                cache.remove(key.to_string()).await;
+                vec!["DEL".to_string(), key]
+            } else {
+                cache
+                    .expire_at(key.to_string(), unix_millis_to_instant(deadline as u64))
+                    .await;
+                vec!["PEXPIREAT".to_string(), key, deadline.to_string()]
+            };
+            if let Some(propagator) = propagator {
+                let cmd = RESPDatatypes::Array(
+                    propagated
+                        .into_iter()
+                        .map(RESPDatatypes::BulkString)
+                        .collect(),
+                );
+                propagator.propagate(cmd.encode()).await;
+            }
+            Ok(RESPDatatypes::Integer(1))
+        })
+    }
+}
@@ -19,7 +19,7 @@ impl Get {
         self.key.to_string()
     }
 
-    fn is_get_cmd(&self, vec: &Vec<RESPDatatypes>) -> bool {
+    fn is_get_cmd(&self, vec: &[RESPDatatypes]) -> bool {
         if let Some(first_elem) = vec.first() {
             return match first_elem {
                 RESPDatatypes::BufBulk(buff) => {
@@ -34,7 +34,7 @@ impl Get {
         false
     }
 
-    fn set_key_if_possible(&mut self, vec: &Vec<RESPDatatypes>) -> bool {
+    fn set_key_if_possible(&mut self, vec: &[RESPDatatypes]) -> bool {
         if let Some(first_elem) = vec.get(1) {
             return match first_elem {
                 RESPDatatypes::BufBulk(buff) => {
@@ -79,7 +79,7 @@ impl Command for Get {
         &mut self,
         cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
         conn: Option<&mut Connection>,
-    ) -> RunResult {
+    ) -> RunResult<'_> {
         match conn {
             Some(conn) if conn.is_in_transaction() => {
                 conn.add_tnx(Box::new(Self::new(self.get_key())));
@@ -88,7 +88,7 @@ impl Command for Get {
             _ => Box::pin(async move {
                 let cache = cache_repo.clone();
 
-                if let Some(data) = cache.lock().await.get(self.get_key()).await {
+                if let Some(data) = cache.lock().await.get(self.get_key()).await? {
                     return Ok(RESPDatatypes::BufBulk(data));
                 }
                 Ok(RESPDatatypes::NullString)
@@ -0,0 +1,46 @@
+use crate::{
+    cache::bitmap::{get_bit, parse_bit_offset},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `GETBIT key offset`, bits past the end of the string being zeros.
+#[derive(Debug, Default)]
+pub struct GetBit {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for GetBit {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["getbit"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 2 {
+            return fail(wrong_arity("getbit"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let offset = parse_bit_offset(&self.args[1], None)?;
+
+            let value = cache_repo.lock().await.get(key).await?.unwrap_or_default();
+            Ok(RESPDatatypes::Integer(get_bit(&value, offset) as i64))
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `GETDEL key`
+#[derive(Debug, Default)]
+pub struct GetDel {
+    pub cmd: Vec<u8>,
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for GetDel {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["getdel"]) else {
+            return false;
+        };
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity("getdel"));
+        };
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let cache = cache_repo.lock().await;
+            let Some(value) = cache.get(key.to_string()).await? else {
+                return Ok(RESPDatatypes::NullString);
+            };
+            cache.remove(key.to_string()).await;
+
+            if let Some(propagator) = propagator {
+                let del = RESPDatatypes::Array(vec![
+                    RESPDatatypes::BulkString("DEL".to_string()),
+                    RESPDatatypes::BulkString(key),
+                ]);
+                propagator.propagate(del.encode()).await;
+            }
+            Ok(RESPDatatypes::BufBulk(value))
+        })
+    }
+}
@@ -0,0 +1,116 @@
+use std::io;
+
+use crate::{
+    cache::core::{unix_millis_to_instant, unix_time_millis},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, syntax_error, wrong_arity, Command, RunResult},
+    set::parse_expiry,
+};
+
+/// `GETEX key [EX | PX | EXAT | PXAT time | PERSIST]`
+#[derive(Debug, Default)]
+pub struct GetEx {
+    pub args: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, PartialEq)]
+enum GetExOption {
+    Unchanged,
+    Persist,
+    /// milliseconds since the unix epoch
+    At(u64),
+}
+
+impl GetEx {
+    fn parse_option(&self) -> io::Result<GetExOption> {
+        let mut option = GetExOption::Unchanged;
+        let mut args = self.args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            let arg = bytes_to_string(arg)
+                .unwrap_or("".to_string())
+                .to_lowercase();
+            if option != GetExOption::Unchanged {
+                return Err(syntax_error());
+            }
+            option = match arg.as_str() {
+                "persist" => GetExOption::Persist,
+                "ex" | "px" | "exat" | "pxat" => {
+                    let time = args.next().ok_or_else(syntax_error)?;
+                    GetExOption::At(parse_expiry("getex", &arg, time)?)
+                }
+                _ => return Err(syntax_error()),
+            };
+        }
+        Ok(option)
+    }
+}
+
+impl Command for GetEx {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["getex"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() {
+            return fail(wrong_arity("getex"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let option = self.parse_option()?;
+
+            let cache = cache_repo.lock().await;
+            let Some(value) = cache.get(key.to_string()).await? else {
+                return Ok(RESPDatatypes::NullString);
+            };
+
+            // propagated the way EXPIRE and PERSIST would be, with an absolute deadline
+            let propagated = match option {
+                GetExOption::Unchanged => None,
+                GetExOption::Persist => cache
+                    .persist(key.to_string())
+                    .await
+                    .then(|| vec!["PERSIST".to_string(), key]),
+                GetExOption::At(deadline) if deadline <= unix_time_millis() => {
+                    cache.remove(key.to_string()).await;
+                    Some(vec!["DEL".to_string(), key])
+                }
+                GetExOption::At(deadline) => {
+                    cache
+                        .expire_at(key.to_string(), unix_millis_to_instant(deadline))
+                        .await;
+                    Some(vec!["PEXPIREAT".to_string(), key, deadline.to_string()])
+                }
+            };
+            if let (Some(propagator), Some(propagated)) = (propagator, propagated) {
+                let cmd = RESPDatatypes::Array(
+                    propagated
+                        .into_iter()
+                        .map(RESPDatatypes::BulkString)
+                        .collect(),
+                );
+                propagator.propagate(cmd.encode()).await;
+            }
+            Ok(RESPDatatypes::BufBulk(value))
+        })
+    }
+}
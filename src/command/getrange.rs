@@ -0,0 +1,50 @@
+use crate::{resp::core::RESPDatatypes, utils::range::clamp_range};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `GETRANGE key start end`, both ends inclusive and counted from the end when negative.
+#[derive(Debug, Default)]
+pub struct GetRange {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for GetRange {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["getrange"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("getrange"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let start = parse_integer(&self.args[1])?;
+            let end = parse_integer(&self.args[2])?;
+
+            let value = cache_repo.lock().await.get(key).await?.unwrap_or_default();
+            let range = match clamp_range(start, end, value.len()) {
+                Some((start, end)) => value[start..=end].to_vec(),
+                None => vec![],
+            };
+            Ok(RESPDatatypes::BufBulk(range))
+        })
+    }
+}
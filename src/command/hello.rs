@@ -0,0 +1,189 @@
+use std::io::{self, Error};
+
+use crate::{
+    cli::core::Roles,
+    resp::{
+        core::{Protocol, RESPDatatypes},
+        deserialize::bytes_to_string,
+    },
+};
+
+use super::core::Command;
+
+#[derive(Debug, Default)]
+pub struct Hello {
+    pub protover: Option<String>,
+    pub auth: Option<(String, String)>,
+    pub client_name: Option<String>,
+    pub syntax_error: bool,
+}
+
+impl Hello {
+    /// Checks every option before any of them is applied, returning the requested protocol.
+    fn validate(&self) -> io::Result<Option<Protocol>> {
+        if self.syntax_error {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR Syntax error in HELLO option",
+            ));
+        }
+        let protocol = self.parse_protocol()?;
+        self.check_auth()?;
+        self.check_client_name()?;
+        Ok(protocol)
+    }
+
+    fn parse_protocol(&self) -> io::Result<Option<Protocol>> {
+        let Some(protover) = self.protover.as_ref() else {
+            return Ok(None);
+        };
+        match protover.parse::<i64>() {
+            Ok(2) => Ok(Some(Protocol::Resp2)),
+            Ok(3) => Ok(Some(Protocol::Resp3)),
+            Ok(_) => Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "NOPROTO sorry, this protocol version is not supported.",
+            )),
+            Err(_) => Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR Protocol version is not an integer or out of range",
+            )),
+        }
+    }
+
+    fn check_auth(&self) -> io::Result<()> {
+        // there is no ACL support, so the only user is `default` and it has no password
+        if let Some((username, _)) = self.auth.as_ref() {
+            if username != "default" {
+                return Err(Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "WRONGPASS invalid username-password pair or user is disabled.",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_client_name(&self) -> io::Result<()> {
+        if let Some(name) = self.client_name.as_ref() {
+            if name.bytes().any(|b| !(b'!'..=b'~').contains(&b)) {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR Client names cannot contain spaces, newlines or special characters.",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Command for Hello {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            let mut args = Vec::with_capacity(vec.len());
+            for elem in vec {
+                match elem {
+                    RESPDatatypes::BufBulk(buf) => {
+                        args.push(bytes_to_string(buf).unwrap_or("".to_string()))
+                    }
+                    _ => return false,
+                }
+            }
+
+            match args.first() {
+                Some(name) if name.to_lowercase() == "hello" => {}
+                _ => return false,
+            }
+
+            self.protover = args.get(1).cloned();
+            let mut idx = 2;
+            while idx < args.len() {
+                match args[idx].to_lowercase().as_str() {
+                    "auth" if idx + 2 < args.len() => {
+                        self.auth = Some((args[idx + 1].to_string(), args[idx + 2].to_string()));
+                        idx += 3;
+                    }
+                    "setname" if idx + 1 < args.len() => {
+                        self.client_name = Some(args[idx + 1].to_string());
+                        idx += 2;
+                    }
+                    _ => {
+                        self.syntax_error = true;
+                        break;
+                    }
+                }
+            }
+            return true;
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        let reply = match (self.validate(), conn) {
+            (Err(err), _) => Err(err),
+            (Ok(_), None) => Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR HELLO is not allowed in this context",
+            )),
+            (Ok(protocol), Some(conn)) => {
+                if let Some(protocol) = protocol {
+                    conn.codec.set_protocol(protocol);
+                }
+                if let Some(name) = self.client_name.take() {
+                    conn.client_name = Some(name);
+                }
+
+                let replication_config = conn.server_config.replication_config.clone();
+                let proto = conn.codec.protocol().version();
+                let id = conn.client_id as i64;
+
+                return Box::pin(async move {
+                    let role = match replication_config.read().await.role {
+                        Roles::Master => "master",
+                        Roles::Slave(_) => "replica",
+                    };
+                    Ok(hello_reply(proto, id, role))
+                });
+            }
+        };
+
+        Box::pin(async move { reply })
+    }
+}
+
+fn hello_reply(proto: i32, id: i64, role: &str) -> RESPDatatypes {
+    RESPDatatypes::Map(vec![
+        (
+            RESPDatatypes::BulkString("server".to_string()),
+            RESPDatatypes::BulkString("redis".to_string()),
+        ),
+        (
+            RESPDatatypes::BulkString("version".to_string()),
+            RESPDatatypes::BulkString("7.2.0".to_string()),
+        ),
+        (
+            RESPDatatypes::BulkString("proto".to_string()),
+            RESPDatatypes::Integer(proto.into()),
+        ),
+        (
+            RESPDatatypes::BulkString("id".to_string()),
+            RESPDatatypes::Integer(id),
+        ),
+        (
+            RESPDatatypes::BulkString("mode".to_string()),
+            RESPDatatypes::BulkString("standalone".to_string()),
+        ),
+        (
+            RESPDatatypes::BulkString("role".to_string()),
+            RESPDatatypes::BulkString(role.to_string()),
+        ),
+        (
+            RESPDatatypes::BulkString("modules".to_string()),
+            RESPDatatypes::Array(vec![]),
+        ),
+    ])
+}
@@ -0,0 +1,58 @@
+use crate::{cache::hash::as_hash, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `HGET`, `HEXISTS` and `HSTRLEN key field`
+#[derive(Debug, Default)]
+pub struct HGet {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for HGet {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["hget", "hexists", "hstrlen"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let field = &self.args[1];
+
+            let value = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(
+                        as_hash(value)?.and_then(|hash| hash.get(field).cloned()),
+                    )
+                })
+                .await?;
+            Ok(match (self.name.as_str(), value) {
+                ("hexists", value) => RESPDatatypes::Integer(value.is_some() as i64),
+                ("hstrlen", value) => RESPDatatypes::Integer(value.map_or(0, |v| v.len() as i64)),
+                (_, Some(value)) => RESPDatatypes::BufBulk(value),
+                (_, None) => RESPDatatypes::NullString,
+            })
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{cache::hash::as_hash, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `HGETALL`, `HKEYS` and `HVALS key`
+#[derive(Debug, Default)]
+pub struct HGetAll {
+    pub name: String,
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for HGetAll {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["hgetall", "hkeys", "hvals"]) else {
+            return false;
+        };
+        self.name = name;
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity(&self.name));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let pairs = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_hash(value)?.map_or(vec![], |hash| {
+                        hash.iter()
+                            .map(|(field, value)| (field.to_vec(), value.to_vec()))
+                            .collect()
+                    }))
+                })
+                .await?;
+
+            Ok(match self.name.as_str() {
+                "hkeys" => RESPDatatypes::Array(
+                    pairs
+                        .into_iter()
+                        .map(|(field, _)| RESPDatatypes::BufBulk(field))
+                        .collect(),
+                ),
+                "hvals" => RESPDatatypes::Array(
+                    pairs
+                        .into_iter()
+                        .map(|(_, value)| RESPDatatypes::BufBulk(value))
+                        .collect(),
+                ),
+                _ => RESPDatatypes::Map(
+                    pairs
+                        .into_iter()
+                        .map(|(field, value)| {
+                            (RESPDatatypes::BufBulk(field), RESPDatatypes::BufBulk(value))
+                        })
+                        .collect(),
+                ),
+            })
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::hash::as_hash_or_create,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_type},
+};
+
+use super::{
+    core::{fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult},
+    incr::overflow_error,
+};
+
+/// `HINCRBY key field increment`
+#[derive(Debug, Default)]
+pub struct HIncrBy {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for HIncrBy {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["hincrby"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("hincrby"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let field = &self.args[1];
+            let delta = parse_integer(&self.args[2])?;
+
+            let cache = cache_repo.lock().await;
+            let val = cache
+                .modify(key, |value| {
+                    let hash = as_hash_or_create(value)?;
+                    let current = match hash.get(field) {
+                        Some(current) => bytes_to_type::<i64>(current).map_err(|_| {
+                            Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "ERR hash value is not an integer",
+                            )
+                        })?,
+                        None => 0,
+                    };
+                    let val = current.checked_add(delta).ok_or_else(overflow_error)?;
+                    hash.insert(field.to_vec(), val.to_string().into_bytes());
+                    Ok::<_, io::Error>(val)
+                })
+                .await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(val))
+        })
+    }
+}
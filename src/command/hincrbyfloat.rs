@@ -0,0 +1,125 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::hash::as_hash_or_create,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_type},
+    utils::float::format_float_sum,
+};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult},
+    incrbyfloat::parse_float,
+};
+
+/// `HINCRBYFLOAT key field increment`
+///
+/// Like `INCRBYFLOAT`, the result is propagated as an `HSET` of the formatted value.
+#[derive(Debug, Default)]
+pub struct HIncrByFloat {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for HIncrByFloat {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["hincrbyfloat"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("hincrbyfloat"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let field = &self.args[1];
+            let increment = parse_float(&self.args[2])?;
+
+            let cache = cache_repo.lock().await;
+            let value = cache
+                .modify(key.to_string(), |value| {
+                    let hash = as_hash_or_create(value)?;
+                    let current = hash.get(field).map_or(b"0".as_slice(), Vec::as_slice);
+                    let val = bytes_to_type::<f64>(current)
+                        .ok()
+                        .filter(|current| !current.is_nan())
+                        .ok_or_else(|| {
+                            Error::new(io::ErrorKind::InvalidInput, "ERR hash value is not a float")
+                        })?
+                        + increment;
+                    if !val.is_finite() {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR increment would produce NaN or Infinity",
+                        ));
+                    }
+                    let value = format_float_sum(current, &self.args[2], val).into_bytes();
+                    hash.insert(field.to_vec(), value.to_vec());
+                    Ok(value)
+                })
+                .await?;
+
+            if let Some(propagator) = propagator {
+                let cmd = RESPDatatypes::Array(vec![
+                    RESPDatatypes::BulkString("HSET".to_string()),
+                    RESPDatatypes::BulkString(key),
+                    RESPDatatypes::BufBulk(field.to_vec()),
+                    RESPDatatypes::BufBulk(value.to_vec()),
+                ]);
+                propagator.propagate(cmd.encode()).await;
+            }
+            Ok(RESPDatatypes::BufBulk(value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{
+        cache::core::CacheRepository, command::core::test_support::run, resp::core::RESPDatatypes,
+    };
+
+    #[tokio::test]
+    async fn formats_the_result_like_redis() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        run(&repo, &["HSET", "h", "f", "2001.1"]).await;
+
+        let reply = run(&repo, &["HINCRBYFLOAT", "h", "f", "0.1"]).await;
+        assert!(matches!(reply, RESPDatatypes::BufBulk(value) if value == b"2001.2"));
+        let reply = run(&repo, &["HINCRBYFLOAT", "h", "new", "5.0e3"]).await;
+        assert!(matches!(reply, RESPDatatypes::BufBulk(value) if value == b"5000"));
+    }
+
+    #[tokio::test]
+    async fn never_uses_scientific_notation() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+
+        let reply = run(&repo, &["HINCRBYFLOAT", "h", "big", "1e20"]).await;
+        assert!(
+            matches!(reply, RESPDatatypes::BufBulk(value) if value == b"100000000000000000000")
+        );
+        let reply = run(&repo, &["HINCRBYFLOAT", "h", "small", "-1.25e-7"]).await;
+        assert!(matches!(reply, RESPDatatypes::BufBulk(value) if value == b"-0.000000125"));
+        let reply = run(&repo, &["HINCRBYFLOAT", "h", "small", "0.0000001"]).await;
+        assert!(matches!(reply, RESPDatatypes::BufBulk(value) if value == b"-0.000000025"));
+    }
+}
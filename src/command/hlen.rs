@@ -0,0 +1,49 @@
+use crate::{cache::hash::as_hash, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `HLEN key`
+#[derive(Debug, Default)]
+pub struct HLen {
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for HLen {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["hlen"]) else {
+            return false;
+        };
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity("hlen"));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let len = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_hash(value)?.map_or(0, |hash| hash.len()))
+                })
+                .await?;
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
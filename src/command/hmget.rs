@@ -0,0 +1,57 @@
+use crate::{cache::hash::as_hash, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `HMGET key field [field ...]`
+#[derive(Debug, Default)]
+pub struct HMGet {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for HMGet {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["hmget"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("hmget"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+
+            let values = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let hash = as_hash(value)?;
+                    Ok::<_, std::io::Error>(
+                        self.args[1..]
+                            .iter()
+                            .map(|field| match hash.and_then(|hash| hash.get(field)) {
+                                Some(value) => RESPDatatypes::BufBulk(value.to_vec()),
+                                None => RESPDatatypes::NullString,
+                            })
+                            .collect(),
+                    )
+                })
+                .await?;
+            Ok(RESPDatatypes::Array(values))
+        })
+    }
+}
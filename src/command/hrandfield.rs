@@ -0,0 +1,124 @@
+use rand::seq::{IteratorRandom, SliceRandom};
+
+use crate::{
+    cache::hash::as_hash,
+    resp::{
+        core::{Protocol, RESPDatatypes},
+        deserialize::bytes_to_string,
+    },
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `HRANDFIELD key [count [WITHVALUES]]`
+///
+/// A positive count picks distinct fields, a negative one may pick the same field again.
+#[derive(Debug, Default)]
+pub struct HRandField {
+    pub args: Vec<Vec<u8>>,
+    /// RESP3 clients get `WITHVALUES` pairs as nested arrays instead of a flat one
+    pub resp3: bool,
+}
+
+impl Command for HRandField {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["hrandfield"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() || self.args.len() > 3 {
+            return fail(wrong_arity("hrandfield"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            self.resp3 = conn.codec.protocol() == Protocol::Resp3;
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let count = match self.args.get(1) {
+                Some(count) => Some(parse_integer(count)?),
+                None => None,
+            };
+            let with_values = match self.args.get(2) {
+                Some(arg)
+                    if bytes_to_string(arg)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        == "withvalues" =>
+                {
+                    true
+                }
+                Some(_) => return Err(syntax_error()),
+                None => false,
+            };
+
+            let picked = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let Some(hash) = as_hash(value)? else {
+                        return Ok(vec![]);
+                    };
+                    let mut rng = rand::thread_rng();
+                    let picked: Vec<(&Vec<u8>, &Vec<u8>)> = match count {
+                        None => hash.iter().choose(&mut rng).into_iter().collect(),
+                        Some(count) if count >= 0 => {
+                            let mut picked = hash.iter().choose_multiple(&mut rng, count as usize);
+                            picked.shuffle(&mut rng);
+                            picked
+                        }
+                        Some(count) => {
+                            let entries: Vec<_> = hash.iter().collect();
+                            (0..count.unsigned_abs())
+                                .filter_map(|_| entries.choose(&mut rng).copied())
+                                .collect()
+                        }
+                    };
+                    Ok::<_, std::io::Error>(
+                        picked
+                            .into_iter()
+                            .map(|(field, value)| (field.to_vec(), value.to_vec()))
+                            .collect(),
+                    )
+                })
+                .await?;
+
+            let Some(_) = count else {
+                return Ok(match picked.into_iter().next() {
+                    Some((field, _)) => RESPDatatypes::BufBulk(field),
+                    None => RESPDatatypes::NullString,
+                });
+            };
+            let mut reply = Vec::with_capacity(picked.len());
+            for (field, value) in picked {
+                match (with_values, self.resp3) {
+                    (false, _) => reply.push(RESPDatatypes::BufBulk(field)),
+                    (true, false) => {
+                        reply.push(RESPDatatypes::BufBulk(field));
+                        reply.push(RESPDatatypes::BufBulk(value));
+                    }
+                    (true, true) => reply.push(RESPDatatypes::Array(vec![
+                        RESPDatatypes::BufBulk(field),
+                        RESPDatatypes::BufBulk(value),
+                    ])),
+                }
+            }
+            Ok(RESPDatatypes::Array(reply))
+        })
+    }
+}
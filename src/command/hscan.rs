@@ -0,0 +1,78 @@
+use crate::{
+    cache::{
+        core::{scan_hash, scan_page},
+        hash::as_hash,
+    },
+    resp::core::RESPDatatypes,
+};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult},
+    scan::ScanOptions,
+};
+
+/// `HSCAN key cursor [MATCH pattern] [COUNT count]`
+#[derive(Debug, Default)]
+pub struct HScan {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for HScan {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["hscan"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("hscan"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let options = ScanOptions::parse(&self.args[1..], false)?;
+
+            let (next_cursor, page) = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let Some(hash) = as_hash(value)? else {
+                        return Ok((0, vec![]));
+                    };
+                    let fields = hash.iter().map(|field| (scan_hash(field.0), field));
+                    let (next_cursor, page) = scan_page(fields, options.cursor, options.count);
+                    let page: Vec<(Vec<u8>, Vec<u8>)> = page
+                        .into_iter()
+                        .map(|(field, value)| (field.to_vec(), value.to_vec()))
+                        .collect();
+                    Ok::<_, std::io::Error>((next_cursor, page))
+                })
+                .await?;
+
+            let mut reply = Vec::with_capacity(page.len() * 2);
+            for (field, value) in page {
+                if options.matches(&field) {
+                    reply.push(RESPDatatypes::BufBulk(field));
+                    reply.push(RESPDatatypes::BufBulk(value));
+                }
+            }
+            Ok(RESPDatatypes::Array(vec![
+                RESPDatatypes::BulkString(next_cursor.to_string()),
+                RESPDatatypes::Array(reply),
+            ]))
+        })
+    }
+}
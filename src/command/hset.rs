@@ -0,0 +1,88 @@
+use crate::{
+    cache::hash::{as_hash_mut, as_hash_or_create},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `HSET key field value [field value ...]`, along with the older `HMSET` and `HSETNX` which
+/// only sets a field that doesn't exist yet.
+#[derive(Debug, Default)]
+pub struct HSet {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl HSet {
+    fn arity_matches(&self) -> bool {
+        match self.name.as_str() {
+            "hsetnx" => self.args.len() == 3,
+            _ => self.args.len() >= 3 && self.args.len() % 2 == 1,
+        }
+    }
+}
+
+impl Command for HSet {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["hset", "hmset", "hsetnx"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if !self.arity_matches() {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let only_new = self.name == "hsetnx";
+
+            let cache = cache_repo.lock().await;
+            let (written, added) = cache
+                .modify(key, |value| {
+                    if only_new
+                        && as_hash_mut(value)?.is_some_and(|hash| hash.contains_key(&self.args[1]))
+                    {
+                        return Ok((false, 0));
+                    }
+                    let hash = as_hash_or_create(value)?;
+                    let mut added = 0;
+                    for pair in self.args[1..].chunks(2) {
+                        if hash.insert(pair[0].to_vec(), pair[1].to_vec()).is_none() {
+                            added += 1;
+                        }
+                    }
+                    Ok::<_, std::io::Error>((true, added))
+                })
+                .await?;
+
+            if written {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(match self.name.as_str() {
+                "hmset" => RESPDatatypes::SimpleString("OK".to_string()),
+                _ => RESPDatatypes::Integer(added),
+            })
+        })
+    }
+}
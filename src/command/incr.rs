@@ -1,133 +1,94 @@
 use std::io::{self, Error};
 
-use crate::{
-    connections::connection::Connection,
-    errors::value_is_not_type::ValueIsNotType,
-    resp::{
-        core::RESPDatatypes,
-        deserialize::{bytes_to_string, bytes_to_type},
-    },
-};
+use crate::{connections::connection::Connection, resp::core::RESPDatatypes};
 
-use super::core::{Command, RunResult};
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
 
+/// `INCR`, `DECR`, `INCRBY` and `DECRBY`, over the full range of a signed 64 bit integer.
 #[derive(Debug, Default)]
 pub struct Incr {
     pub cmd: Vec<u8>,
-    pub key: String,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
 }
 
 impl Incr {
-    fn new(key: String) -> Self {
-        Incr { key, cmd: vec![] }
+    fn arity_matches(&self) -> bool {
+        match self.name.as_str() {
+            "incr" | "decr" => self.args.len() == 1,
+            _ => self.args.len() == 2,
+        }
     }
+
+    fn delta(&self) -> io::Result<i64> {
+        match self.name.as_str() {
+            "incr" => Ok(1),
+            "decr" => Ok(-1),
+            "incrby" => parse_integer(&self.args[1]),
+            _ => parse_integer(&self.args[1])?
+                .checked_neg()
+                .ok_or_else(overflow_error),
+        }
+    }
+}
+
+pub fn overflow_error() -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        "ERR increment or decrement would overflow",
+    )
 }
 
 impl Command for Incr {
     fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
-        if let RESPDatatypes::Array(arr) = cmd {
-            if arr.len() < 2 {
-                return false;
-            }
-
-            let first_elem = arr.first().unwrap();
-            if let RESPDatatypes::BufBulk(buf) = first_elem {
-                if bytes_to_string(buf)
-                    .unwrap_or("".to_string())
-                    .to_lowercase()
-                    == "incr"
-                {
-                    if let RESPDatatypes::BufBulk(scnd_elem) = arr.get(1).unwrap() {
-                        self.key = bytes_to_string(scnd_elem).unwrap_or("".to_string());
-                        self.cmd = cmd.encode();
-                        return true;
-                    }
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
-        false
+        let Some((name, args)) = parse_args(cmd, &["incr", "decr", "incrby", "decrby"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
     }
 
-    // this implementation is wrong i need to pass only the result;
     fn run(
         &mut self,
         cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
         conn: Option<&mut Connection>,
-    ) -> RunResult {
-        match conn {
-            Some(conn) => {
-                if conn.is_in_transaction() {
-                    conn.add_tnx(Box::new(Self::new(self.key.to_string())));
-                    return Box::pin(async move {
-                        Ok(RESPDatatypes::SimpleString("QUEUED".to_string()))
-                    });
-                }
-                let cmdq = conn.cmdq.clone();
-                let cmd = self.cmd.to_vec();
-                let jh = tokio::spawn(async move {
-                    let mut cmd_queue = cmdq.lock().await;
-                    cmd_queue.add(cmd).await;
-                });
+    ) -> RunResult<'_> {
+        if !self.arity_matches() {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
 
-                Box::pin(async move {
-                    let cache = cache_repo.clone();
-                    let key = self.key.to_string();
-                    let mut val = 1;
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let delta = self.delta()?;
 
-                    if let Some(existing_data) = cache.lock().await.get(key.to_string()).await {
-                        match bytes_to_type::<i32>(&existing_data.to_vec()) {
-                            Ok(existing_val) => {
-                                val = existing_val + 1;
-                            }
-                            Err(err) => {
-                                println!("err: {}", err);
-                                return Err(Error::new(
-                                    io::ErrorKind::InvalidInput,
-                                    ValueIsNotType {
-                                        type_name: "integer".to_string(),
-                                        can_be_out_of_range: Some(true),
-                                    },
-                                ));
-                            }
-                        }
-                    }
+            // the keyspace stays locked until the write is propagated, so the
+            // increment is atomic and lines up with persistence and replication
+            let cache = cache_repo.lock().await;
+            let current = match cache.get(key.to_string()).await? {
+                Some(existing_data) => parse_integer(&existing_data)?,
+                None => 0,
+            };
+            let val = current.checked_add(delta).ok_or_else(overflow_error)?;
 
-                    let value = format!("{}", val).into_bytes();
-                    cache.lock().await.set(key, value).await?;
-                    jh.await?;
-                    Ok(RESPDatatypes::Integer(val))
-                })
+            cache
+                .set_keep_ttl(key, format!("{}", val).into_bytes())
+                .await?;
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
             }
-            None => Box::pin(async move {
-                let cache = cache_repo.clone();
-                let key = self.key.to_string();
-                let mut val = 1;
-
-                if let Some(existing_data) = cache.lock().await.get(key.to_string()).await {
-                    match bytes_to_type::<i32>(&existing_data.to_vec()) {
-                        Ok(existing_val) => {
-                            val = existing_val + 1;
-                        }
-                        Err(err) => {
-                            println!("err: {}", err);
-                            return Err(Error::new(
-                                io::ErrorKind::InvalidInput,
-                                ValueIsNotType {
-                                    type_name: "integer".to_string(),
-                                    can_be_out_of_range: Some(true),
-                                },
-                            ));
-                        }
-                    }
-                }
-
-                let value = format!("{}", val).into_bytes();
-                cache.lock().await.set(key, value).await?;
-                Ok(RESPDatatypes::Integer(val))
-            }),
-        }
+            Ok(RESPDatatypes::Integer(val))
+        })
     }
 }
@@ -0,0 +1,118 @@
+use std::io::{self, Error};
+
+use crate::{
+    resp::{core::RESPDatatypes, deserialize::bytes_to_type},
+    utils::float::format_float_sum,
+};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult},
+    set::{set_command, ExpiryOption},
+};
+
+/// `INCRBYFLOAT key increment`
+///
+/// The result is propagated as a `SET` of the formatted value, so replicas don't redo the
+/// floating point math and end up with a different string.
+#[derive(Debug, Default)]
+pub struct IncrByFloat {
+    pub args: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+pub fn parse_float(arg: &[u8]) -> io::Result<f64> {
+    bytes_to_type::<f64>(arg)
+        .ok()
+        .filter(|val| !val.is_nan())
+        .ok_or_else(|| {
+            Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR value is not a valid float",
+            )
+        })
+}
+
+impl Command for IncrByFloat {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["incrbyfloat"]) else {
+            return false;
+        };
+        if let [key, increment] = args.as_slice() {
+            self.args = Some((key.to_vec(), increment.to_vec()));
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some((key, increment)) = self.args.clone() else {
+            return fail(wrong_arity("incrbyfloat"));
+        };
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let increment_val = parse_float(&increment)?;
+
+            let cache = cache_repo.lock().await;
+            let current = cache.get(key.to_string()).await?.unwrap_or(b"0".to_vec());
+            let val = parse_float(&current)? + increment_val;
+            if !val.is_finite() {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR increment would produce NaN or Infinity",
+                ));
+            }
+
+            let value = format_float_sum(&current, &increment, val).into_bytes();
+            cache.set_keep_ttl(key.to_string(), value.to_vec()).await?;
+            if let Some(propagator) = propagator {
+                propagator
+                    .propagate(set_command(&key, &value, ExpiryOption::Keep))
+                    .await;
+            }
+            Ok(RESPDatatypes::BufBulk(value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{
+        cache::core::CacheRepository, command::core::test_support::run, resp::core::RESPDatatypes,
+    };
+
+    async fn incr(repo: &Arc<Mutex<CacheRepository>>, key: &str, increment: &str) -> Vec<u8> {
+        match run(repo, &["INCRBYFLOAT", key, increment]).await {
+            RESPDatatypes::BufBulk(value) => value,
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+    }
+
+    #[tokio::test]
+    async fn formats_the_result_like_redis() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        run(&repo, &["SET", "f", "2001.1"]).await;
+
+        assert_eq!(incr(&repo, "f", "0.1").await, b"2001.2");
+        assert_eq!(incr(&repo, "f", "-2001.2").await, b"0");
+        assert_eq!(incr(&repo, "new", "1.5e3").await, b"1500");
+        assert_eq!(incr(&repo, "big", "1e20").await, b"100000000000000000000");
+        assert_eq!(incr(&repo, "small", "0.00001").await, b"0.00001");
+        let stored = repo.lock().await.get("f".to_string()).await.unwrap();
+        assert_eq!(stored, Some(b"0".to_vec()));
+    }
+}
@@ -44,14 +44,18 @@ impl Command for Info {
         &mut self,
         _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
         conn: Option<&mut crate::connections::connection::Connection>,
-    ) -> super::core::RunResult {
+    ) -> super::core::RunResult<'_> {
         if let Some(conn) = conn {
             let server_config = conn.server_config.clone();
 
             return Box::pin(async move {
                 if self.sub_command == "replication" {
                     return Ok(RESPDatatypes::BulkString(
-                        server_config.replication_config.to_config_string(),
+                        server_config
+                            .replication_config
+                            .read()
+                            .await
+                            .to_config_string(),
                     ));
                 }
                 Ok(RESPDatatypes::SimpleString("OK".to_string()))
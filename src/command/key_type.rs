@@ -0,0 +1,48 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `TYPE key`
+#[derive(Debug, Default)]
+pub struct KeyType {
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for KeyType {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["type"]) else {
+            return false;
+        };
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity("type"));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let type_name = cache_repo
+                .lock()
+                .await
+                .value_type(key)
+                .await
+                .unwrap_or("none");
+            Ok(RESPDatatypes::SimpleString(type_name.to_string()))
+        })
+    }
+}
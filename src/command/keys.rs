@@ -0,0 +1,47 @@
+use crate::{resp::core::RESPDatatypes, utils::glob::glob_match};
+
+use super::core::{fail, parse_args, queued, wrong_arity, Command, RunResult};
+
+/// `KEYS pattern`
+#[derive(Debug, Default)]
+pub struct Keys {
+    pub pattern: Option<Vec<u8>>,
+}
+
+impl Command for Keys {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["keys"]) else {
+            return false;
+        };
+        if let [pattern] = args.as_slice() {
+            self.pattern = Some(pattern.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(pattern) = self.pattern.clone() else {
+            return fail(wrong_arity("keys"));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let keys = cache_repo.lock().await.keys().await;
+            Ok(RESPDatatypes::Array(
+                keys.into_iter()
+                    .filter(|key| glob_match(&pattern, key.as_bytes()))
+                    .map(RESPDatatypes::BulkString)
+                    .collect(),
+            ))
+        })
+    }
+}
@@ -0,0 +1,42 @@
+use crate::resp::{core::RESPDatatypes, deserialize::bytes_to_string};
+
+use super::core::Command;
+
+#[derive(Default)]
+pub struct LastSave;
+
+impl Command for LastSave {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            if vec.len() != 1 {
+                return false;
+            }
+            let cmd = vec.first().unwrap();
+            match cmd {
+                RESPDatatypes::BufBulk(vec)
+                    if bytes_to_string(vec)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        == "lastsave" =>
+                {
+                    return true;
+                }
+                _ => {
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        _conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        Box::pin(async move {
+            let last_save = *cache_repo.lock().await.last_save.read().await;
+            Ok(RESPDatatypes::Integer(last_save as i64))
+        })
+    }
+}
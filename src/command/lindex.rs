@@ -0,0 +1,63 @@
+use crate::{cache::list::as_list, resp::core::RESPDatatypes};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `LINDEX key index`
+#[derive(Debug, Default)]
+pub struct LIndex {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// The position `index` points to in a list of `len` elements, counting from the end when
+/// negative.
+pub fn list_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+impl Command for LIndex {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["lindex"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 2 {
+            return fail(wrong_arity("lindex"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let index = parse_integer(&self.args[1])?;
+
+            let elem = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_list(value)?.and_then(|list| {
+                        list_index(index, list.len()).map(|index| list[index].to_vec())
+                    }))
+                })
+                .await?;
+            Ok(match elem {
+                Some(elem) => RESPDatatypes::BufBulk(elem),
+                None => RESPDatatypes::NullString,
+            })
+        })
+    }
+}
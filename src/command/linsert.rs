@@ -0,0 +1,79 @@
+use crate::{
+    cache::list::as_list_mut,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_key, queued, syntax_error, wrong_arity, Command, RunResult,
+};
+
+/// `LINSERT key BEFORE | AFTER pivot element`
+#[derive(Debug, Default)]
+pub struct LInsert {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for LInsert {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["linsert"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 4 {
+            return fail(wrong_arity("linsert"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let after = match bytes_to_string(&self.args[1])
+                .unwrap_or("".to_string())
+                .to_lowercase()
+                .as_str()
+            {
+                "before" => false,
+                "after" => true,
+                _ => return Err(syntax_error()),
+            };
+            let pivot = &self.args[2];
+
+            let cache = cache_repo.lock().await;
+            let len = cache
+                .modify(key, |value| {
+                    let Some(list) = as_list_mut(value)? else {
+                        return Ok(0);
+                    };
+                    let Some(idx) = list.iter().position(|elem| elem == pivot) else {
+                        return Ok(-1);
+                    };
+                    list.insert(idx + after as usize, self.args[3].to_vec());
+                    Ok::<_, std::io::Error>(list.len() as i64)
+                })
+                .await?;
+
+            if len > 0 {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::Integer(len))
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{cache::list::as_list, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `LLEN key`
+#[derive(Debug, Default)]
+pub struct LLen {
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for LLen {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["llen"]) else {
+            return false;
+        };
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity("llen"));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let len = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_list(value)?.map_or(0, |list| list.len()))
+                })
+                .await?;
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+
+use crate::{
+    cache::{
+        core::CacheRepository,
+        list::{as_list, as_list_mut, as_list_or_create, pop, push, Side},
+    },
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{
+    fail, parse_args, parse_key, queued, syntax_error, wake_blocked, wrong_arity, Command,
+    RunResult,
+};
+
+/// `LMOVE source destination LEFT | RIGHT LEFT | RIGHT`
+#[derive(Debug, Default)]
+pub struct LMove {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+/// Pops an element off `source` and pushes it to `destination`, `None` when `source` is
+/// empty. Both keys are type checked before anything moves.
+pub async fn move_element(
+    cache: &CacheRepository,
+    source: String,
+    destination: String,
+    from: Side,
+    to: Side,
+) -> io::Result<Option<Vec<u8>>> {
+    cache
+        .view(destination.to_string(), |value| as_list(value).map(|_| ()))
+        .await?;
+    let Some(elem) = cache
+        .modify(source, |value| {
+            Ok::<_, io::Error>(as_list_mut(value)?.and_then(|list| pop(list, from)))
+        })
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    cache
+        .modify(destination, |value| {
+            as_list_or_create(value).map(|list| push(list, to, elem.to_vec()))
+        })
+        .await?;
+    Ok(Some(elem))
+}
+
+/// Parses the `LEFT | RIGHT LEFT | RIGHT` pair of `LMOVE` and `BLMOVE`.
+pub fn parse_sides(from: &[u8], to: &[u8]) -> io::Result<(Side, Side)> {
+    match (Side::parse(from), Side::parse(to)) {
+        (Some(from), Some(to)) => Ok((from, to)),
+        _ => Err(syntax_error()),
+    }
+}
+
+impl Command for LMove {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["lmove"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 4 {
+            return fail(wrong_arity("lmove"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let source = parse_key(&self.args[0])?;
+            let destination = parse_key(&self.args[1])?;
+            let (from, to) = parse_sides(&self.args[2], &self.args[3])?;
+
+            let cache = cache_repo.lock().await;
+            let Some(elem) =
+                move_element(&cache, source, destination.to_string(), from, to).await?
+            else {
+                return Ok(RESPDatatypes::NullString);
+            };
+
+            if let Some(propagator) = propagator.as_ref() {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            wake_blocked(&cache, destination, propagator.as_ref()).await;
+            Ok(RESPDatatypes::BufBulk(elem))
+        })
+    }
+}
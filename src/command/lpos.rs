@@ -0,0 +1,153 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::list::as_list,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]`
+#[derive(Debug, Default)]
+pub struct LPos {
+    pub args: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+struct LPosOptions {
+    rank: i64,
+    /// `None` replies with a single position instead of an array, 0 asks for every match
+    count: Option<usize>,
+    /// how many elements to compare at most, 0 for the whole list
+    max_len: usize,
+}
+
+impl LPos {
+    fn parse_options(&self) -> io::Result<LPosOptions> {
+        let mut options = LPosOptions {
+            rank: 1,
+            count: None,
+            max_len: 0,
+        };
+        let mut args = self.args.iter().skip(2);
+        while let Some(option) = args.next() {
+            let option = bytes_to_string(option)
+                .unwrap_or("".to_string())
+                .to_lowercase();
+            let value = parse_integer(args.next().ok_or_else(syntax_error)?)?;
+            match option.as_str() {
+                "rank" => {
+                    if value == 0 {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list",
+                        ));
+                    }
+                    options.rank = value;
+                }
+                "count" => {
+                    if value < 0 {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR COUNT can't be negative",
+                        ));
+                    }
+                    options.count = Some(value as usize);
+                }
+                "maxlen" => {
+                    if value < 0 {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR MAXLEN can't be negative",
+                        ));
+                    }
+                    options.max_len = value as usize;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl Command for LPos {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["lpos"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("lpos"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let elem = &self.args[1];
+            let options = self.parse_options()?;
+
+            let positions = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let Some(list) = as_list(value)? else {
+                        return Ok(vec![]);
+                    };
+                    let scanned = match options.max_len {
+                        0 => list.len(),
+                        max_len => max_len.min(list.len()),
+                    };
+                    let wanted = match options.count {
+                        Some(0) => usize::MAX,
+                        Some(count) => count,
+                        None => 1,
+                    };
+                    let skipped = options.rank.unsigned_abs() as usize - 1;
+
+                    // a negative rank scans from the tail, positions still count from the head
+                    let indexes: Box<dyn Iterator<Item = usize>> = if options.rank > 0 {
+                        Box::new(0..scanned)
+                    } else {
+                        Box::new((list.len() - scanned..list.len()).rev())
+                    };
+                    Ok::<_, io::Error>(
+                        indexes
+                            .filter(|idx| list[*idx] == *elem)
+                            .skip(skipped)
+                            .take(wanted)
+                            .collect::<Vec<usize>>(),
+                    )
+                })
+                .await?;
+
+            Ok(match options.count {
+                Some(_) => RESPDatatypes::Array(
+                    positions
+                        .into_iter()
+                        .map(|idx| RESPDatatypes::Integer(idx as i64))
+                        .collect(),
+                ),
+                None => match positions.first() {
+                    Some(idx) => RESPDatatypes::Integer(*idx as i64),
+                    None => RESPDatatypes::NullString,
+                },
+            })
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{cache::list::as_list, resp::core::RESPDatatypes, utils::range::clamp_range};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `LRANGE key start stop`
+#[derive(Debug, Default)]
+pub struct LRange {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for LRange {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["lrange"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("lrange"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let start = parse_integer(&self.args[1])?;
+            let stop = parse_integer(&self.args[2])?;
+
+            let elems = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let Some(list) = as_list(value)? else {
+                        return Ok(vec![]);
+                    };
+                    Ok::<_, std::io::Error>(match clamp_range(start, stop, list.len()) {
+                        Some((start, stop)) => list
+                            .range(start..=stop)
+                            .cloned()
+                            .map(RESPDatatypes::BufBulk)
+                            .collect(),
+                        None => vec![],
+                    })
+                })
+                .await?;
+            Ok(RESPDatatypes::Array(elems))
+        })
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{cache::list::as_list_mut, resp::core::RESPDatatypes};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `LREM key count element`, removing from the head when `count` is positive, from the tail
+/// when negative and everywhere when 0.
+#[derive(Debug, Default)]
+pub struct LRem {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for LRem {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["lrem"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("lrem"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let count = parse_integer(&self.args[1])?;
+            let elem = &self.args[2];
+
+            let cache = cache_repo.lock().await;
+            let removed = cache
+                .modify(key, |value| {
+                    let Some(list) = as_list_mut(value)? else {
+                        return Ok(0);
+                    };
+                    let limit = match count {
+                        0 => usize::MAX,
+                        count => count.unsigned_abs() as usize,
+                    };
+
+                    let mut positions: Vec<usize> =
+                        (0..list.len()).filter(|idx| list[*idx] == *elem).collect();
+                    if count < 0 {
+                        positions.reverse();
+                    }
+                    positions.truncate(limit);
+                    positions.sort_unstable();
+                    for idx in positions.iter().rev() {
+                        list.remove(*idx);
+                    }
+                    Ok::<_, std::io::Error>(positions.len())
+                })
+                .await?;
+
+            if removed > 0 {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::Integer(removed as i64))
+        })
+    }
+}
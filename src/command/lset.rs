@@ -0,0 +1,71 @@
+use std::io::{self, Error};
+
+use crate::{cache::list::as_list_mut, resp::core::RESPDatatypes};
+
+use super::{
+    core::{fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult},
+    lindex::list_index,
+};
+
+/// `LSET key index element`
+#[derive(Debug, Default)]
+pub struct LSet {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for LSet {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["lset"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("lset"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let index = parse_integer(&self.args[1])?;
+
+            let cache = cache_repo.lock().await;
+            cache
+                .modify(key, |value| {
+                    let Some(list) = as_list_mut(value)? else {
+                        return Err(Error::new(io::ErrorKind::NotFound, "ERR no such key"));
+                    };
+                    let Some(index) = list_index(index, list.len()) else {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR index out of range",
+                        ));
+                    };
+                    list[index] = self.args[2].to_vec();
+                    Ok(())
+                })
+                .await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
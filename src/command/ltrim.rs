@@ -0,0 +1,71 @@
+use crate::{cache::list::as_list_mut, resp::core::RESPDatatypes, utils::range::clamp_range};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `LTRIM key start stop`
+#[derive(Debug, Default)]
+pub struct LTrim {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for LTrim {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["ltrim"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("ltrim"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let start = parse_integer(&self.args[1])?;
+            let stop = parse_integer(&self.args[2])?;
+
+            let cache = cache_repo.lock().await;
+            let trimmed = cache
+                .modify(key, |value| {
+                    let Some(list) = as_list_mut(value)? else {
+                        return Ok(false);
+                    };
+                    match clamp_range(start, stop, list.len()) {
+                        Some((start, stop)) => {
+                            list.truncate(stop + 1);
+                            list.drain(..start);
+                        }
+                        None => list.clear(),
+                    }
+                    Ok::<_, std::io::Error>(true)
+                })
+                .await?;
+
+            if trimmed {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
@@ -0,0 +1,54 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `MGET key [key ...]`
+#[derive(Debug, Default)]
+pub struct MGet {
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl Command for MGet {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["mget"]) else {
+            return false;
+        };
+        self.keys = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.keys.is_empty() {
+            return fail(wrong_arity("mget"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let keys = self
+                .keys
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            // every value is read under the same lock, so the reply is a consistent snapshot
+            let cache = cache_repo.lock().await;
+            let mut values = Vec::with_capacity(keys.len());
+            for key in keys {
+                // keys holding anything but a string read as missing rather than failing
+                values.push(match cache.get(key.to_string()).await {
+                    Ok(Some(value)) => RESPDatatypes::BufBulk(value),
+                    _ => RESPDatatypes::NullString,
+                });
+            }
+            Ok(RESPDatatypes::Array(values))
+        })
+    }
+}
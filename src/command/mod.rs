@@ -1,12 +1,98 @@
+pub mod append;
+pub mod bgrewriteaof;
+pub mod bgsave;
+pub mod bitcount;
+pub mod bitfield;
+pub mod bitop;
+pub mod bitpos;
+pub mod blmove;
+pub mod blpop;
+pub mod bzpop;
 pub mod core;
+pub mod del;
 pub mod discard;
 pub mod echo;
 pub mod exec;
+pub mod exists;
+pub mod expire;
 pub mod get;
+pub mod getbit;
+pub mod getdel;
+pub mod getex;
+pub mod getrange;
+pub mod hdel;
+pub mod hello;
+pub mod hget;
+pub mod hgetall;
+pub mod hincrby;
+pub mod hincrbyfloat;
+pub mod hlen;
+pub mod hmget;
+pub mod hrandfield;
+pub mod hscan;
+pub mod hset;
 pub mod incr;
+pub mod incrbyfloat;
 pub mod info;
+pub mod key_type;
+pub mod keys;
+pub mod lastsave;
+pub mod lindex;
+pub mod linsert;
+pub mod llen;
+pub mod lmove;
+pub mod lpos;
+pub mod lrange;
+pub mod lrem;
+pub mod lset;
+pub mod ltrim;
+pub mod mget;
+pub mod mset;
 pub mod multi;
+pub mod persist;
 pub mod ping;
+pub mod pop;
 pub mod psync;
+pub mod push;
+pub mod rename;
 pub mod replconf;
+pub mod replicaof;
+pub mod sadd;
+pub mod save;
+pub mod scan;
+pub mod scard;
 pub mod set;
+pub mod setbit;
+pub mod setop;
+pub mod setrange;
+pub mod sintercard;
+pub mod sismember;
+pub mod smembers;
+pub mod smove;
+pub mod spop;
+pub mod srandmember;
+pub mod srem;
+pub mod sscan;
+pub mod strlen;
+pub mod ttl;
+pub mod wait;
+pub mod xack;
+pub mod xadd;
+pub mod xautoclaim;
+pub mod xclaim;
+pub mod xgroup;
+pub mod xinfo;
+pub mod xlen;
+pub mod xpending;
+pub mod xrange;
+pub mod xread;
+pub mod xsetid;
+pub mod zadd;
+pub mod zcount;
+pub mod zincrby;
+pub mod zpop;
+pub mod zrange;
+pub mod zrank;
+pub mod zrem;
+pub mod zscore;
+pub mod zunionstore;
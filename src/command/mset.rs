@@ -0,0 +1,76 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `MSET key value [key value ...]` and `MSETNX`, which sets nothing when any key exists.
+#[derive(Debug, Default)]
+pub struct MSet {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub pairs: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Command for MSet {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["mset", "msetnx"]) else {
+            return false;
+        };
+        self.name = name;
+        if args.len() % 2 == 0 {
+            self.pairs = args
+                .chunks(2)
+                .map(|pair| (pair[0].to_vec(), pair[1].to_vec()))
+                .collect();
+        }
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.pairs.is_empty() {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let pairs = self
+                .pairs
+                .iter()
+                .map(|(key, value)| Ok((parse_key(key)?, value)))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            // the keyspace stays locked across every key, and the command is propagated
+            // whole, so no client or replica ever sees half of it applied
+            let cache = cache_repo.lock().await;
+            if self.name == "msetnx" {
+                for (key, _) in pairs.iter() {
+                    if cache.exists(key.to_string()).await {
+                        return Ok(RESPDatatypes::Integer(0));
+                    }
+                }
+            }
+
+            for (key, value) in pairs {
+                cache.set(key.to_string(), value.to_vec()).await?;
+            }
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+
+            if self.name == "msetnx" {
+                return Ok(RESPDatatypes::Integer(1));
+            }
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
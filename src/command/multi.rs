@@ -33,7 +33,7 @@ impl Command for Multi {
         &mut self,
         _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
         conn: Option<&mut crate::connections::connection::Connection>,
-    ) -> super::core::RunResult {
+    ) -> super::core::RunResult<'_> {
         if let Some(conn) = conn {
             conn.enable_transaction();
         }
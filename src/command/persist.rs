@@ -0,0 +1,53 @@
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `PERSIST key`
+#[derive(Debug, Default)]
+pub struct Persist {
+    pub cmd: Vec<u8>,
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for Persist {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["persist"]) else {
+            return false;
+        };
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity("persist"));
+        };
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let cache = cache_repo.lock().await;
+            if !cache.persist(key).await {
+                return Ok(RESPDatatypes::Integer(0));
+            }
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(1))
+        })
+    }
+}
@@ -46,7 +46,7 @@ impl Command for Ping {
         &mut self,
         _cache_repo: Arc<Mutex<CacheRepository>>,
         _conn: Option<&mut Connection>,
-    ) -> RunResult {
+    ) -> RunResult<'_> {
         Box::pin(async move { Ok(self.get_output()) })
     }
 }
@@ -0,0 +1,101 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::list::{as_list_mut, pop, Side},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `LPOP` and `RPOP key [count]`
+#[derive(Debug, Default)]
+pub struct Pop {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for Pop {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["lpop", "rpop"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() || self.args.len() > 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let side = if self.name == "lpop" {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            let count = match self.args.get(1) {
+                Some(count) => {
+                    let count = parse_integer(count)?;
+                    if count < 0 {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR value is out of range, must be positive",
+                        ));
+                    }
+                    Some(count as usize)
+                }
+                None => None,
+            };
+
+            let cache = cache_repo.lock().await;
+            let popped = cache
+                .modify(key, |value| {
+                    let Some(list) = as_list_mut(value)? else {
+                        return Ok(None);
+                    };
+                    let popped: Vec<Vec<u8>> = (0..count.unwrap_or(1))
+                        .map_while(|_| pop(list, side))
+                        .collect();
+                    Ok::<_, io::Error>(Some(popped))
+                })
+                .await?;
+
+            if popped.as_ref().is_some_and(|popped| !popped.is_empty()) {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+
+            Ok(match (popped, count) {
+                (None, Some(_)) => RESPDatatypes::NullArray,
+                (None, None) => RESPDatatypes::NullString,
+                (Some(popped), Some(_)) => {
+                    RESPDatatypes::Array(popped.into_iter().map(RESPDatatypes::BufBulk).collect())
+                }
+                (Some(mut popped), None) => match popped.pop() {
+                    Some(elem) => RESPDatatypes::BufBulk(elem),
+                    None => RESPDatatypes::NullString,
+                },
+            })
+        })
+    }
+}
@@ -1,8 +1,16 @@
-use crate::resp::{core::RESPDatatypes, deserialize::bytes_to_string};
+use crate::{
+    connections::connection::SlaveConfig,
+    rdb::{self, encode::RdbEncoder},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
 
 use super::core::Command;
 
-pub struct Psync;
+#[derive(Debug, Default)]
+pub struct Psync {
+    pub repl_id: String,
+    pub offset: String,
+}
 
 impl Command for Psync {
     fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
@@ -13,34 +21,135 @@ impl Command for Psync {
             let cmd = vec.first().unwrap();
             match cmd {
                 RESPDatatypes::BufBulk(vec)
-                    if bytes_to_string(&vec.to_vec()).unwrap_or("".to_string()) == "PSYNC" =>
-                {
-                    return true;
-                }
+                    if bytes_to_string(&vec.to_vec()).unwrap_or("".to_string()) == "PSYNC" => {}
                 _ => {
                     return false;
                 }
             }
+
+            if let (RESPDatatypes::BufBulk(repl_id), RESPDatatypes::BufBulk(offset)) =
+                (vec.get(1).unwrap(), vec.get(2).unwrap())
+            {
+                self.repl_id = bytes_to_string(repl_id).unwrap_or("".to_string());
+                self.offset = bytes_to_string(offset).unwrap_or("".to_string());
+                return true;
+            }
         }
         false
     }
 
-    fn run(
-        &mut self,
-        _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
-        conn: Option<&mut crate::connections::connection::Connection>,
-    ) -> super::core::RunResult {
+    fn run<'a>(
+        &'a mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&'a mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'a> {
         let conn = conn.unwrap();
-        conn.send_rdb_file = Some(());
-        let server_replication_config = conn.server_config.replication_config.clone();
 
-        let replication_id = server_replication_config
-            .master_repl_id
-            .unwrap_or("8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb".to_string());
-        let replication_offset = server_replication_config.master_repl_offset.unwrap_or(0);
+        Box::pin(async move {
+            // the keyspace and the backlog are locked together, so the snapshot and the
+            // offset the replica continues from describe the same point of the stream
+            let cache = cache_repo.lock().await;
+            let cmdq = conn.cmdq.clone();
+            let mut cmdq = cmdq.lock().await;
+            let replication_id = conn
+                .server_config
+                .replication_config
+                .read()
+                .await
+                .master_repl_id
+                .to_string();
+
+            let slave_config = conn
+                .slave_config
+                .get_or_insert_with(|| SlaveConfig::new(String::new()));
+            conn.is_replica = true;
+            slave_config.repl_id = replication_id.to_string();
+            cmdq.register_replica(&slave_config.ack_offset);
+
+            // the replica asks for the first byte it is missing, one past what it has applied
+            let continue_from = match self.offset.parse::<u64>() {
+                Ok(offset) if self.repl_id == replication_id && offset > 0 => Some(offset - 1),
+                _ => None,
+            };
+            if let Some(offset) = continue_from {
+                if cmdq.backlog.read_from(offset).is_some() {
+                    slave_config.offset = offset;
+                    return Ok(RESPDatatypes::SimpleString(format!(
+                        "CONTINUE {}",
+                        replication_id
+                    )));
+                }
+            }
+
+            let entries = rdb::core::snapshot(&cache).await;
+            let replication_offset = cmdq.backlog.offset();
+            slave_config.offset = replication_offset;
+            conn.send_rdb_file = Some(RdbEncoder::default().encode(&entries));
+
+            Ok(RESPDatatypes::SimpleString(format!(
+                "FULLRESYNC {} {}",
+                replication_id, replication_offset
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{
+        cache::core::CacheRepository,
+        cmd_queue::backlog::Backlog,
+        command::core::test_support::{client, send},
+    };
+
+    /// Sends `PSYNC` to a master whose backlog holds `written` bytes and keeps the last
+    /// `capacity` of them, returning the reply and where the replica continues from.
+    async fn psync(repl_id: &str, offset: &str, written: usize, capacity: usize) -> (String, u64) {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), None).await;
+        {
+            let mut cmdq = conn.cmdq.lock().await;
+            cmdq.backlog = Backlog::new(capacity);
+            cmdq.backlog.push(&vec![b'x'; written]);
+        }
+
+        let reply = send(&mut conn, &repo, &["PSYNC", repl_id, offset]).await;
+        let reply = String::from_utf8(reply).unwrap();
+        assert_eq!(
+            conn.send_rdb_file.is_some(),
+            reply.starts_with("+FULLRESYNC")
+        );
+        (reply, conn.slave_config.unwrap().offset)
+    }
+
+    #[tokio::test]
+    async fn continues_from_the_first_missing_byte() {
+        let id = "0".repeat(40);
+        let continued = format!("+CONTINUE {}\r\n", id);
+
+        assert_eq!(psync(&id, "21", 20, 8).await, (continued.clone(), 20));
+        assert_eq!(psync(&id, "14", 20, 8).await, (continued.clone(), 13));
+        // the oldest byte held is the 13th, so the replica must have the 12 before it
+        assert_eq!(psync(&id, "13", 20, 8).await, (continued, 12));
+    }
 
-        let message = format!("FULLRESYNC {} {}", replication_id, replication_offset);
+    #[tokio::test]
+    async fn resyncs_when_the_stream_cannot_continue() {
+        let id = "0".repeat(40);
+        let resync = format!("+FULLRESYNC {} 20\r\n", id);
 
-        Box::pin(async move { Ok(RESPDatatypes::SimpleString(message)) })
+        // before the window, past the end, another history, or no history at all
+        assert_eq!(psync(&id, "12", 20, 8).await, (resync.clone(), 20));
+        assert_eq!(psync(&id, "22", 20, 8).await, (resync.clone(), 20));
+        assert_eq!(
+            psync(&"1".repeat(40), "21", 20, 8).await,
+            (resync.clone(), 20)
+        );
+        assert_eq!(psync("?", "-1", 20, 8).await, (resync.clone(), 20));
+        assert_eq!(psync(&id, "0", 20, 8).await, (resync, 20));
     }
 }
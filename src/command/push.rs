@@ -0,0 +1,83 @@
+use crate::{
+    cache::list::{as_list_mut, as_list_or_create, push, Side},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{
+    fail, parse_args, parse_key, queued, wake_blocked, wrong_arity, Command, RunResult,
+};
+
+/// `LPUSH` and `RPUSH key element [element ...]`, along with `LPUSHX` and `RPUSHX` which only
+/// push to a list that already exists.
+#[derive(Debug, Default)]
+pub struct Push {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for Push {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["lpush", "rpush", "lpushx", "rpushx"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let side = if self.name.starts_with('l') {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            let only_existing = self.name.ends_with('x');
+
+            let cache = cache_repo.lock().await;
+            let len = cache
+                .modify(key.to_string(), |value| {
+                    let list = if only_existing {
+                        match as_list_mut(value)? {
+                            Some(list) => list,
+                            None => return Ok(0),
+                        }
+                    } else {
+                        as_list_or_create(value)?
+                    };
+                    for elem in self.args.iter().skip(1) {
+                        push(list, side, elem.to_vec());
+                    }
+                    Ok::<_, std::io::Error>(list.len())
+                })
+                .await?;
+
+            if len > 0 {
+                if let Some(propagator) = propagator.as_ref() {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+                wake_blocked(&cache, key, propagator.as_ref()).await;
+            }
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::{self, Error};
+
+use crate::resp::core::RESPDatatypes;
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `RENAME key newkey` and `RENAMENX key newkey`, which leaves an existing `newkey` alone.
+#[derive(Debug, Default)]
+pub struct Rename {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub keys: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Rename {
+    fn is_nx(&self) -> bool {
+        self.name == "renamenx"
+    }
+}
+
+impl Command for Rename {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["rename", "renamenx"]) else {
+            return false;
+        };
+        self.name = name;
+        if let [from, to] = args.as_slice() {
+            self.keys = Some((from.to_vec(), to.to_vec()));
+        }
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some((from, to)) = self.keys.clone() else {
+            return fail(wrong_arity(&self.name));
+        };
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let (from, to) = (parse_key(&from)?, parse_key(&to)?);
+            let cache = cache_repo.lock().await;
+            if !cache.exists(from.to_string()).await {
+                return Err(Error::new(io::ErrorKind::NotFound, "ERR no such key"));
+            }
+            if self.is_nx() && cache.exists(to.to_string()).await {
+                return Ok(RESPDatatypes::Integer(0));
+            }
+
+            cache.rename(from, to).await?;
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+
+            if self.is_nx() {
+                return Ok(RESPDatatypes::Integer(1));
+            }
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
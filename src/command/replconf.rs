@@ -1,5 +1,3 @@
-use std::time::Instant;
-
 use crate::{
     connections::connection::SlaveConfig,
     resp::{core::RESPDatatypes, deserialize::bytes_to_string},
@@ -48,39 +46,30 @@ impl Command for ReplConf {
         &mut self,
         _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
         conn: Option<&mut crate::connections::connection::Connection>,
-    ) -> super::core::RunResult {
+    ) -> super::core::RunResult<'_> {
         if self.conf_type != "listening-port" && self.conf_type != "GETACK" {
             if let Some(conn) = conn {
-                conn.slave_config.replace(SlaveConfig {
-                    port: self.conf_data.to_string(),
-                    exp: Instant::now(),
-                    last_cmd_id: None,
-                    send_output: None,
-                    bytes_offset: 0,
-                });
+                conn.slave_config
+                    .replace(SlaveConfig::new(self.conf_data.to_string()));
             }
         } else if self.conf_type == "GETACK" {
-            println!("should be callled");
-            let mut bytes_size = 0;
+            let mut replication_config = None;
             if let Some(conn) = conn {
-                if let Some(slave_config) = conn.slave_config.as_mut() {
-                    slave_config.send_output.replace(true);
-                    bytes_size = slave_config.bytes_offset;
-                } else {
-                    conn.slave_config.replace(SlaveConfig {
-                        port: "4000".to_string(),
-                        exp: Instant::now(),
-                        last_cmd_id: None,
-                        send_output: Some(true),
-                        bytes_offset: 0,
-                    });
-                }
+                conn.slave_config
+                    .get_or_insert_with(|| SlaveConfig::new("4000".to_string()))
+                    .send_output
+                    .replace(true);
+                replication_config = Some(conn.server_config.replication_config.clone());
             }
             return Box::pin(async move {
+                let mut offset = 0;
+                if let Some(replication_config) = replication_config {
+                    offset = replication_config.read().await.master_repl_offset;
+                }
                 Ok(RESPDatatypes::Array(vec![
                     RESPDatatypes::BulkString("REPLCONF".to_string()),
                     RESPDatatypes::BulkString("ACK".to_string()),
-                    RESPDatatypes::BulkString(format!("{}", bytes_size)),
+                    RESPDatatypes::BulkString(format!("{}", offset)),
                 ]))
             });
         }
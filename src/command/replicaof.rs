@@ -0,0 +1,113 @@
+use std::io::{self, Error};
+
+use crate::{
+    cli::core::{generate_repl_id, Roles},
+    connections::master_link::MasterLink,
+    errors::value_is_not_type::ValueIsNotType,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::Command;
+
+/// `REPLICAOF host port` and `REPLICAOF NO ONE`, also known as `SLAVEOF`.
+#[derive(Debug, Default)]
+pub struct ReplicaOf {
+    pub host: String,
+    pub port: String,
+}
+
+impl ReplicaOf {
+    fn is_no_one(&self) -> bool {
+        self.host.to_lowercase() == "no" && self.port.to_lowercase() == "one"
+    }
+}
+
+impl Command for ReplicaOf {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            if vec.len() != 3 {
+                return false;
+            }
+            let mut args = Vec::with_capacity(vec.len());
+            for elem in vec {
+                match elem {
+                    RESPDatatypes::BufBulk(buf) => {
+                        args.push(bytes_to_string(buf).unwrap_or("".to_string()))
+                    }
+                    _ => return false,
+                }
+            }
+            match args[0].to_lowercase().as_str() {
+                "replicaof" | "slaveof" => {}
+                _ => return false,
+            }
+            self.host = args[1].to_string();
+            self.port = args[2].to_string();
+            return true;
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        let Some(conn) = conn else {
+            return Box::pin(async move {
+                Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR REPLICAOF is not allowed in this context",
+                ))
+            });
+        };
+        let config = conn.server_config.clone();
+        let cmdq = conn.cmdq.clone();
+
+        Box::pin(async move {
+            if self.is_no_one() {
+                let cmdq = cmdq.lock().await;
+                let mut replication_config = config.replication_config.write().await;
+                if let Roles::Slave(_) = replication_config.role {
+                    MasterLink::stop(&mut replication_config);
+                    // writes accepted from now on make a new history, which replicas of the
+                    // old master can't continue from
+                    replication_config.role = Roles::Master;
+                    replication_config.master_repl_id = generate_repl_id();
+                    replication_config.master_repl_offset = cmdq.backlog.offset();
+                    println!("promoted to master");
+                }
+                return Ok(RESPDatatypes::SimpleString("OK".to_string()));
+            }
+
+            if self.port.parse::<u16>().is_err() {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    ValueIsNotType {
+                        type_name: "integer".to_string(),
+                        can_be_out_of_range: Some(true),
+                    },
+                ));
+            }
+            let addr = format!("{} {}", self.host, self.port);
+            {
+                let mut replication_config = config.replication_config.write().await;
+                if let Roles::Slave(current) = &replication_config.role {
+                    if *current == addr {
+                        return Ok(RESPDatatypes::SimpleString(
+                            "OK Already connected to specified master".to_string(),
+                        ));
+                    }
+                }
+                MasterLink::stop(&mut replication_config);
+                replication_config.role = Roles::Slave(addr.to_string());
+            }
+
+            println!("replicating {}", addr);
+            MasterLink::new(addr, config, cache_repo, cmdq)
+                .spawn()
+                .await;
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{cache::set::as_set_or_create, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `SADD key member [member ...]`
+#[derive(Debug, Default)]
+pub struct SAdd {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SAdd {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["sadd"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("sadd"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+
+            let cache = cache_repo.lock().await;
+            let added = cache
+                .modify(key, |value| {
+                    let set = as_set_or_create(value)?;
+                    Ok::<_, std::io::Error>(
+                        self.args[1..]
+                            .iter()
+                            .filter(|member| set.insert(member.to_vec()))
+                            .count(),
+                    )
+                })
+                .await?;
+
+            if added > 0 {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::Integer(added as i64))
+        })
+    }
+}
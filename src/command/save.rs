@@ -0,0 +1,69 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::core::unix_time_secs,
+    rdb::core::{save_to_file, snapshot},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::Command;
+
+#[derive(Default)]
+pub struct Save;
+
+impl Command for Save {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            if vec.len() != 1 {
+                return false;
+            }
+            let cmd = vec.first().unwrap();
+            match cmd {
+                RESPDatatypes::BufBulk(vec)
+                    if bytes_to_string(vec)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        == "save" =>
+                {
+                    return true;
+                }
+                _ => {
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        let Some(conn) = conn else {
+            return Box::pin(async move {
+                Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR SAVE is not allowed in this context",
+                ))
+            });
+        };
+        let path = conn.server_config.persistence_config.rdb_path();
+
+        Box::pin(async move {
+            // the keyspace stays locked for the whole save, like the blocking save of redis
+            let repo = cache_repo.lock().await;
+            if *repo.bgsave_in_progress.lock().await {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR Background save already in progress",
+                ));
+            }
+
+            let entries = snapshot(&repo).await;
+            save_to_file(&path, &entries).await?;
+            *repo.last_save.write().await = unix_time_secs();
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::{self, Error};
+
+use crate::{
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+    utils::glob::glob_match,
+};
+
+use super::core::{fail, parse_args, queued, syntax_error, wrong_arity, Command, RunResult};
+
+const DEFAULT_COUNT: usize = 10;
+
+/// `SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]`
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// The cursor and options shared by `SCAN`, `HSCAN`, `SSCAN` and `ZSCAN`.
+#[derive(Debug)]
+pub struct ScanOptions {
+    pub cursor: u64,
+    pub pattern: Option<Vec<u8>>,
+    pub count: usize,
+    /// only `SCAN` takes a `TYPE` filter
+    pub type_name: Option<String>,
+}
+
+impl ScanOptions {
+    /// Parses `cursor [MATCH pattern] [COUNT count]`, and `[TYPE type]` when `with_type`.
+    pub fn parse(args: &[Vec<u8>], with_type: bool) -> io::Result<ScanOptions> {
+        let mut args = args.iter();
+        let cursor = args
+            .next()
+            .and_then(|cursor| bytes_to_string(cursor).ok())
+            .and_then(|cursor| cursor.parse::<u64>().ok())
+            .ok_or_else(|| Error::new(io::ErrorKind::InvalidInput, "ERR invalid cursor"))?;
+
+        let mut options = ScanOptions {
+            cursor,
+            pattern: None,
+            count: DEFAULT_COUNT,
+            type_name: None,
+        };
+        while let Some(option) = args.next() {
+            let option = bytes_to_string(option)
+                .unwrap_or("".to_string())
+                .to_lowercase();
+            let value = args.next().ok_or_else(syntax_error)?;
+            match option.as_str() {
+                "match" => options.pattern = Some(value.to_vec()),
+                "count" => {
+                    options.count = bytes_to_string(value)
+                        .ok()
+                        .and_then(|count| count.parse::<usize>().ok())
+                        .filter(|count| *count > 0)
+                        .ok_or_else(syntax_error)?
+                }
+                "type" if with_type => {
+                    options.type_name = Some(
+                        bytes_to_string(value)
+                            .unwrap_or("".to_string())
+                            .to_lowercase(),
+                    )
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Whether `item` passes the `MATCH` pattern.
+    pub fn matches(&self, item: &[u8]) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| glob_match(pattern, item))
+    }
+}
+
+impl Command for Scan {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["scan"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() {
+            return fail(wrong_arity("scan"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let options = ScanOptions::parse(&self.args, true)?;
+            let cache = cache_repo.lock().await;
+            let (next_cursor, keys) = cache.scan(options.cursor, options.count).await;
+
+            // like redis, filters apply to the page, so a page can come back short or empty
+            let mut page = Vec::with_capacity(keys.len());
+            for key in keys {
+                if !options.matches(key.as_bytes()) {
+                    continue;
+                }
+                if let Some(type_name) = options.type_name.as_ref() {
+                    if cache.value_type(key.to_string()).await != Some(type_name.as_str()) {
+                        continue;
+                    }
+                }
+                page.push(RESPDatatypes::BulkString(key));
+            }
+
+            Ok(RESPDatatypes::Array(vec![
+                RESPDatatypes::BulkString(next_cursor.to_string()),
+                RESPDatatypes::Array(page),
+            ]))
+        })
+    }
+}
@@ -1,190 +1,393 @@
+use std::io::{self, Error};
+
 use tokio::sync::Mutex;
 
 use crate::{
+    cache::core::{unix_millis_to_instant, unix_time_millis, CacheRepository},
+    cmd_queue::core::Propagator,
     connections::connection::Connection,
-    resp::{
-        core::RESPDatatypes,
-        deserialize::{bytes_to_string, bytes_to_type},
-    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
 };
 
-use super::core::{Command, RunResult};
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
 
+/// `SET key value [NX | XX] [GET] [EX | PX | EXAT | PXAT time | KEEPTTL]`, along with the
+/// older forms built on it: `SETNX`, `SETEX`, `PSETEX` and `GETSET`.
 #[derive(Debug, Default)]
 pub struct Set {
-    pub cmd: Vec<u8>,
-    pub key: String,
-    pub value: Vec<u8>,
-    pub expiry_ttl: Option<u64>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
 }
 
-impl Set {
-    fn new(key: String, value: Vec<u8>, expiry_ttl: Option<u64>) -> Self {
-        Set {
-            key,
-            value,
-            expiry_ttl,
-            cmd: vec![],
-        }
-    }
-    fn get_key(&self) -> String {
-        self.key.to_string()
-    }
+/// What a write does to the deadline of the key it replaces.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ExpiryOption {
+    #[default]
+    Clear,
+    Keep,
+    /// milliseconds since the unix epoch
+    At(u64),
+}
 
-    fn get_value(&self) -> Vec<u8> {
-        self.value.to_vec()
-    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Condition {
+    Nx,
+    Xx,
+}
 
-    fn is_first_elem_is_set_cmd(&mut self, vec: &Vec<RESPDatatypes>) -> bool {
-        if let Some(first_elem) = vec.first() {
-            return match first_elem {
-                RESPDatatypes::BufBulk(buff) => {
-                    bytes_to_string(buff)
-                        .unwrap_or("".to_string())
-                        .to_lowercase()
-                        == "set"
-                }
-                _ => false,
-            };
-        }
-        false
+#[derive(Debug, Default)]
+struct SetOptions {
+    key: String,
+    value: Vec<u8>,
+    expiry: ExpiryOption,
+    condition: Option<Condition>,
+    get: bool,
+}
+
+/// Turns the time given to `EX`, `PX`, `EXAT` or `PXAT` into an absolute deadline in
+/// milliseconds since the unix epoch.
+pub fn parse_expiry(cmd: &str, unit: &str, time: &[u8]) -> io::Result<u64> {
+    let time = parse_integer(time)?;
+
+    let now = unix_time_millis() as i64;
+    let deadline = match unit {
+        "ex" => time
+            .checked_mul(1000)
+            .and_then(|time| time.checked_add(now)),
+        "px" => time.checked_add(now),
+        "exat" => time.checked_mul(1000),
+        _ => Some(time),
+    };
+    match deadline {
+        Some(deadline) if time > 0 && deadline > 0 => Ok(deadline as u64),
+        _ => Err(Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("ERR invalid expire time in '{}' command", cmd),
+        )),
     }
+}
 
-    fn set_key_when_parsable(&mut self, vec: &Vec<RESPDatatypes>) -> bool {
-        if let Some(first_elem) = vec.get(1) {
-            return match first_elem {
-                RESPDatatypes::BufBulk(buff) => {
-                    let key = bytes_to_string(buff).unwrap_or("".to_string());
-                    if !key.is_empty() {
-                        self.key = key;
-                        return true;
-                    }
-                    false
-                }
-                _ => false,
-            };
+/// The command replicas and the append only file get for a write that set `value` with
+/// `expiry`, which always carries an absolute deadline.
+pub fn set_command(key: &str, value: &[u8], expiry: ExpiryOption) -> Vec<u8> {
+    let mut cmd = vec![
+        RESPDatatypes::BulkString("SET".to_string()),
+        RESPDatatypes::BufBulk(key.as_bytes().to_vec()),
+        RESPDatatypes::BufBulk(value.to_vec()),
+    ];
+    match expiry {
+        ExpiryOption::Clear => {}
+        ExpiryOption::Keep => cmd.push(RESPDatatypes::BulkString("KEEPTTL".to_string())),
+        ExpiryOption::At(deadline) => {
+            cmd.push(RESPDatatypes::BulkString("PXAT".to_string()));
+            cmd.push(RESPDatatypes::BulkString(deadline.to_string()));
         }
-        false
     }
+    RESPDatatypes::Array(cmd).encode()
+}
 
-    fn set_value_if_buffer(&mut self, vec: &Vec<RESPDatatypes>) -> bool {
-        if let Some(second_elem) = vec.get(2) {
-            return match second_elem {
-                RESPDatatypes::BufBulk(buff) => {
-                    self.value = buff.to_vec();
-                    true
-                }
-                _ => false,
-            };
+impl Set {
+    fn arity_matches(&self) -> bool {
+        match self.name.as_str() {
+            "set" => self.args.len() >= 2,
+            "setex" | "psetex" => self.args.len() == 3,
+            _ => self.args.len() == 2,
         }
-        false
     }
 
-    fn set_ttl_if_provided(&mut self, vec: &Vec<RESPDatatypes>) {
-        if vec.len() < 5 {
-            return;
+    fn parse_options(&self) -> io::Result<SetOptions> {
+        let key = parse_key(&self.args[0])?;
+        match self.name.as_str() {
+            "setnx" => {
+                return Ok(SetOptions {
+                    key,
+                    value: self.args[1].to_vec(),
+                    condition: Some(Condition::Nx),
+                    ..Default::default()
+                })
+            }
+            "setex" | "psetex" => {
+                let unit = if self.name == "setex" { "ex" } else { "px" };
+                return Ok(SetOptions {
+                    key,
+                    value: self.args[2].to_vec(),
+                    expiry: ExpiryOption::At(parse_expiry(&self.name, unit, &self.args[1])?),
+                    ..Default::default()
+                });
+            }
+            "getset" => {
+                return Ok(SetOptions {
+                    key,
+                    value: self.args[1].to_vec(),
+                    get: true,
+                    ..Default::default()
+                })
+            }
+            _ => {}
         }
 
-        if let RESPDatatypes::BufBulk(buff) = vec.get(3).unwrap() {
-            let key = bytes_to_string(buff)
+        let mut options = SetOptions {
+            key,
+            value: self.args[1].to_vec(),
+            ..Default::default()
+        };
+        let mut args = self.args.iter().skip(2);
+        while let Some(option) = args.next() {
+            let option = bytes_to_string(option)
                 .unwrap_or("".to_string())
                 .to_lowercase();
-            if key != "px" {
-                return;
+            match option.as_str() {
+                "nx" | "xx" => {
+                    let condition = if option == "nx" {
+                        Condition::Nx
+                    } else {
+                        Condition::Xx
+                    };
+                    if options
+                        .condition
+                        .is_some_and(|current| current != condition)
+                    {
+                        return Err(syntax_error());
+                    }
+                    options.condition = Some(condition);
+                }
+                "get" => options.get = true,
+                "keepttl" => {
+                    if options.expiry != ExpiryOption::Clear {
+                        return Err(syntax_error());
+                    }
+                    options.expiry = ExpiryOption::Keep;
+                }
+                "ex" | "px" | "exat" | "pxat" => {
+                    if options.expiry != ExpiryOption::Clear {
+                        return Err(syntax_error());
+                    }
+                    let time = args.next().ok_or_else(syntax_error)?;
+                    options.expiry = ExpiryOption::At(parse_expiry("set", &option, time)?);
+                }
+                _ => return Err(syntax_error()),
             }
+        }
+        Ok(options)
+    }
 
-            if let RESPDatatypes::BufBulk(buff) = vec.get(4).unwrap() {
-                let expiry_ttl: u64 = bytes_to_type(buff).unwrap_or(0);
-                // println!("{}", expiry_ttl);
+    async fn execute(
+        &self,
+        cache_repo: std::sync::Arc<Mutex<CacheRepository>>,
+        propagator: Option<Propagator>,
+    ) -> io::Result<RESPDatatypes> {
+        let options = self.parse_options()?;
 
-                if expiry_ttl == 0 {
-                    return;
-                }
+        // the keyspace stays locked until the write is propagated, so the condition holds
+        // and the write lines up with persistence and replication
+        let cache = cache_repo.lock().await;
+        // only GET cares about the kind of value, a plain SET replaces any of them
+        let old = match options.get {
+            true => cache.get(options.key.to_string()).await?,
+            false => None,
+        };
+        let exists = cache.exists(options.key.to_string()).await;
+        let applied = match options.condition {
+            Some(Condition::Nx) => !exists,
+            Some(Condition::Xx) => exists,
+            None => true,
+        };
 
-                self.expiry_ttl = Some(expiry_ttl);
+        if applied {
+            let propagated = match options.expiry {
+                ExpiryOption::At(deadline) if deadline <= unix_time_millis() => {
+                    cache.remove(options.key.to_string()).await;
+                    RESPDatatypes::Array(vec![
+                        RESPDatatypes::BulkString("DEL".to_string()),
+                        RESPDatatypes::BulkString(options.key.to_string()),
+                    ])
+                    .encode()
+                }
+                ExpiryOption::At(deadline) => {
+                    cache
+                        .set_with_expiry_at(
+                            options.key.to_string(),
+                            options.value.to_vec(),
+                            unix_millis_to_instant(deadline),
+                        )
+                        .await?;
+                    set_command(&options.key, &options.value, options.expiry)
+                }
+                ExpiryOption::Keep => {
+                    cache
+                        .set_keep_ttl(options.key.to_string(), options.value.to_vec())
+                        .await?;
+                    set_command(&options.key, &options.value, options.expiry)
+                }
+                ExpiryOption::Clear => {
+                    cache
+                        .set(options.key.to_string(), options.value.to_vec())
+                        .await?;
+                    set_command(&options.key, &options.value, options.expiry)
+                }
+            };
+            // propagate in execution order so replicas apply pipelined writes in order
+            if let Some(propagator) = propagator {
+                propagator.propagate(propagated).await;
             }
         }
+
+        if options.get {
+            return Ok(match old {
+                Some(old) => RESPDatatypes::BufBulk(old),
+                None => RESPDatatypes::NullString,
+            });
+        }
+        Ok(match self.name.as_str() {
+            "setnx" => RESPDatatypes::Integer(applied as i64),
+            _ if applied => RESPDatatypes::SimpleString("OK".to_string()),
+            _ => RESPDatatypes::NullString,
+        })
     }
 }
 
 impl Command for Set {
     fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
-        match cmd {
-            RESPDatatypes::Array(vec) => {
-                if vec.len() < 3 {
-                    return false;
-                }
-                if !self.is_first_elem_is_set_cmd(vec) {
-                    return false;
-                }
+        let Some((name, args)) = parse_args(cmd, &["set", "setnx", "setex", "psetex", "getset"])
+        else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
 
-                if !self.set_key_when_parsable(vec) {
-                    return false;
-                }
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut Connection>,
+    ) -> RunResult<'_> {
+        if !self.arity_matches() {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
 
-                if !self.set_value_if_buffer(vec) {
-                    return false;
-                }
+        Box::pin(async move { self.execute(cache_repo, propagator).await })
+    }
+}
 
-                self.set_ttl_if_provided(vec);
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
 
-                self.cmd = cmd.encode();
-                true
-            }
-            _ => false,
+    use tokio::sync::Mutex;
+
+    use crate::{
+        cache::{blocking::encode_command, core::CacheRepository},
+        command::core::test_support::{client, run, send},
+        resp::core::RESPDatatypes,
+    };
+
+    use super::*;
+
+    fn set(args: &[&str]) -> Set {
+        Set {
+            name: args[0].to_lowercase(),
+            args: args[1..]
+                .iter()
+                .map(|arg| arg.as_bytes().to_vec())
+                .collect(),
         }
     }
 
-    fn run(
-        &mut self,
-        cache_repo: std::sync::Arc<Mutex<crate::cache::core::CacheRepository>>,
-        conn: Option<&mut Connection>,
-    ) -> RunResult {
-        match conn {
-            Some(conn) => {
-                if conn.is_in_transaction() {
-                    let key = self.get_key();
-                    let value = self.get_value();
-                    let expiry_ttl = self.expiry_ttl.take();
-                    conn.add_tnx(Box::new(Set::new(key, value, expiry_ttl)));
-                    return Box::pin(async move {
-                        Ok(RESPDatatypes::SimpleString("QUEUED".to_string()))
-                    });
-                }
+    fn error(result: io::Result<impl std::fmt::Debug>) -> String {
+        result.unwrap_err().to_string()
+    }
 
-                let cmdq = conn.cmdq.clone();
-                let cmd = self.cmd.to_vec();
-                tokio::spawn(async move {
-                    let mut cmd_queue = cmdq.lock().await;
-                    cmd_queue.add(cmd).await;
-                });
-                Box::pin(async move {
-                    let cache_repo_clone = cache_repo.clone();
-                    let key = self.get_key();
-                    let buff = self.get_value();
-
-                    let mut repo = cache_repo_clone.lock().await;
-                    if let Some(ttl) = self.expiry_ttl {
-                        repo.set_with_expiry(key, buff, ttl).await?;
-                    } else {
-                        repo.set(key, buff).await?;
-                    }
-                    Ok(RESPDatatypes::SimpleString("OK".to_string()))
-                })
-            }
-            None => Box::pin(async move {
-                let cache_repo_clone = cache_repo.clone();
-                let key = self.get_key();
-                let buff = self.get_value();
-
-                let mut repo = cache_repo_clone.lock().await;
-                if let Some(ttl) = self.expiry_ttl {
-                    repo.set_with_expiry(key, buff, ttl).await?;
-                } else {
-                    repo.set(key, buff).await?;
-                }
-                Ok(RESPDatatypes::SimpleString("OK".to_string()))
-            }),
+    #[test]
+    fn options_come_in_any_order() {
+        let options = set(&["SET", "k", "v", "px", "100", "GET", "Xx"])
+            .parse_options()
+            .unwrap();
+        assert_eq!(options.condition, Some(Condition::Xx));
+        assert!(options.get);
+        assert!(matches!(options.expiry, ExpiryOption::At(_)));
+
+        let options = set(&["SET", "k", "v", "KEEPTTL", "nx", "get"])
+            .parse_options()
+            .unwrap();
+        assert_eq!(options.condition, Some(Condition::Nx));
+        assert!(options.get);
+        assert_eq!(options.expiry, ExpiryOption::Keep);
+    }
+
+    #[test]
+    fn conflicting_options_are_rejected() {
+        for args in [
+            ["SET", "k", "v", "NX", "XX"].as_slice(),
+            &["SET", "k", "v", "XX", "GET", "NX"],
+            &["SET", "k", "v", "EX", "10", "KEEPTTL"],
+            &["SET", "k", "v", "KEEPTTL", "PX", "10"],
+            &["SET", "k", "v", "EX", "10", "PXAT", "10"],
+            &["SET", "k", "v", "EX"],
+            &["SET", "k", "v", "FOREVER"],
+        ] {
+            assert_eq!(error(set(args).parse_options()), "ERR syntax error");
+        }
+    }
+
+    #[test]
+    fn setex_and_psetex_need_a_positive_time() {
+        for (args, cmd) in [
+            (["SETEX", "k", "0", "v"], "setex"),
+            (["SETEX", "k", "-5", "v"], "setex"),
+            (["PSETEX", "k", "0", "v"], "psetex"),
+            (["PSETEX", "k", "-1", "v"], "psetex"),
+        ] {
+            assert_eq!(
+                error(set(&args).parse_options()),
+                format!("ERR invalid expire time in '{}' command", cmd)
+            );
         }
     }
+
+    #[tokio::test]
+    async fn get_fails_on_other_kinds_of_values() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        run(&repo, &["RPUSH", "list", "a"]).await;
+
+        let err = error(
+            set(&["SET", "list", "v", "GET"])
+                .execute(repo.clone(), None)
+                .await,
+        );
+        assert!(err.starts_with("WRONGTYPE"), "{}", err);
+        // the list is left alone
+        let reply = run(&repo, &["LLEN", "list"]).await;
+        assert!(matches!(reply, RESPDatatypes::Integer(1)));
+    }
+
+    #[tokio::test]
+    async fn deadlines_in_the_past_delete_the_key() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), None).await;
+        send(&mut conn, &repo, &["SET", "a", "old"]).await;
+        send(&mut conn, &repo, &["SET", "b", "old"]).await;
+        let offset = conn.cmdq.lock().await.backlog.offset();
+
+        send(&mut conn, &repo, &["SET", "a", "new", "EXAT", "1"]).await;
+        send(&mut conn, &repo, &["SET", "b", "new", "PXAT", "1000"]).await;
+
+        assert!(!repo.lock().await.exists("a".to_string()).await);
+        assert!(!repo.lock().await.exists("b".to_string()).await);
+        let mut expected = encode_command(&["DEL", "a"]);
+        expected.extend(encode_command(&["DEL", "b"]));
+        let streamed = conn.cmdq.lock().await.backlog.read_from(offset).unwrap();
+        assert_eq!(streamed, expected);
+    }
 }
@@ -0,0 +1,69 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::bitmap::{parse_bit_offset, set_bit},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `SETBIT key offset value`, growing the string with zero bytes to reach the bit.
+#[derive(Debug, Default)]
+pub struct SetBit {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SetBit {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["setbit"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("setbit"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let offset = parse_bit_offset(&self.args[1], None)?;
+            let bit = match self.args[2].as_slice() {
+                b"0" => false,
+                b"1" => true,
+                _ => {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ERR bit is not an integer or out of range",
+                    ));
+                }
+            };
+
+            let cache = cache_repo.lock().await;
+            let mut value = cache.get(key.to_string()).await?.unwrap_or_default();
+            let old = set_bit(&mut value, offset, bit);
+            cache.set_keep_ttl(key, value).await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(old as i64))
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    cache::{core::Value, set::SetOp},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `SINTER`, `SUNION` and `SDIFF key [key ...]`, along with their `STORE` variants which take
+/// a destination key first and replace whatever it held with the result.
+#[derive(Debug, Default)]
+pub struct SetAlgebra {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+    /// registered apart from the plain variants, as only the `STORE` ones write
+    pub stores: bool,
+}
+
+impl SetAlgebra {
+    fn op(&self) -> SetOp {
+        match self.name.trim_end_matches("store") {
+            "sinter" => SetOp::Inter,
+            "sunion" => SetOp::Union,
+            _ => SetOp::Diff,
+        }
+    }
+
+    fn has_destination(&self) -> bool {
+        self.name.ends_with("store")
+    }
+}
+
+impl Command for SetAlgebra {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let names: &[&str] = match self.stores {
+            true => &["sinterstore", "sunionstore", "sdiffstore"],
+            false => &["sinter", "sunion", "sdiff"],
+        };
+        let Some((name, args)) = parse_args(cmd, names) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 1 + self.has_destination() as usize {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let mut keys = self
+                .args
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            let destination = self.has_destination().then(|| keys.remove(0));
+
+            let cache = cache_repo.lock().await;
+            let result = self.op().combine(cache.sets(&keys).await?);
+
+            let Some(destination) = destination else {
+                return Ok(RESPDatatypes::Set(
+                    result.into_iter().map(RESPDatatypes::BufBulk).collect(),
+                ));
+            };
+            let len = result.len();
+            if len > 0 {
+                cache.insert(destination, Value::Set(result), None).await?;
+            } else {
+                cache.remove(destination).await;
+            }
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
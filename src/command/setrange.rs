@@ -0,0 +1,84 @@
+use std::io::{self, Error};
+
+use crate::{cache::core::MAX_STRING_SIZE, resp::core::RESPDatatypes};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `SETRANGE key offset value`, padding with zero bytes when the offset is past the end.
+#[derive(Debug, Default)]
+pub struct SetRange {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SetRange {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["setrange"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("setrange"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let offset = parse_integer(&self.args[1])?;
+            let patch = &self.args[2];
+            if offset < 0 {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR offset is out of range",
+                ));
+            }
+            let offset = offset as usize;
+            if offset.saturating_add(patch.len()) > MAX_STRING_SIZE {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR string exceeds maximum allowed size (proto-max-bulk-len)",
+                ));
+            }
+
+            let cache = cache_repo.lock().await;
+            let existing = cache.get(key.to_string()).await?;
+            // an empty patch changes nothing, not even creating the key
+            if patch.is_empty() {
+                let len = existing.map_or(0, |value| value.len());
+                return Ok(RESPDatatypes::Integer(len as i64));
+            }
+
+            let mut value = existing.unwrap_or_default();
+            let end = offset + patch.len();
+            if value.len() < end {
+                value.resize(end, 0);
+            }
+            value[offset..end].copy_from_slice(patch);
+            let len = value.len();
+            cache.set_keep_ttl(key, value).await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::set::SetOp,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `SINTERCARD numkeys key [key ...] [LIMIT limit]`
+#[derive(Debug, Default)]
+pub struct SInterCard {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl SInterCard {
+    /// The keys to intersect and the limit, 0 for none.
+    fn parse(&self) -> io::Result<(Vec<String>, usize)> {
+        let numkeys = parse_integer(&self.args[0])?;
+        if numkeys <= 0 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR numkeys should be greater than 0",
+            ));
+        }
+        let numkeys = numkeys as usize;
+        if numkeys > self.args.len() - 1 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR Number of keys can't be greater than number of args",
+            ));
+        }
+        let keys = self.args[1..=numkeys]
+            .iter()
+            .map(|key| parse_key(key))
+            .collect::<io::Result<_>>()?;
+
+        let limit = match &self.args[numkeys + 1..] {
+            [] => 0,
+            [option, limit]
+                if bytes_to_string(option)
+                    .unwrap_or("".to_string())
+                    .to_lowercase()
+                    == "limit" =>
+            {
+                let limit = parse_integer(limit)?;
+                if limit < 0 {
+                    return Err(Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "ERR LIMIT can't be negative",
+                    ));
+                }
+                limit as usize
+            }
+            _ => return Err(syntax_error()),
+        };
+        Ok((keys, limit))
+    }
+}
+
+impl Command for SInterCard {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["sintercard"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("sintercard"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let (keys, limit) = self.parse()?;
+            let sets = cache_repo.lock().await.sets(&keys).await?;
+            let len = SetOp::Inter.combine(sets).len();
+            Ok(RESPDatatypes::Integer(match limit {
+                0 => len,
+                limit => len.min(limit),
+            } as i64))
+        })
+    }
+}
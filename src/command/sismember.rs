@@ -0,0 +1,69 @@
+use crate::{cache::set::as_set, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `SISMEMBER key member` and `SMISMEMBER key member [member ...]`
+#[derive(Debug, Default)]
+pub struct SIsMember {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SIsMember {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["sismember", "smismember"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let arity_matches = match self.name.as_str() {
+            "sismember" => self.args.len() == 2,
+            _ => self.args.len() >= 2,
+        };
+        if !arity_matches {
+            return fail(wrong_arity(&self.name));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+
+            let found: Vec<bool> = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let set = as_set(value)?;
+                    Ok::<_, std::io::Error>(
+                        self.args[1..]
+                            .iter()
+                            .map(|member| set.is_some_and(|set| set.contains(member)))
+                            .collect(),
+                    )
+                })
+                .await?;
+
+            Ok(match self.name.as_str() {
+                "sismember" => RESPDatatypes::Integer(found[0] as i64),
+                _ => RESPDatatypes::Array(
+                    found
+                        .into_iter()
+                        .map(|found| RESPDatatypes::Integer(found as i64))
+                        .collect(),
+                ),
+            })
+        })
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{cache::set::as_set, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `SMEMBERS key`
+#[derive(Debug, Default)]
+pub struct SMembers {
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for SMembers {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["smembers"]) else {
+            return false;
+        };
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity("smembers"));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let members = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_set(value)?.map_or(vec![], |set| {
+                        set.iter()
+                            .map(|member| RESPDatatypes::BufBulk(member.to_vec()))
+                            .collect()
+                    }))
+                })
+                .await?;
+            Ok(RESPDatatypes::Set(members))
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{
+    cache::set::{as_set, as_set_mut, as_set_or_create},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `SMOVE source destination member`
+#[derive(Debug, Default)]
+pub struct SMove {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SMove {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["smove"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("smove"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let source = parse_key(&self.args[0])?;
+            let destination = parse_key(&self.args[1])?;
+            let member = &self.args[2];
+
+            let cache = cache_repo.lock().await;
+            // both keys are type checked before anything moves
+            cache
+                .view(destination.to_string(), |value| as_set(value).map(|_| ()))
+                .await?;
+            let moved = cache
+                .modify(source, |value| {
+                    Ok::<_, std::io::Error>(
+                        as_set_mut(value)?.is_some_and(|set| set.remove(member)),
+                    )
+                })
+                .await?;
+            if !moved {
+                return Ok(RESPDatatypes::Integer(0));
+            }
+            cache
+                .modify(destination, |value| {
+                    as_set_or_create(value).map(|set| set.insert(member.to_vec()))
+                })
+                .await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::Integer(1))
+        })
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::{self, Error};
+
+use rand::seq::IteratorRandom;
+
+use crate::{cache::set::as_set_mut, resp::core::RESPDatatypes};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `SPOP key [count]`
+///
+/// The members are picked at random, so replicas are told which ones went with an `SREM`.
+#[derive(Debug, Default)]
+pub struct SPop {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SPop {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["spop"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() || self.args.len() > 2 {
+            return fail(wrong_arity("spop"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let count = match self.args.get(1) {
+                Some(count) => {
+                    let count = parse_integer(count)?;
+                    if count < 0 {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR value is out of range, must be positive",
+                        ));
+                    }
+                    Some(count as usize)
+                }
+                None => None,
+            };
+
+            let cache = cache_repo.lock().await;
+            let popped = cache
+                .modify(key.to_string(), |value| {
+                    let Some(set) = as_set_mut(value)? else {
+                        return Ok(vec![]);
+                    };
+                    let popped: Vec<Vec<u8>> = set
+                        .iter()
+                        .choose_multiple(&mut rand::thread_rng(), count.unwrap_or(1))
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                    for member in popped.iter() {
+                        set.remove(member);
+                    }
+                    Ok::<_, io::Error>(popped)
+                })
+                .await?;
+
+            if !popped.is_empty() {
+                if let Some(propagator) = propagator {
+                    let mut cmd = vec![
+                        RESPDatatypes::BulkString("SREM".to_string()),
+                        RESPDatatypes::BulkString(key),
+                    ];
+                    cmd.extend(popped.iter().cloned().map(RESPDatatypes::BufBulk));
+                    propagator
+                        .propagate(RESPDatatypes::Array(cmd).encode())
+                        .await;
+                }
+            }
+
+            Ok(match count {
+                Some(_) => {
+                    RESPDatatypes::Set(popped.into_iter().map(RESPDatatypes::BufBulk).collect())
+                }
+                None => match popped.into_iter().next() {
+                    Some(member) => RESPDatatypes::BufBulk(member),
+                    None => RESPDatatypes::NullString,
+                },
+            })
+        })
+    }
+}
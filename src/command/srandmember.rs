@@ -0,0 +1,85 @@
+use rand::seq::{IteratorRandom, SliceRandom};
+
+use crate::{cache::set::as_set, resp::core::RESPDatatypes};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult,
+};
+
+/// `SRANDMEMBER key [count]`
+///
+/// A positive count picks distinct members, a negative one may pick the same member again.
+#[derive(Debug, Default)]
+pub struct SRandMember {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SRandMember {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["srandmember"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() || self.args.len() > 2 {
+            return fail(wrong_arity("srandmember"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let count = match self.args.get(1) {
+                Some(count) => Some(parse_integer(count)?),
+                None => None,
+            };
+
+            let picked = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let Some(set) = as_set(value)? else {
+                        return Ok(vec![]);
+                    };
+                    let mut rng = rand::thread_rng();
+                    let picked: Vec<&Vec<u8>> = match count {
+                        None => set.iter().choose(&mut rng).into_iter().collect(),
+                        Some(count) if count >= 0 => {
+                            let mut picked = set.iter().choose_multiple(&mut rng, count as usize);
+                            picked.shuffle(&mut rng);
+                            picked
+                        }
+                        Some(count) => {
+                            let members: Vec<_> = set.iter().collect();
+                            (0..count.unsigned_abs())
+                                .filter_map(|_| members.choose(&mut rng).copied())
+                                .collect()
+                        }
+                    };
+                    Ok::<_, std::io::Error>(picked.into_iter().cloned().collect())
+                })
+                .await?;
+
+            Ok(match count {
+                Some(_) => {
+                    RESPDatatypes::Array(picked.into_iter().map(RESPDatatypes::BufBulk).collect())
+                }
+                None => match picked.into_iter().next() {
+                    Some(member) => RESPDatatypes::BufBulk(member),
+                    None => RESPDatatypes::NullString,
+                },
+            })
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{
+    cache::{
+        core::{scan_hash, scan_page},
+        set::as_set,
+    },
+    resp::core::RESPDatatypes,
+};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult},
+    scan::ScanOptions,
+};
+
+/// `SSCAN key cursor [MATCH pattern] [COUNT count]`
+#[derive(Debug, Default)]
+pub struct SScan {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for SScan {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["sscan"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("sscan"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let options = ScanOptions::parse(&self.args[1..], false)?;
+
+            let (next_cursor, page) = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    let Some(set) = as_set(value)? else {
+                        return Ok((0, vec![]));
+                    };
+                    let members = set.iter().map(|member| (scan_hash(member), member));
+                    let (next_cursor, page) = scan_page(members, options.cursor, options.count);
+                    let page: Vec<Vec<u8>> = page.into_iter().cloned().collect();
+                    Ok::<_, std::io::Error>((next_cursor, page))
+                })
+                .await?;
+
+            let reply = page
+                .into_iter()
+                .filter(|member| options.matches(member))
+                .map(RESPDatatypes::BufBulk)
+                .collect();
+            Ok(RESPDatatypes::Array(vec![
+                RESPDatatypes::BulkString(next_cursor.to_string()),
+                RESPDatatypes::Array(reply),
+            ]))
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::Instant;
+
+use crate::{cache::core::instant_to_unix_millis, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `TTL`, `PTTL`, `EXPIRETIME` and `PEXPIRETIME key`. They reply -2 for a missing key and -1
+/// for a key without a deadline.
+#[derive(Debug, Default)]
+pub struct Ttl {
+    pub name: String,
+    pub key: Option<Vec<u8>>,
+}
+
+impl Command for Ttl {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["ttl", "pttl", "expiretime", "pexpiretime"])
+        else {
+            return false;
+        };
+        self.name = name;
+        if let [key] = args.as_slice() {
+            self.key = Some(key.to_vec());
+        }
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(key) = self.key.clone() else {
+            return fail(wrong_arity(&self.name));
+        };
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&key)?;
+            let expiry = match cache_repo.lock().await.expiry(key).await {
+                None => return Ok(RESPDatatypes::Integer(-2)),
+                Some(None) => return Ok(RESPDatatypes::Integer(-1)),
+                Some(Some(expiry)) => expiry,
+            };
+
+            let remaining = expiry.saturating_duration_since(Instant::now()).as_millis() as i64;
+            let reply = match self.name.as_str() {
+                "ttl" => (remaining + 500) / 1000,
+                "pttl" => remaining,
+                "expiretime" => (instant_to_unix_millis(expiry) / 1000) as i64,
+                _ => instant_to_unix_millis(expiry) as i64,
+            };
+            Ok(RESPDatatypes::Integer(reply))
+        })
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::{self, Error},
+    sync::atomic::Ordering,
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{
+    cli::core::Roles,
+    errors::value_is_not_type::ValueIsNotType,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::Command;
+
+#[derive(Debug, Default)]
+pub struct Wait {
+    pub numreplicas: String,
+    pub timeout: String,
+}
+
+impl Wait {
+    fn parse_args(&self) -> io::Result<(usize, u64)> {
+        let not_integer = || {
+            Error::new(
+                io::ErrorKind::InvalidInput,
+                ValueIsNotType {
+                    type_name: "integer".to_string(),
+                    can_be_out_of_range: Some(true),
+                },
+            )
+        };
+        let numreplicas = self.numreplicas.parse::<i64>().map_err(|_| not_integer())?;
+        let timeout = self.timeout.parse::<i64>().map_err(|_| not_integer())?;
+        if timeout < 0 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR timeout is negative",
+            ));
+        }
+        Ok((numreplicas.max(0) as usize, timeout as u64))
+    }
+}
+
+impl Command for Wait {
+    fn can_execute(&mut self, cmd: &crate::resp::core::RESPDatatypes) -> bool {
+        if let RESPDatatypes::Array(vec) = cmd {
+            if vec.len() != 3 {
+                return false;
+            }
+            let mut args = Vec::with_capacity(vec.len());
+            for elem in vec {
+                match elem {
+                    RESPDatatypes::BufBulk(buf) => {
+                        args.push(bytes_to_string(buf).unwrap_or("".to_string()))
+                    }
+                    _ => return false,
+                }
+            }
+            if args[0].to_lowercase() != "wait" {
+                return false;
+            }
+            self.numreplicas = args[1].to_string();
+            self.timeout = args[2].to_string();
+            return true;
+        }
+        false
+    }
+
+    fn run(
+        &mut self,
+        _cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> super::core::RunResult<'_> {
+        let Some(conn) = conn else {
+            return Box::pin(async move {
+                Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR WAIT is not allowed in this context",
+                ))
+            });
+        };
+        let cmdq = conn.cmdq.clone();
+        let replication_config = conn.server_config.replication_config.clone();
+        // every replica has to reach the end of this client's last write
+        let target = conn.write_offset.load(Ordering::Acquire);
+
+        Box::pin(async move {
+            let (numreplicas, timeout) = self.parse_args()?;
+            if let Roles::Slave(_) = replication_config.read().await.role {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "ERR WAIT cannot be used with replica instances.",
+                ));
+            }
+
+            let (mut ack_rx, mut acked) = {
+                let mut cmdq = cmdq.lock().await;
+                (cmdq.subscribe_acks(), cmdq.count_acked(target))
+            };
+            if acked >= numreplicas {
+                return Ok(RESPDatatypes::Integer(acked as i64));
+            }
+
+            cmdq.lock().await.request_acks().await;
+
+            // a timeout of 0 blocks until enough replicas acknowledged
+            let deadline = (timeout > 0).then(|| Instant::now() + Duration::from_millis(timeout));
+            while acked < numreplicas {
+                let timed_out = async {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    changed = ack_rx.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                    _ = timed_out => break,
+                }
+                acked = cmdq.lock().await.count_acked(target);
+            }
+
+            Ok(RESPDatatypes::Integer(acked as i64))
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::io;
+
+use crate::{
+    cache::stream::{as_stream_mut, StreamId},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `XACK key group id [id ...]`
+#[derive(Debug, Default)]
+pub struct XAck {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for XAck {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xack"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 3 {
+            return fail(wrong_arity("xack"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let group = &self.args[1];
+            let ids = self.args[2..]
+                .iter()
+                .map(|id| StreamId::parse(id, 0))
+                .collect::<io::Result<Vec<StreamId>>>()?;
+
+            let cache = cache_repo.lock().await;
+            let acked = cache
+                .modify(key, |value| {
+                    let Some(group) = as_stream_mut(value)?
+                        .and_then(|stream| stream.groups.get_mut(group.as_slice()))
+                    else {
+                        return Ok(0);
+                    };
+                    Ok::<_, io::Error>(
+                        ids.iter()
+                            .filter(|id| group.pending.remove(id).is_some())
+                            .count(),
+                    )
+                })
+                .await?;
+
+            if acked > 0 {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::Integer(acked as i64))
+        })
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::{
+        core::unix_time_millis,
+        stream::{as_stream, as_stream_or_create, NewId, Stream, StreamId, Trim, TrimStrategy},
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wake_blocked, wrong_arity,
+    Command, RunResult,
+};
+
+/// `XADD key [NOMKSTREAM] [MAXLEN | MINID [= | ~] threshold [LIMIT count]] id | * field value
+/// [field value ...]`
+#[derive(Debug, Default)]
+pub struct XAdd {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// Approximate trimming evicts at most this many entries at once, unless told otherwise.
+const DEFAULT_TRIM_LIMIT: usize = 10000;
+
+#[derive(Debug)]
+struct XAddOptions {
+    no_mkstream: bool,
+    trim: Option<Trim>,
+    /// where the id is in the arguments, the field value pairs follow it
+    id_idx: usize,
+}
+
+fn parse_options(args: &[Vec<u8>]) -> io::Result<XAddOptions> {
+    let (mut no_mkstream, mut strategy, mut approximate, mut limit) = (false, None, false, None);
+    let mut idx = 1;
+    while let Some(arg) = args.get(idx) {
+        let option = bytes_to_string(arg)
+            .unwrap_or("".to_string())
+            .to_lowercase();
+        match option.as_str() {
+            "nomkstream" => no_mkstream = true,
+            "maxlen" | "minid" => {
+                idx += 1;
+                let mut threshold = args.get(idx).ok_or_else(syntax_error)?;
+                if threshold == b"~" || threshold == b"=" {
+                    approximate = threshold == b"~";
+                    idx += 1;
+                    threshold = args.get(idx).ok_or_else(syntax_error)?;
+                }
+                strategy = Some(match option.as_str() {
+                    "maxlen" => match parse_integer(threshold)? {
+                        max_len if max_len < 0 => {
+                            return Err(Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "ERR The MAXLEN argument must be >= 0.",
+                            ));
+                        }
+                        max_len => TrimStrategy::MaxLen(max_len as usize),
+                    },
+                    _ => TrimStrategy::MinId(StreamId::parse(threshold, 0)?),
+                });
+            }
+            "limit" => {
+                idx += 1;
+                match parse_integer(args.get(idx).ok_or_else(syntax_error)?)? {
+                    count if count < 0 => {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR The LIMIT argument must be >= 0.",
+                        ));
+                    }
+                    count => limit = Some(count as usize),
+                }
+            }
+            _ => break,
+        }
+        idx += 1;
+    }
+
+    if limit.is_some() && !approximate {
+        return Err(Error::new(
+            io::ErrorKind::InvalidInput,
+            "ERR syntax error, LIMIT cannot be used without the special ~ option",
+        ));
+    }
+    let trim = strategy.map(|strategy| Trim {
+        strategy,
+        limit: match limit {
+            _ if !approximate => None,
+            Some(0) => None,
+            Some(limit) => Some(limit),
+            None => Some(DEFAULT_TRIM_LIMIT),
+        },
+    });
+    Ok(XAddOptions {
+        no_mkstream,
+        trim,
+        id_idx: idx,
+    })
+}
+
+impl Command for XAdd {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xadd"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 4 {
+            return fail(wrong_arity("xadd"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let options = parse_options(&self.args)?;
+            let Some((id_arg, pairs)) = self.args[options.id_idx..].split_first() else {
+                return Err(wrong_arity("xadd"));
+            };
+            if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+                return Err(wrong_arity("xadd"));
+            }
+            let new_id = NewId::parse(id_arg)?;
+            let fields: Vec<(Vec<u8>, Vec<u8>)> = pairs
+                .chunks(2)
+                .map(|pair| (pair[0].to_vec(), pair[1].to_vec()))
+                .collect();
+
+            let cache = cache_repo.lock().await;
+            let id = cache
+                .modify(key.to_string(), |value| {
+                    let now = unix_time_millis();
+                    // the id is checked first so a failing XADD doesn't create the stream
+                    let id = match as_stream(value.as_ref())? {
+                        Some(stream) => stream.next_id(new_id, now)?,
+                        None if options.no_mkstream => return Ok(None),
+                        None => Stream::default().next_id(new_id, now)?,
+                    };
+                    let stream = as_stream_or_create(value)?;
+                    stream.add(id, fields);
+                    if let Some(trim) = options.trim.as_ref() {
+                        stream.trim(trim);
+                    }
+                    Ok::<_, io::Error>(Some(id))
+                })
+                .await?;
+            let Some(id) = id else {
+                return Ok(RESPDatatypes::NullString);
+            };
+
+            // replicas add the entry under the id it got here
+            if let Some(propagator) = propagator.as_ref() {
+                let mut cmd = vec![RESPDatatypes::BulkString("XADD".to_string())];
+                cmd.extend(self.args.iter().enumerate().map(|(idx, arg)| {
+                    match idx == options.id_idx {
+                        true => RESPDatatypes::BulkString(id.to_string()),
+                        false => RESPDatatypes::BufBulk(arg.to_vec()),
+                    }
+                }));
+                propagator
+                    .propagate(RESPDatatypes::Array(cmd).encode())
+                    .await;
+            }
+            wake_blocked(&cache, key, propagator.as_ref()).await;
+            Ok(RESPDatatypes::BulkString(id.to_string()))
+        })
+    }
+}
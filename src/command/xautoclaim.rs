@@ -0,0 +1,182 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::{
+        core::unix_time_millis,
+        stream::{as_stream_mut, no_such_key_or_group, Claim, ClaimOptions, StreamId},
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::{
+    core::{
+        fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+        RunResult,
+    },
+    xclaim::{ack_command, claim_command, parse_millis},
+    xgroup::create_consumer_command,
+    xrange::entry_reply,
+};
+
+/// `XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]`
+#[derive(Debug, Default)]
+pub struct XAutoClaim {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// Pending entries looked at per entry asked for, so a scan over mostly busy entries ends.
+const ATTEMPTS_PER_COUNT: usize = 10;
+
+impl Command for XAutoClaim {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xautoclaim"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 5 {
+            return fail(wrong_arity("xautoclaim"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let (group, consumer) = (self.args[1].to_vec(), self.args[2].to_vec());
+            let now = unix_time_millis();
+            let mut options = ClaimOptions {
+                min_idle: parse_millis(
+                    &self.args[3],
+                    "ERR Invalid min-idle-time argument for XAUTOCLAIM",
+                )?,
+                ..Default::default()
+            };
+            let start = StreamId::parse_bound(&self.args[4], false)?;
+            let mut count = 100;
+            let mut args = self.args[5..].iter();
+            while let Some(arg) = args.next() {
+                match bytes_to_string(arg)
+                    .unwrap_or("".to_string())
+                    .to_lowercase()
+                    .as_str()
+                {
+                    "count" => {
+                        count = match parse_integer(args.next().ok_or_else(syntax_error)?)? {
+                            count if count > 0 && count <= i64::MAX / ATTEMPTS_PER_COUNT as i64 => {
+                                count as usize
+                            }
+                            _ => {
+                                return Err(Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    "ERR COUNT must be > 0",
+                                ));
+                            }
+                        }
+                    }
+                    "justid" => options.just_id = true,
+                    _ => return Err(syntax_error()),
+                }
+            }
+
+            let cache = cache_repo.lock().await;
+            let (created, claims, cursor) = cache
+                .modify(key.to_string(), |value| {
+                    let Some(stream) = as_stream_mut(value)? else {
+                        return Err(no_such_key_or_group(&key, &group));
+                    };
+                    let Some(consumer_group) = stream.groups.get_mut(&group) else {
+                        return Err(no_such_key_or_group(&key, &group));
+                    };
+                    let created = consumer_group.touch_consumer(&consumer, now);
+                    let mut candidates: Vec<StreamId> = consumer_group
+                        .pending
+                        .range(start..)
+                        .map(|(id, _)| *id)
+                        .take(count * ATTEMPTS_PER_COUNT + 1)
+                        .collect();
+                    // the scan goes on from the first pending entry it didn't look at
+                    let cursor = match candidates.len() > count * ATTEMPTS_PER_COUNT {
+                        true => candidates.pop(),
+                        false => None,
+                    };
+
+                    let mut claims = Vec::new();
+                    let mut claimed = 0;
+                    let mut looked_at = 0;
+                    for id in candidates.iter() {
+                        if claimed == count {
+                            break;
+                        }
+                        looked_at += 1;
+                        let claim = stream.claim(&group, &consumer, *id, &options, now);
+                        if let Claim::Claimed(..) = claim {
+                            claimed += 1;
+                        }
+                        claims.push((*id, claim));
+                    }
+                    let cursor = candidates.get(looked_at).copied().or(cursor);
+                    Ok((created, claims, cursor.unwrap_or(StreamId::MIN)))
+                })
+                .await?;
+
+            let mut claimed = Vec::new();
+            let mut deleted = Vec::new();
+            for (id, claim) in claims {
+                match claim {
+                    Claim::Claimed(fields, pending) => claimed.push((id, fields, pending)),
+                    Claim::Deleted => deleted.push(id),
+                    Claim::Skipped => {}
+                }
+            }
+            if let Some(propagator) = propagator {
+                if created {
+                    propagator
+                        .propagate(create_consumer_command(&key, &group, &consumer))
+                        .await;
+                }
+                for (id, _, pending) in claimed.iter() {
+                    propagator
+                        .propagate(claim_command(&key, &group, *id, pending))
+                        .await;
+                }
+                if !deleted.is_empty() {
+                    propagator
+                        .propagate(ack_command(&key, &group, &deleted))
+                        .await;
+                }
+            }
+
+            Ok(RESPDatatypes::Array(vec![
+                RESPDatatypes::BulkString(cursor.to_string()),
+                RESPDatatypes::Array(
+                    claimed
+                        .into_iter()
+                        .map(|(id, fields, _)| match options.just_id {
+                            true => RESPDatatypes::BulkString(id.to_string()),
+                            false => entry_reply((id, fields)),
+                        })
+                        .collect(),
+                ),
+                RESPDatatypes::Array(
+                    deleted
+                        .into_iter()
+                        .map(|id| RESPDatatypes::BulkString(id.to_string()))
+                        .collect(),
+                ),
+            ]))
+        })
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::{
+        core::unix_time_millis,
+        stream::{
+            as_stream_mut, no_such_key_or_group, Claim, ClaimOptions, PendingEntry, StreamId,
+        },
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult},
+    xgroup::{create_consumer_command, set_id_command},
+    xrange::entry_reply,
+};
+
+/// `XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-time-milliseconds]
+/// [RETRYCOUNT count] [FORCE] [JUSTID] [LASTID lastid]`
+#[derive(Debug, Default)]
+pub struct XClaim {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// The claim replicas apply for a pending entry, recording its delivery exactly as it is here.
+pub fn claim_command(key: &str, group: &[u8], id: StreamId, pending: &PendingEntry) -> Vec<u8> {
+    RESPDatatypes::Array(vec![
+        RESPDatatypes::BulkString("XCLAIM".to_string()),
+        RESPDatatypes::BulkString(key.to_string()),
+        RESPDatatypes::BufBulk(group.to_vec()),
+        RESPDatatypes::BufBulk(pending.consumer.to_vec()),
+        RESPDatatypes::BulkString("0".to_string()),
+        RESPDatatypes::BulkString(id.to_string()),
+        RESPDatatypes::BulkString("TIME".to_string()),
+        RESPDatatypes::BulkString(pending.delivered_at.to_string()),
+        RESPDatatypes::BulkString("RETRYCOUNT".to_string()),
+        RESPDatatypes::BulkString(pending.deliveries.to_string()),
+        RESPDatatypes::BulkString("FORCE".to_string()),
+        RESPDatatypes::BulkString("JUSTID".to_string()),
+    ])
+    .encode()
+}
+
+/// The `XACK` replicas apply for pending entries dropped since their entry was deleted.
+pub fn ack_command(key: &str, group: &[u8], ids: &[StreamId]) -> Vec<u8> {
+    let mut cmd = vec![
+        RESPDatatypes::BulkString("XACK".to_string()),
+        RESPDatatypes::BulkString(key.to_string()),
+        RESPDatatypes::BufBulk(group.to_vec()),
+    ];
+    cmd.extend(
+        ids.iter()
+            .map(|id| RESPDatatypes::BulkString(id.to_string())),
+    );
+    RESPDatatypes::Array(cmd).encode()
+}
+
+/// Parses a number of milliseconds, negative ones counting as 0.
+pub fn parse_millis(arg: &[u8], err: &str) -> io::Result<u64> {
+    bytes_to_string(arg)
+        .ok()
+        .and_then(|millis| millis.parse::<i64>().ok())
+        .map(|millis| millis.max(0) as u64)
+        .ok_or_else(|| Error::new(io::ErrorKind::InvalidInput, err.to_string()))
+}
+
+impl Command for XClaim {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xclaim"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 5 {
+            return fail(wrong_arity("xclaim"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let (group, consumer) = (self.args[1].to_vec(), self.args[2].to_vec());
+            let now = unix_time_millis();
+            let mut options = ClaimOptions {
+                min_idle: parse_millis(
+                    &self.args[3],
+                    "ERR Invalid min-idle-time argument for XCLAIM",
+                )?,
+                ..Default::default()
+            };
+
+            // the ids run up to the first argument that isn't one
+            let mut ids = Vec::new();
+            let mut args = self.args[4..].iter().peekable();
+            while let Some(id) = args.peek().and_then(|id| StreamId::parse(id, 0).ok()) {
+                ids.push(id);
+                args.next();
+            }
+            let mut last_id = None;
+            while let Some(arg) = args.next() {
+                let option = bytes_to_string(arg)
+                    .unwrap_or("".to_string())
+                    .to_lowercase();
+                let mut value = |err: &str| {
+                    args.next()
+                        .ok_or_else(|| Error::new(io::ErrorKind::InvalidInput, err.to_string()))
+                };
+                match option.as_str() {
+                    "idle" => {
+                        let err = "ERR Invalid IDLE option argument for XCLAIM";
+                        let idle = parse_millis(value(err)?, err)?;
+                        options.delivered_at = Some(now.saturating_sub(idle));
+                    }
+                    "time" => {
+                        let err = "ERR Invalid TIME option argument for XCLAIM";
+                        options.delivered_at = Some(parse_millis(value(err)?, err)?.min(now));
+                    }
+                    "retrycount" => {
+                        let err = "ERR Invalid RETRYCOUNT option argument for XCLAIM";
+                        options.retry_count = Some(parse_millis(value(err)?, err)?);
+                    }
+                    "force" => options.force = true,
+                    "justid" => options.just_id = true,
+                    "lastid" => {
+                        let arg = value("ERR syntax error")?;
+                        last_id = Some(StreamId::parse(arg, 0)?);
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "ERR Unrecognized XCLAIM option '{}'",
+                                String::from_utf8_lossy(arg)
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            let cache = cache_repo.lock().await;
+            let (created, claims, moved_last_id) = cache
+                .modify(key.to_string(), |value| {
+                    let Some(stream) = as_stream_mut(value)? else {
+                        return Err(no_such_key_or_group(&key, &group));
+                    };
+                    let Some(consumer_group) = stream.groups.get_mut(&group) else {
+                        return Err(no_such_key_or_group(&key, &group));
+                    };
+                    let created = consumer_group.touch_consumer(&consumer, now);
+                    let moved_last_id = match last_id {
+                        Some(last_id) if last_id > consumer_group.last_delivered => {
+                            consumer_group.last_delivered = last_id;
+                            true
+                        }
+                        _ => false,
+                    };
+                    let claims: Vec<(StreamId, Claim)> = ids
+                        .iter()
+                        .map(|id| (*id, stream.claim(&group, &consumer, *id, &options, now)))
+                        .collect();
+                    let set_id =
+                        moved_last_id.then(|| set_id_command(&key, &group, &stream.groups[&group]));
+                    Ok((created, claims, set_id))
+                })
+                .await?;
+
+            if let Some(propagator) = propagator {
+                if created {
+                    propagator
+                        .propagate(create_consumer_command(&key, &group, &consumer))
+                        .await;
+                }
+                if let Some(set_id) = moved_last_id {
+                    propagator.propagate(set_id).await;
+                }
+                let mut deleted = Vec::new();
+                for (id, claim) in claims.iter() {
+                    match claim {
+                        Claim::Claimed(_, pending) => {
+                            propagator
+                                .propagate(claim_command(&key, &group, *id, pending))
+                                .await;
+                        }
+                        Claim::Deleted => deleted.push(*id),
+                        Claim::Skipped => {}
+                    }
+                }
+                if !deleted.is_empty() {
+                    propagator
+                        .propagate(ack_command(&key, &group, &deleted))
+                        .await;
+                }
+            }
+
+            Ok(RESPDatatypes::Array(
+                claims
+                    .into_iter()
+                    .filter_map(|(id, claim)| match claim {
+                        Claim::Claimed(_, _) if options.just_id => {
+                            Some(RESPDatatypes::BulkString(id.to_string()))
+                        }
+                        Claim::Claimed(fields, _) => Some(entry_reply((id, fields))),
+                        _ => None,
+                    })
+                    .collect(),
+            ))
+        })
+    }
+}
@@ -0,0 +1,268 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::{
+        core::unix_time_millis,
+        stream::{as_stream, as_stream_mut, as_stream_or_create, ConsumerGroup, StreamId},
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wake_blocked, wrong_arity,
+    Command, RunResult,
+};
+
+/// `XGROUP CREATE key group id | $ [MKSTREAM] [ENTRIESREAD entries-read]`,
+/// `XGROUP SETID key group id | $ [ENTRIESREAD entries-read]`, `XGROUP DESTROY key group`,
+/// `XGROUP CREATECONSUMER key group consumer` and `XGROUP DELCONSUMER key group consumer`
+#[derive(Debug, Default)]
+pub struct XGroup {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+pub fn no_such_group(key: &str, group: &[u8]) -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "NOGROUP No such consumer group '{}' for key name '{}'",
+            String::from_utf8_lossy(group),
+            key
+        ),
+    )
+}
+
+/// The `XGROUP SETID` replicas apply to catch up with where `group` is.
+pub fn set_id_command(key: &str, name: &[u8], group: &ConsumerGroup) -> Vec<u8> {
+    let entries_read = group.entries_read.map_or(-1, |read| read as i64);
+    RESPDatatypes::Array(vec![
+        RESPDatatypes::BulkString("XGROUP".to_string()),
+        RESPDatatypes::BulkString("SETID".to_string()),
+        RESPDatatypes::BulkString(key.to_string()),
+        RESPDatatypes::BufBulk(name.to_vec()),
+        RESPDatatypes::BulkString(group.last_delivered.to_string()),
+        RESPDatatypes::BulkString("ENTRIESREAD".to_string()),
+        RESPDatatypes::BulkString(entries_read.to_string()),
+    ])
+    .encode()
+}
+
+/// The `XGROUP CREATECONSUMER` replicas apply for a consumer created implicitly.
+pub fn create_consumer_command(key: &str, group: &[u8], consumer: &[u8]) -> Vec<u8> {
+    RESPDatatypes::Array(vec![
+        RESPDatatypes::BulkString("XGROUP".to_string()),
+        RESPDatatypes::BulkString("CREATECONSUMER".to_string()),
+        RESPDatatypes::BulkString(key.to_string()),
+        RESPDatatypes::BufBulk(group.to_vec()),
+        RESPDatatypes::BufBulk(consumer.to_vec()),
+    ])
+    .encode()
+}
+
+fn requires_key() -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want \
+         to use the MKSTREAM option to create an empty stream automatically.",
+    )
+}
+
+/// The options after the id of `CREATE` and `SETID`.
+#[derive(Debug, Default)]
+struct GroupOptions {
+    mkstream: bool,
+    entries_read: Option<u64>,
+}
+
+fn parse_options(args: &[Vec<u8>], can_mkstream: bool) -> io::Result<GroupOptions> {
+    let mut options = GroupOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match bytes_to_string(arg)
+            .unwrap_or("".to_string())
+            .to_lowercase()
+            .as_str()
+        {
+            "mkstream" if can_mkstream => options.mkstream = true,
+            "entriesread" => {
+                options.entries_read = match parse_integer(args.next().ok_or_else(syntax_error)?)? {
+                    -1 => None,
+                    entries_read if entries_read >= 0 => Some(entries_read as u64),
+                    _ => {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR value for ENTRIESREAD must be positive or -1",
+                        ));
+                    }
+                }
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+    Ok(options)
+}
+
+impl Command for XGroup {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xgroup"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(subcommand) = self.args.first() else {
+            return fail(wrong_arity("xgroup"));
+        };
+        let subcommand = bytes_to_string(subcommand)
+            .unwrap_or("".to_string())
+            .to_lowercase();
+        let arity_ok = match subcommand.as_str() {
+            "create" => (4..=7).contains(&self.args.len()),
+            "setid" => (4..=6).contains(&self.args.len()),
+            "destroy" => self.args.len() == 3,
+            "createconsumer" | "delconsumer" => self.args.len() == 4,
+            _ => {
+                return fail(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "ERR unknown subcommand '{}'. Try XGROUP HELP.",
+                        String::from_utf8_lossy(&self.args[0])
+                    ),
+                ));
+            }
+        };
+        if !arity_ok {
+            return fail(wrong_arity(&format!("xgroup|{}", subcommand)));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[1])?;
+            let group = self.args[2].to_vec();
+
+            let cache = cache_repo.lock().await;
+            match subcommand.as_str() {
+                "create" | "setid" => {
+                    let create = subcommand == "create";
+                    let options = parse_options(&self.args[4..], create)?;
+                    let id = match self.args[3].as_slice() {
+                        b"$" => None,
+                        id => Some(StreamId::parse(id, 0)?),
+                    };
+                    let id = cache
+                        .modify(key.to_string(), |value| {
+                            let exists = as_stream(value.as_ref())?.is_some();
+                            let can_create = create && options.mkstream;
+                            if !(exists || can_create) {
+                                return Err(requires_key());
+                            }
+                            let stream = as_stream_or_create(value)?;
+                            let id = id.unwrap_or(stream.last_id);
+                            if create {
+                                if stream.groups.contains_key(&group) {
+                                    return Err(Error::new(
+                                        io::ErrorKind::InvalidInput,
+                                        "BUSYGROUP Consumer Group name already exists",
+                                    ));
+                                }
+                                stream.groups.insert(
+                                    group.to_vec(),
+                                    ConsumerGroup::new(id, options.entries_read),
+                                );
+                            } else {
+                                let Some(consumer_group) = stream.groups.get_mut(&group) else {
+                                    return Err(no_such_group(&key, &group));
+                                };
+                                consumer_group.last_delivered = id;
+                                consumer_group.entries_read = options.entries_read;
+                            }
+                            Ok(id)
+                        })
+                        .await?;
+
+                    // replicas get the id `$` stood for here
+                    if let Some(propagator) = propagator {
+                        let mut cmd = vec![RESPDatatypes::BulkString("XGROUP".to_string())];
+                        cmd.extend(self.args.iter().enumerate().map(|(idx, arg)| match idx {
+                            3 => RESPDatatypes::BulkString(id.to_string()),
+                            _ => RESPDatatypes::BufBulk(arg.to_vec()),
+                        }));
+                        propagator
+                            .propagate(RESPDatatypes::Array(cmd).encode())
+                            .await;
+                    }
+                    Ok(RESPDatatypes::SimpleString("OK".to_string()))
+                }
+                "destroy" => {
+                    let destroyed = cache
+                        .modify(key.to_string(), |value| {
+                            let Some(stream) = as_stream_mut(value)? else {
+                                return Err(requires_key());
+                            };
+                            Ok(stream.groups.remove(&group).is_some())
+                        })
+                        .await?;
+                    if destroyed {
+                        if let Some(propagator) = propagator.as_ref() {
+                            propagator.propagate(self.cmd.to_vec()).await;
+                        }
+                        // its blocked consumers find out it is gone
+                        wake_blocked(&cache, key, propagator.as_ref()).await;
+                    }
+                    Ok(RESPDatatypes::Integer(destroyed as i64))
+                }
+                _ => {
+                    let consumer = self.args[3].to_vec();
+                    let create = subcommand == "createconsumer";
+                    let changed = cache
+                        .modify(key.to_string(), |value| {
+                            let Some(stream) = as_stream_mut(value)? else {
+                                return Err(requires_key());
+                            };
+                            let Some(consumer_group) = stream.groups.get_mut(&group) else {
+                                return Err(no_such_group(&key, &group));
+                            };
+                            if create {
+                                if consumer_group.consumers.contains_key(&consumer) {
+                                    return Ok(None);
+                                }
+                                consumer_group.touch_consumer(&consumer, unix_time_millis());
+                                return Ok(Some(1));
+                            }
+                            if consumer_group.consumers.remove(&consumer).is_none() {
+                                return Ok(None);
+                            }
+                            let before = consumer_group.pending.len();
+                            consumer_group
+                                .pending
+                                .retain(|_, pending| pending.consumer != consumer);
+                            Ok(Some(before - consumer_group.pending.len()))
+                        })
+                        .await?;
+
+                    if changed.is_some() {
+                        if let Some(propagator) = propagator {
+                            propagator.propagate(self.cmd.to_vec()).await;
+                        }
+                    }
+                    Ok(RESPDatatypes::Integer(changed.unwrap_or(0) as i64))
+                }
+            }
+        })
+    }
+}
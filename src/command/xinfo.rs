@@ -0,0 +1,303 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::{
+        core::unix_time_millis,
+        stream::{as_stream, ConsumerGroup, Stream, StreamEntry},
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::{
+    core::{
+        fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+        RunResult,
+    },
+    xgroup::no_such_group,
+    xrange::{entries_reply, entry_reply},
+};
+
+/// `XINFO STREAM key [FULL [COUNT count]]`, `XINFO GROUPS key` and
+/// `XINFO CONSUMERS key group`
+#[derive(Debug, Default)]
+pub struct XInfo {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// `FULL` lists this many entries and pending entries unless told otherwise.
+const DEFAULT_FULL_COUNT: usize = 10;
+
+fn field(name: &str, value: RESPDatatypes) -> (RESPDatatypes, RESPDatatypes) {
+    (RESPDatatypes::BulkString(name.to_string()), value)
+}
+
+fn id_reply(id: impl ToString) -> RESPDatatypes {
+    RESPDatatypes::BulkString(id.to_string())
+}
+
+fn optional_integer(val: Option<u64>) -> RESPDatatypes {
+    val.map_or(RESPDatatypes::Null, |val| {
+        RESPDatatypes::Integer(val as i64)
+    })
+}
+
+fn optional_entry(entry: Option<StreamEntry>) -> RESPDatatypes {
+    entry.map_or(RESPDatatypes::Null, entry_reply)
+}
+
+/// The fields every form of `XINFO STREAM` starts with.
+fn stream_header(stream: &Stream) -> Vec<(RESPDatatypes, RESPDatatypes)> {
+    vec![
+        field("length", RESPDatatypes::Integer(stream.len() as i64)),
+        field("last-generated-id", id_reply(stream.last_id)),
+        field("max-deleted-entry-id", id_reply(stream.max_deleted_id)),
+        field(
+            "entries-added",
+            RESPDatatypes::Integer(stream.entries_added as i64),
+        ),
+        field("recorded-first-entry-id", id_reply(stream.first_id())),
+    ]
+}
+
+fn stream_reply(stream: &Stream) -> RESPDatatypes {
+    let mut fields = stream_header(stream);
+    fields.push(field(
+        "groups",
+        RESPDatatypes::Integer(stream.groups.len() as i64),
+    ));
+    fields.push(field("first-entry", optional_entry(stream.first_entry())));
+    fields.push(field("last-entry", optional_entry(stream.last_entry())));
+    RESPDatatypes::Map(fields)
+}
+
+/// The stream with its entries and groups spelled out, `count` of each list at most.
+fn full_stream_reply(stream: &Stream, count: Option<usize>) -> RESPDatatypes {
+    let limit = count.unwrap_or(usize::MAX);
+    let mut fields = stream_header(stream);
+    fields.push(field(
+        "entries",
+        entries_reply(
+            stream
+                .entries
+                .iter()
+                .take(limit)
+                .map(|(id, fields)| (*id, fields.to_vec()))
+                .collect(),
+        ),
+    ));
+    let groups = stream
+        .groups
+        .iter()
+        .map(|(name, group)| {
+            let pending = group
+                .pending
+                .iter()
+                .take(limit)
+                .map(|(id, pending)| {
+                    RESPDatatypes::Array(vec![
+                        id_reply(id),
+                        RESPDatatypes::BufBulk(pending.consumer.to_vec()),
+                        RESPDatatypes::Integer(pending.delivered_at as i64),
+                        RESPDatatypes::Integer(pending.deliveries as i64),
+                    ])
+                })
+                .collect();
+            let consumers = group
+                .consumers
+                .iter()
+                .map(|(name, consumer)| {
+                    let pending: Vec<_> = group.pending_of(name).collect();
+                    RESPDatatypes::Map(vec![
+                        field("name", RESPDatatypes::BufBulk(name.to_vec())),
+                        field("seen-time", RESPDatatypes::Integer(consumer.seen_at as i64)),
+                        field(
+                            "active-time",
+                            RESPDatatypes::Integer(consumer.active_at.map_or(-1, |at| at as i64)),
+                        ),
+                        field("pel-count", RESPDatatypes::Integer(pending.len() as i64)),
+                        field(
+                            "pending",
+                            RESPDatatypes::Array(
+                                pending
+                                    .into_iter()
+                                    .take(limit)
+                                    .map(|(id, pending)| {
+                                        RESPDatatypes::Array(vec![
+                                            id_reply(id),
+                                            RESPDatatypes::Integer(pending.delivered_at as i64),
+                                            RESPDatatypes::Integer(pending.deliveries as i64),
+                                        ])
+                                    })
+                                    .collect(),
+                            ),
+                        ),
+                    ])
+                })
+                .collect();
+            RESPDatatypes::Map(vec![
+                field("name", RESPDatatypes::BufBulk(name.to_vec())),
+                field("last-delivered-id", id_reply(group.last_delivered)),
+                field("entries-read", optional_integer(group.entries_read)),
+                field("lag", optional_integer(stream.lag(group))),
+                field(
+                    "pel-count",
+                    RESPDatatypes::Integer(group.pending.len() as i64),
+                ),
+                field("pending", RESPDatatypes::Array(pending)),
+                field("consumers", RESPDatatypes::Array(consumers)),
+            ])
+        })
+        .collect();
+    fields.push(field("groups", RESPDatatypes::Array(groups)));
+    RESPDatatypes::Map(fields)
+}
+
+fn group_reply(stream: &Stream, name: &[u8], group: &ConsumerGroup) -> RESPDatatypes {
+    RESPDatatypes::Map(vec![
+        field("name", RESPDatatypes::BufBulk(name.to_vec())),
+        field(
+            "consumers",
+            RESPDatatypes::Integer(group.consumers.len() as i64),
+        ),
+        field(
+            "pending",
+            RESPDatatypes::Integer(group.pending.len() as i64),
+        ),
+        field("last-delivered-id", id_reply(group.last_delivered)),
+        field("entries-read", optional_integer(group.entries_read)),
+        field("lag", optional_integer(stream.lag(group))),
+    ])
+}
+
+fn consumers_reply(group: &ConsumerGroup, now: u64) -> RESPDatatypes {
+    RESPDatatypes::Array(
+        group
+            .consumers
+            .iter()
+            .map(|(name, consumer)| {
+                let inactive = consumer
+                    .active_at
+                    .map_or(-1, |at| now.saturating_sub(at) as i64);
+                RESPDatatypes::Map(vec![
+                    field("name", RESPDatatypes::BufBulk(name.to_vec())),
+                    field(
+                        "pending",
+                        RESPDatatypes::Integer(group.pending_of(name).count() as i64),
+                    ),
+                    field(
+                        "idle",
+                        RESPDatatypes::Integer(now.saturating_sub(consumer.seen_at) as i64),
+                    ),
+                    field("inactive", RESPDatatypes::Integer(inactive)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+impl Command for XInfo {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xinfo"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let Some(subcommand) = self.args.first() else {
+            return fail(wrong_arity("xinfo"));
+        };
+        let subcommand = bytes_to_string(subcommand)
+            .unwrap_or("".to_string())
+            .to_lowercase();
+        let arity_ok = match subcommand.as_str() {
+            "stream" => self.args.len() >= 2,
+            "groups" => self.args.len() == 2,
+            "consumers" => self.args.len() == 3,
+            _ => {
+                return fail(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "ERR unknown subcommand '{}'. Try XINFO HELP.",
+                        String::from_utf8_lossy(&self.args[0])
+                    ),
+                ));
+            }
+        };
+        if !arity_ok {
+            return fail(wrong_arity(&format!("xinfo|{}", subcommand)));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[1])?;
+            // `None` is the plain form of `XINFO STREAM`, `Some` the `FULL` one and its count
+            let full = match (subcommand.as_str(), &self.args[2..]) {
+                ("stream", []) => None,
+                ("stream", [full, rest @ ..]) => {
+                    if !bytes_to_string(full)
+                        .unwrap_or("".to_string())
+                        .eq_ignore_ascii_case("full")
+                    {
+                        return Err(syntax_error());
+                    }
+                    Some(match rest {
+                        [] => Some(DEFAULT_FULL_COUNT),
+                        [option, count]
+                            if bytes_to_string(option)
+                                .unwrap_or("".to_string())
+                                .eq_ignore_ascii_case("count") =>
+                        {
+                            match parse_integer(count)? {
+                                count if count > 0 => Some(count as usize),
+                                _ => None,
+                            }
+                        }
+                        _ => return Err(syntax_error()),
+                    })
+                }
+                _ => None,
+            };
+            let now = unix_time_millis();
+
+            let cache = cache_repo.lock().await;
+            cache
+                .view(key.to_string(), |value| {
+                    let Some(stream) = as_stream(value)? else {
+                        return Err(Error::new(io::ErrorKind::InvalidInput, "ERR no such key"));
+                    };
+                    Ok(match subcommand.as_str() {
+                        "stream" => match full {
+                            Some(count) => full_stream_reply(stream, count),
+                            None => stream_reply(stream),
+                        },
+                        "groups" => RESPDatatypes::Array(
+                            stream
+                                .groups
+                                .iter()
+                                .map(|(name, group)| group_reply(stream, name, group))
+                                .collect(),
+                        ),
+                        _ => {
+                            let name = &self.args[2];
+                            let Some(group) = stream.groups.get(name) else {
+                                return Err(no_such_group(&key, name));
+                            };
+                            consumers_reply(group, now)
+                        }
+                    })
+                })
+                .await
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{cache::stream::as_stream, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `XLEN key`
+#[derive(Debug, Default)]
+pub struct XLen {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for XLen {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xlen"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 1 {
+            return fail(wrong_arity("xlen"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let cache = cache_repo.lock().await;
+            let len = cache
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_stream(value)?.map_or(0, |stream| stream.len()))
+                })
+                .await?;
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::BTreeMap, io};
+
+use crate::{
+    cache::{
+        core::unix_time_millis,
+        stream::{as_stream, no_such_key_or_group, StreamId},
+    },
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::{
+    core::{
+        fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+        RunResult,
+    },
+    xclaim::parse_millis,
+};
+
+/// `XPENDING key group [[IDLE min-idle-time] start end count [consumer]]`
+#[derive(Debug, Default)]
+pub struct XPending {
+    pub args: Vec<Vec<u8>>,
+}
+
+/// The filters of the extended form, which lists the pending entries themselves.
+#[derive(Debug)]
+struct PendingQuery {
+    min_idle: u64,
+    start: StreamId,
+    end: StreamId,
+    count: usize,
+    consumer: Option<Vec<u8>>,
+}
+
+impl PendingQuery {
+    /// Parses the arguments after the group.
+    fn parse(args: &[Vec<u8>]) -> io::Result<Option<PendingQuery>> {
+        if args.is_empty() {
+            return Ok(None);
+        }
+        let mut args = args;
+        let mut min_idle = 0;
+        if bytes_to_string(&args[0])
+            .unwrap_or("".to_string())
+            .eq_ignore_ascii_case("idle")
+        {
+            let Some(idle) = args.get(1) else {
+                return Err(syntax_error());
+            };
+            min_idle = parse_millis(idle, "ERR value is not an integer or out of range")?;
+            args = &args[2..];
+        }
+        let (start, end, count, consumer) = match args {
+            [start, end, count] => (start, end, count, None),
+            [start, end, count, consumer] => (start, end, count, Some(consumer.to_vec())),
+            _ => return Err(syntax_error()),
+        };
+        Ok(Some(PendingQuery {
+            min_idle,
+            start: StreamId::parse_bound(start, false)?,
+            end: StreamId::parse_bound(end, true)?,
+            count: parse_integer(count)?.max(0) as usize,
+            consumer,
+        }))
+    }
+}
+
+impl Command for XPending {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xpending"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("xpending"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let group = &self.args[1];
+            let query = PendingQuery::parse(&self.args[2..])?;
+            let now = unix_time_millis();
+
+            let cache = cache_repo.lock().await;
+            cache
+                .view(key.to_string(), |value| {
+                    let Some(group) =
+                        as_stream(value)?.and_then(|stream| stream.groups.get(group.as_slice()))
+                    else {
+                        return Err(no_such_key_or_group(&key, group));
+                    };
+
+                    let Some(query) = query else {
+                        let (Some((first, _)), Some((last, _))) = (
+                            group.pending.first_key_value(),
+                            group.pending.last_key_value(),
+                        ) else {
+                            return Ok(RESPDatatypes::Array(vec![
+                                RESPDatatypes::Integer(0),
+                                RESPDatatypes::NullString,
+                                RESPDatatypes::NullString,
+                                RESPDatatypes::NullArray,
+                            ]));
+                        };
+                        let mut per_consumer: BTreeMap<&[u8], usize> = BTreeMap::new();
+                        for pending in group.pending.values() {
+                            *per_consumer.entry(&pending.consumer).or_default() += 1;
+                        }
+                        return Ok(RESPDatatypes::Array(vec![
+                            RESPDatatypes::Integer(group.pending.len() as i64),
+                            RESPDatatypes::BulkString(first.to_string()),
+                            RESPDatatypes::BulkString(last.to_string()),
+                            RESPDatatypes::Array(
+                                per_consumer
+                                    .into_iter()
+                                    .map(|(consumer, count)| {
+                                        RESPDatatypes::Array(vec![
+                                            RESPDatatypes::BufBulk(consumer.to_vec()),
+                                            RESPDatatypes::BulkString(count.to_string()),
+                                        ])
+                                    })
+                                    .collect(),
+                            ),
+                        ]));
+                    };
+
+                    if query.start > query.end {
+                        return Ok(RESPDatatypes::Array(vec![]));
+                    }
+                    Ok(RESPDatatypes::Array(
+                        group
+                            .pending
+                            .range(query.start..=query.end)
+                            .filter(|(_, pending)| {
+                                query
+                                    .consumer
+                                    .as_ref()
+                                    .is_none_or(|consumer| pending.consumer == *consumer)
+                            })
+                            .map(|(id, pending)| {
+                                (id, pending, now.saturating_sub(pending.delivered_at))
+                            })
+                            .filter(|(_, _, idle)| *idle >= query.min_idle)
+                            .take(query.count)
+                            .map(|(id, pending, idle)| {
+                                RESPDatatypes::Array(vec![
+                                    RESPDatatypes::BulkString(id.to_string()),
+                                    RESPDatatypes::BufBulk(pending.consumer.to_vec()),
+                                    RESPDatatypes::Integer(idle as i64),
+                                    RESPDatatypes::Integer(pending.deliveries as i64),
+                                ])
+                            })
+                            .collect(),
+                    ))
+                })
+                .await
+        })
+    }
+}
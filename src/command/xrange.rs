@@ -0,0 +1,97 @@
+use std::io;
+
+use crate::{
+    cache::stream::{as_stream, StreamEntry, StreamId},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `XRANGE key start end [COUNT count]` and `XREVRANGE key end start [COUNT count]`
+#[derive(Debug, Default)]
+pub struct XRange {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+/// An entry as replies show it, its id followed by its flattened field value pairs.
+pub fn entry_reply((id, fields): StreamEntry) -> RESPDatatypes {
+    RESPDatatypes::Array(vec![
+        RESPDatatypes::BulkString(id.to_string()),
+        RESPDatatypes::Array(
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| {
+                    [RESPDatatypes::BufBulk(field), RESPDatatypes::BufBulk(value)]
+                })
+                .collect(),
+        ),
+    ])
+}
+
+pub fn entries_reply(entries: Vec<StreamEntry>) -> RESPDatatypes {
+    RESPDatatypes::Array(entries.into_iter().map(entry_reply).collect())
+}
+
+impl Command for XRange {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["xrange", "xrevrange"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 3 {
+            return fail(wrong_arity(&self.name));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let rev = self.name == "xrevrange";
+            let (start, end) = match rev {
+                true => (&self.args[2], &self.args[1]),
+                false => (&self.args[1], &self.args[2]),
+            };
+            let start = StreamId::parse_bound(start, false)?;
+            let end = StreamId::parse_bound(end, true)?;
+            let count = match &self.args[3..] {
+                [] => None,
+                [option, count]
+                    if bytes_to_string(option)
+                        .unwrap_or("".to_string())
+                        .eq_ignore_ascii_case("count") =>
+                {
+                    Some(parse_integer(count)?.max(0) as usize)
+                }
+                _ => return Err(syntax_error()),
+            };
+
+            let cache = cache_repo.lock().await;
+            let entries = cache
+                .view(key, |value| {
+                    Ok::<_, io::Error>(
+                        as_stream(value)?
+                            .map_or(vec![], |stream| stream.range(start, end, rev, count)),
+                    )
+                })
+                .await?;
+            Ok(entries_reply(entries))
+        })
+    }
+}
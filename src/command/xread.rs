@@ -0,0 +1,446 @@
+use std::{
+    io::{self, Error},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cache::{
+        blocking::{wait_until_served, BlockedOp},
+        core::{unix_time_millis, CacheRepository},
+        stream::{as_stream, as_stream_mut, StreamId},
+    },
+    cmd_queue::core::Propagator,
+    resp::{
+        core::{Protocol, RESPDatatypes},
+        deserialize::bytes_to_string,
+    },
+};
+
+use super::{
+    core::{
+        fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+        RunResult,
+    },
+    xclaim::claim_command,
+    xgroup::{create_consumer_command, set_id_command},
+    xrange::{entries_reply, entry_reply},
+};
+
+/// `XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]` and
+/// `XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds] [NOACK] STREAMS ...`,
+/// which reads as a consumer of a group.
+#[derive(Debug, Default)]
+pub struct XRead {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+    /// RESP3 clients get the entries of each stream in a map keyed by the stream
+    pub resp3: bool,
+    /// registered apart from `XREAD`, as only `XREADGROUP` writes
+    pub grouped: bool,
+}
+
+/// Where reading a stream starts.
+#[derive(Debug, Clone, Copy)]
+enum ReadFrom {
+    /// the entries above this id
+    After(StreamId),
+    /// `$`, only the entries added from now on
+    LastId,
+    /// `>`, the entries the group didn't deliver to anyone yet
+    Undelivered,
+}
+
+#[derive(Debug)]
+struct ReadQuery {
+    group: Option<(Vec<u8>, Vec<u8>)>,
+    count: Option<usize>,
+    /// `None` doesn't block, `Some(None)` waits forever
+    block: Option<Option<Duration>>,
+    noack: bool,
+    streams: Vec<(String, ReadFrom)>,
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(io::ErrorKind::InvalidInput, msg.to_string())
+}
+
+impl ReadQuery {
+    fn parse(name: &str, args: &[Vec<u8>], grouped: bool) -> io::Result<ReadQuery> {
+        let mut query = ReadQuery {
+            group: None,
+            count: None,
+            block: None,
+            noack: false,
+            streams: vec![],
+        };
+        let mut args = args.iter();
+        loop {
+            let option = bytes_to_string(args.next().ok_or_else(syntax_error)?)
+                .unwrap_or("".to_string())
+                .to_lowercase();
+            match option.as_str() {
+                "count" => {
+                    let count = parse_integer(args.next().ok_or_else(syntax_error)?)?;
+                    query.count = (count > 0).then_some(count as usize);
+                }
+                "block" => {
+                    let timeout = bytes_to_string(args.next().ok_or_else(syntax_error)?)
+                        .ok()
+                        .and_then(|timeout| timeout.parse::<i64>().ok())
+                        .ok_or_else(|| invalid("ERR timeout is not an integer or out of range"))?;
+                    if timeout < 0 {
+                        return Err(invalid("ERR timeout is negative"));
+                    }
+                    query.block =
+                        Some((timeout > 0).then(|| Duration::from_millis(timeout as u64)));
+                }
+                "group" if grouped => {
+                    let (Some(group), Some(consumer)) = (args.next(), args.next()) else {
+                        return Err(syntax_error());
+                    };
+                    query.group = Some((group.to_vec(), consumer.to_vec()));
+                }
+                "noack" if grouped => query.noack = true,
+                "streams" => break,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        let rest = args.as_slice();
+        if rest.is_empty() || !rest.len().is_multiple_of(2) {
+            return Err(invalid(&format!(
+                "ERR Unbalanced '{}' list of streams: for each stream key an ID or '$' must be \
+                 specified.",
+                name
+            )));
+        }
+        if grouped && query.group.is_none() {
+            return Err(invalid("ERR Missing GROUP option for XREADGROUP"));
+        }
+        let (keys, ids) = rest.split_at(rest.len() / 2);
+        for (key, id) in keys.iter().zip(ids) {
+            let from = match id.as_slice() {
+                b"$" if grouped => {
+                    return Err(invalid(
+                        "ERR The $ ID is meaningless in the context of XREADGROUP: you want to \
+                         read the history of this consumer by specifying a proper ID, or use \
+                         the > ID to get new messages. The $ ID would just return an empty \
+                         result set.",
+                    ));
+                }
+                b"$" => ReadFrom::LastId,
+                b">" if grouped => ReadFrom::Undelivered,
+                b">" => {
+                    return Err(invalid(
+                        "ERR The > ID can be specified only when calling XREADGROUP using the \
+                         GROUP <group> <consumer> option.",
+                    ));
+                }
+                id => ReadFrom::After(StreamId::parse(id, 0)?),
+            };
+            query.streams.push((parse_key(key)?, from));
+        }
+        Ok(query)
+    }
+
+    /// Reads every stream once, resolving `$` to the last id it has the first time. Returns the
+    /// streams that had something, with what they had.
+    async fn read(&mut self, cache: &CacheRepository) -> io::Result<Vec<(String, RESPDatatypes)>> {
+        let mut replies = Vec::new();
+        for (key, from) in self.streams.iter_mut() {
+            let entries = cache
+                .view(key.to_string(), |value| {
+                    let stream = as_stream(value)?;
+                    if let ReadFrom::LastId = from {
+                        *from =
+                            ReadFrom::After(stream.map_or(StreamId::MIN, |stream| stream.last_id));
+                    }
+                    let (Some(stream), ReadFrom::After(after)) = (stream, *from) else {
+                        return Ok::<_, io::Error>(vec![]);
+                    };
+                    Ok(match after.next() {
+                        Some(start) => stream.range(start, StreamId::MAX, false, self.count),
+                        None => vec![],
+                    })
+                })
+                .await?;
+            if !entries.is_empty() {
+                replies.push((key.to_string(), entries_reply(entries)));
+            }
+        }
+        Ok(replies)
+    }
+
+    /// Reads every stream once as a consumer of the group, handing it the new entries it gets.
+    /// The history of pending entries is replied even when there are none.
+    async fn read_group(
+        &self,
+        cache: &CacheRepository,
+        propagator: Option<&Propagator>,
+    ) -> io::Result<Vec<(String, RESPDatatypes)>> {
+        let (group, consumer) = self.group.as_ref().unwrap();
+        for (key, _) in self.streams.iter() {
+            let exists = cache
+                .view(key.to_string(), |value| {
+                    Ok::<_, io::Error>(
+                        as_stream(value)?.is_some_and(|stream| stream.groups.contains_key(group)),
+                    )
+                })
+                .await?;
+            if !exists {
+                return Err(invalid(&format!(
+                    "NOGROUP No such key '{}' or consumer group '{}' in XREADGROUP with GROUP \
+                     option",
+                    key,
+                    String::from_utf8_lossy(group)
+                )));
+            }
+        }
+
+        let now = unix_time_millis();
+        let mut replies = Vec::new();
+        for (key, from) in self.streams.iter() {
+            let (propagated, reply) = cache
+                .modify(key.to_string(), |value| {
+                    let Some(stream) = as_stream_mut(value).ok().flatten() else {
+                        return (vec![], None);
+                    };
+                    let Some(consumer_group) = stream.groups.get_mut(group) else {
+                        return (vec![], None);
+                    };
+                    let mut propagated = Vec::new();
+                    if consumer_group.touch_consumer(consumer, now) {
+                        propagated.push(create_consumer_command(key, group, consumer));
+                    }
+
+                    let ReadFrom::After(after) = *from else {
+                        let entries =
+                            stream.read_group(group, consumer, self.count, self.noack, now);
+                        if entries.is_empty() {
+                            return (propagated, None);
+                        }
+                        // replicas record the deliveries made here, then where the group is
+                        let consumer_group = &stream.groups[group];
+                        for (id, _) in entries.iter() {
+                            if let Some(pending) = consumer_group.pending.get(id) {
+                                propagated.push(claim_command(key, group, *id, pending));
+                            }
+                        }
+                        propagated.push(set_id_command(key, group, consumer_group));
+                        return (propagated, Some(entries_reply(entries)));
+                    };
+                    let history =
+                        stream.consumer_history(&stream.groups[group], consumer, after, self.count);
+                    let reply = RESPDatatypes::Array(
+                        history
+                            .into_iter()
+                            .map(|(id, fields)| match fields {
+                                Some(fields) => entry_reply((id, fields)),
+                                None => RESPDatatypes::Array(vec![
+                                    RESPDatatypes::BulkString(id.to_string()),
+                                    RESPDatatypes::NullArray,
+                                ]),
+                            })
+                            .collect(),
+                    );
+                    (propagated, Some(reply))
+                })
+                .await;
+
+            if let Some(propagator) = propagator {
+                for cmd in propagated {
+                    propagator.propagate(cmd).await;
+                }
+            }
+            if let Some(reply) = reply {
+                replies.push((key.to_string(), reply));
+            }
+        }
+        Ok(replies)
+    }
+}
+
+impl XRead {
+    fn reply(&self, replies: Vec<(String, RESPDatatypes)>) -> RESPDatatypes {
+        if replies.is_empty() {
+            return RESPDatatypes::NullArray;
+        }
+        let replies = replies
+            .into_iter()
+            .map(|(key, entries)| (RESPDatatypes::BulkString(key), entries));
+        match self.resp3 {
+            true => RESPDatatypes::Map(replies.collect()),
+            false => RESPDatatypes::Array(
+                replies
+                    .map(|(key, entries)| RESPDatatypes::Array(vec![key, entries]))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Command for XRead {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let name = if self.grouped { "xreadgroup" } else { "xread" };
+        let Some((name, args)) = parse_args(cmd, &[name]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run<'a>(
+        &'a mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&'a mut crate::connections::connection::Connection>,
+    ) -> RunResult<'a> {
+        let grouped = self.name == "xreadgroup";
+        if self.args.len() < 3 + 3 * grouped as usize {
+            return fail(wrong_arity(&self.name));
+        }
+        // only a client can wait, inside a transaction it reads without blocking
+        let conn = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            conn => conn,
+        };
+        self.resp3 = conn
+            .as_ref()
+            .is_some_and(|conn| conn.codec.protocol() == Protocol::Resp3);
+        let propagator = conn.as_ref().map(|conn| conn.propagator());
+
+        Box::pin(async move {
+            let mut query = ReadQuery::parse(&self.name, &self.args, grouped)?;
+            let waiting = propagator
+                .as_ref()
+                .is_some_and(|propagator| !propagator.is_transaction());
+            let mut conn = conn.filter(|_| waiting && query.block.is_some());
+            let deadline = query
+                .block
+                .flatten()
+                .map(|timeout| Instant::now() + timeout);
+            let keys: Vec<String> = query
+                .streams
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .collect();
+
+            loop {
+                let cache = cache_repo.lock().await;
+                let replies = match grouped {
+                    true => query.read_group(&cache, propagator.as_ref()).await?,
+                    false => query.read(&cache).await?,
+                };
+                let Some(conn) = conn.as_deref_mut().filter(|_| replies.is_empty()) else {
+                    return Ok(self.reply(replies));
+                };
+
+                // woken once entries are added, which may have been taken by someone else
+                // by the time this reads again
+                let op = if grouped {
+                    BlockedOp::XReadGroup
+                } else {
+                    BlockedOp::XRead
+                };
+                let (id, rx) = cache.blocked.lock().await.block(keys.clone(), op);
+                drop(cache);
+                let timeout =
+                    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                if wait_until_served(cache_repo.clone(), id, rx, timeout, conn)
+                    .await?
+                    .is_none()
+                {
+                    return Ok(RESPDatatypes::NullArray);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::Mutex;
+
+    use crate::{
+        aof::core::replay,
+        cache::{
+            blocking::encode_command,
+            core::{CacheRepository, Value},
+        },
+        command::core::test_support::{aof_path, client, send},
+    };
+
+    #[tokio::test]
+    async fn transactions_propagate_stream_writes() {
+        let path = aof_path("stream-transaction");
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, _peer) = client(repo.clone(), Some(path.clone())).await;
+
+        send(&mut conn, &repo, &["MULTI"]).await;
+        send(&mut conn, &repo, &["XADD", "s", "1-1", "f", "a"]).await;
+        send(&mut conn, &repo, &["XADD", "s", "1-2", "f", "b"]).await;
+        send(
+            &mut conn,
+            &repo,
+            &["XADD", "s", "MAXLEN", "3", "1-3", "f", "c"],
+        )
+        .await;
+        send(
+            &mut conn,
+            &repo,
+            &["XADD", "s", "MAXLEN", "3", "1-4", "f", "d"],
+        )
+        .await;
+        send(&mut conn, &repo, &["XGROUP", "CREATE", "s", "g", "0"]).await;
+        // a blocking read inside a transaction returns right away
+        let args = [
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "alice",
+            "BLOCK",
+            "0",
+            "STREAMS",
+            "s",
+            ">",
+        ];
+        send(&mut conn, &repo, &args).await;
+        send(&mut conn, &repo, &["XACK", "s", "g", "1-2"]).await;
+        send(&mut conn, &repo, &["XCLAIM", "s", "g", "bob", "0", "1-3"]).await;
+        send(&mut conn, &repo, &["EXEC"]).await;
+
+        let streamed = conn.cmdq.lock().await.backlog.read_from(0).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), streamed);
+        assert!(streamed.starts_with(&encode_command(&["MULTI"])));
+        assert!(streamed.ends_with(&encode_command(&["EXEC"])));
+
+        let restarted = Arc::new(Mutex::new(CacheRepository::default()));
+        replay(&path, restarted.clone()).await.unwrap();
+        let Some(Value::Stream(mut replayed)) =
+            restarted.lock().await.get_value("s".to_string()).await
+        else {
+            panic!("expected the stream to be replayed");
+        };
+        let Some(Value::Stream(mut stream)) = repo.lock().await.get_value("s".to_string()).await
+        else {
+            panic!("expected a stream");
+        };
+        // consumers are seen again when their commands are replayed
+        for group in stream
+            .groups
+            .values_mut()
+            .chain(replayed.groups.values_mut())
+        {
+            group.consumers.clear();
+        }
+        assert_eq!(replayed, stream);
+        assert_eq!(stream.entries.len(), 3);
+        assert_eq!(stream.groups[b"g".as_slice()].pending.len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::stream::{as_stream_mut, StreamId},
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wrong_arity, Command,
+    RunResult,
+};
+
+/// `XSETID key last-id [ENTRIESADDED entries-added] [MAXDELETEDID max-deleted-id]`
+#[derive(Debug, Default)]
+pub struct XSetId {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(io::ErrorKind::InvalidInput, msg.to_string())
+}
+
+impl Command for XSetId {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["xsetid"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("xsetid"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let last_id = StreamId::parse(&self.args[1], 0)?;
+            let (mut entries_added, mut max_deleted_id) = (None, None);
+            let mut args = self.args[2..].iter();
+            while let Some(arg) = args.next() {
+                let value = args.next().ok_or_else(syntax_error)?;
+                match bytes_to_string(arg)
+                    .unwrap_or("".to_string())
+                    .to_lowercase()
+                    .as_str()
+                {
+                    "entriesadded" => match parse_integer(value)? {
+                        added if added < 0 => {
+                            return Err(invalid("ERR entries_added must be positive"));
+                        }
+                        added => entries_added = Some(added as u64),
+                    },
+                    "maxdeletedid" => {
+                        let id = StreamId::parse(value, 0)?;
+                        if last_id < id {
+                            return Err(invalid(
+                                "ERR The ID specified in XSETID is smaller than the provided \
+                                 max_deleted_entry_id",
+                            ));
+                        }
+                        max_deleted_id = Some(id);
+                    }
+                    _ => return Err(syntax_error()),
+                }
+            }
+
+            let cache = cache_repo.lock().await;
+            cache
+                .modify(key, |value| {
+                    let Some(stream) = as_stream_mut(value)? else {
+                        return Err(invalid("ERR no such key"));
+                    };
+                    if entries_added.is_some_and(|added| added < stream.len() as u64) {
+                        return Err(invalid(
+                            "ERR The entries_added specified in XSETID is smaller than the \
+                             target stream length",
+                        ));
+                    }
+                    if stream
+                        .entries
+                        .last_key_value()
+                        .is_some_and(|(top, _)| last_id < *top)
+                    {
+                        return Err(invalid(
+                            "ERR The ID specified in XSETID is smaller than the target stream \
+                             top item",
+                        ));
+                    }
+                    stream.last_id = last_id;
+                    if let Some(entries_added) = entries_added {
+                        stream.entries_added = entries_added;
+                    }
+                    if let Some(max_deleted_id) = max_deleted_id {
+                        stream.max_deleted_id = max_deleted_id;
+                    }
+                    Ok(())
+                })
+                .await?;
+
+            if let Some(propagator) = propagator {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            Ok(RESPDatatypes::SimpleString("OK".to_string()))
+        })
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::sorted_set::as_zset_or_create,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::{
+    core::{
+        fail, parse_args, parse_key, queued, syntax_error, wake_blocked, wrong_arity, Command,
+        RunResult,
+    },
+    incrbyfloat::parse_float,
+};
+
+/// `ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member [score member ...]`
+#[derive(Debug, Default)]
+pub struct ZAdd {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Default)]
+struct ZAddOptions {
+    nx: bool,
+    xx: bool,
+    gt: bool,
+    lt: bool,
+    ch: bool,
+    incr: bool,
+    pairs: Vec<(f64, Vec<u8>)>,
+}
+
+pub fn nan_score_error() -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        "ERR resulting score is not a number (NaN)",
+    )
+}
+
+impl ZAdd {
+    fn parse_options(&self) -> io::Result<ZAddOptions> {
+        let mut options = ZAddOptions::default();
+        let mut idx = 1;
+        while let Some(arg) = self.args.get(idx) {
+            let flag = match bytes_to_string(arg)
+                .unwrap_or("".to_string())
+                .to_lowercase()
+                .as_str()
+            {
+                "nx" => &mut options.nx,
+                "xx" => &mut options.xx,
+                "gt" => &mut options.gt,
+                "lt" => &mut options.lt,
+                "ch" => &mut options.ch,
+                "incr" => &mut options.incr,
+                _ => break,
+            };
+            *flag = true;
+            idx += 1;
+        }
+
+        let pairs = &self.args[idx..];
+        if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+            return Err(syntax_error());
+        }
+        if options.nx && options.xx {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR XX and NX options at the same time are not compatible",
+            ));
+        }
+        if (options.gt && options.lt) || (options.nx && (options.gt || options.lt)) {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR GT, LT, and/or NX options at the same time are not compatible",
+            ));
+        }
+        if options.incr && pairs.len() > 2 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR INCR option supports a single increment-element pair",
+            ));
+        }
+        for pair in pairs.chunks(2) {
+            options
+                .pairs
+                .push((parse_float(&pair[0])?, pair[1].to_vec()));
+        }
+        Ok(options)
+    }
+}
+
+impl Command for ZAdd {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["zadd"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 3 {
+            return fail(wrong_arity("zadd"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let options = self.parse_options()?;
+
+            let cache = cache_repo.lock().await;
+            let (added, changed, score) = cache
+                .modify(key.to_string(), |value| {
+                    let zset = as_zset_or_create(value)?;
+                    let (mut added, mut changed, mut score) = (0, 0, None);
+                    for (increment, member) in options.pairs.iter() {
+                        let current = zset.score(member);
+                        if (options.nx && current.is_some()) || (options.xx && current.is_none()) {
+                            continue;
+                        }
+                        let new = match (options.incr, current) {
+                            (true, Some(current)) => current + increment,
+                            _ => *increment,
+                        };
+                        if new.is_nan() {
+                            return Err(nan_score_error());
+                        }
+                        if let Some(current) = current {
+                            if (options.gt && new <= current) || (options.lt && new >= current) {
+                                continue;
+                            }
+                            if new != current {
+                                changed += 1;
+                            }
+                        } else {
+                            added += 1;
+                        }
+                        zset.insert(member.to_vec(), new);
+                        score = Some(new);
+                    }
+                    Ok((added, changed, score))
+                })
+                .await?;
+
+            if added + changed > 0 {
+                if let Some(propagator) = propagator.as_ref() {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+                wake_blocked(&cache, key, propagator.as_ref()).await;
+            }
+
+            Ok(match (options.incr, score) {
+                (true, Some(score)) => RESPDatatypes::Double(score),
+                (true, None) => RESPDatatypes::NullString,
+                (false, _) if options.ch => RESPDatatypes::Integer(added + changed),
+                (false, _) => RESPDatatypes::Integer(added),
+            })
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    cache::sorted_set::{as_zset, LexBound, Range, ScoreBound},
+    resp::core::RESPDatatypes,
+};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `ZCOUNT key min max`, and `ZLEXCOUNT` for a range of members sharing a score.
+#[derive(Debug, Default)]
+pub struct ZCount {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for ZCount {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["zcount", "zlexcount"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity(&self.name));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let range = match self.name.as_str() {
+                "zcount" => Range::Score(
+                    ScoreBound::parse(&self.args[1])?,
+                    ScoreBound::parse(&self.args[2])?,
+                ),
+                _ => Range::Lex(
+                    LexBound::parse(&self.args[1])?,
+                    LexBound::parse(&self.args[2])?,
+                ),
+            };
+
+            let count = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_zset(value)?.map_or(0, |zset| zset.count(&range)))
+                })
+                .await?;
+            Ok(RESPDatatypes::Integer(count as i64))
+        })
+    }
+}
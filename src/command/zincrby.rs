@@ -0,0 +1,68 @@
+use crate::{cache::sorted_set::as_zset_or_create, resp::core::RESPDatatypes};
+
+use super::{
+    core::{fail, parse_args, parse_key, queued, wake_blocked, wrong_arity, Command, RunResult},
+    incrbyfloat::parse_float,
+    zadd::nan_score_error,
+};
+
+/// `ZINCRBY key increment member`
+#[derive(Debug, Default)]
+pub struct ZIncrBy {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for ZIncrBy {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["zincrby"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 3 {
+            return fail(wrong_arity("zincrby"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let increment = parse_float(&self.args[1])?;
+            let member = &self.args[2];
+
+            let cache = cache_repo.lock().await;
+            let score = cache
+                .modify(key.to_string(), |value| {
+                    let zset = as_zset_or_create(value)?;
+                    let score = zset.score(member).unwrap_or(0.0) + increment;
+                    if score.is_nan() {
+                        return Err(nan_score_error());
+                    }
+                    zset.insert(member.to_vec(), score);
+                    Ok(score)
+                })
+                .await?;
+
+            if let Some(propagator) = propagator.as_ref() {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            wake_blocked(&cache, key, propagator.as_ref()).await;
+            Ok(RESPDatatypes::Double(score))
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::sorted_set::as_zset_mut,
+    resp::core::{Protocol, RESPDatatypes},
+};
+
+use super::{
+    core::{fail, parse_args, parse_integer, parse_key, queued, wrong_arity, Command, RunResult},
+    zrange::scored_reply,
+};
+
+/// `ZPOPMIN` and `ZPOPMAX key [count]`
+#[derive(Debug, Default)]
+pub struct ZPop {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+    /// RESP3 clients get members with their scores as nested arrays when there is a count
+    pub resp3: bool,
+}
+
+impl Command for ZPop {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["zpopmin", "zpopmax"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.is_empty() || self.args.len() > 2 {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => {
+                self.resp3 = conn.codec.protocol() == Protocol::Resp3;
+                Some(conn.propagator())
+            }
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let count = match self.args.get(1) {
+                Some(count) => {
+                    let count = parse_integer(count)?;
+                    if count < 0 {
+                        return Err(Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "ERR value is out of range, must be positive",
+                        ));
+                    }
+                    Some(count as usize)
+                }
+                None => None,
+            };
+            let max = self.name == "zpopmax";
+
+            let cache = cache_repo.lock().await;
+            let popped = cache
+                .modify(key, |value| {
+                    Ok::<_, io::Error>(
+                        as_zset_mut(value)?
+                            .map_or(vec![], |zset| zset.pop(count.unwrap_or(1), max)),
+                    )
+                })
+                .await?;
+
+            if !popped.is_empty() {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(scored_reply(popped, true, self.resp3 && count.is_some()))
+        })
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::{self, Error};
+
+use crate::{
+    cache::{
+        core::Value,
+        sorted_set::{as_zset, LexBound, Range, ScoreBound, SortedSet},
+    },
+    resp::{
+        core::{Protocol, RESPDatatypes},
+        deserialize::bytes_to_string,
+    },
+    utils::range::clamp_range,
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wake_blocked, wrong_arity,
+    Command, RunResult,
+};
+
+/// `ZRANGE key start stop [BYSCORE | BYLEX] [REV] [LIMIT offset count] [WITHSCORES]` and
+/// `ZRANGESTORE destination key start stop ...`, which stores the members it selects.
+#[derive(Debug, Default)]
+pub struct ZRange {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+    /// RESP3 clients get members with their scores as nested arrays instead of a flat one
+    pub resp3: bool,
+    /// registered apart from `ZRANGE`, as only `ZRANGESTORE` writes
+    pub stores: bool,
+}
+
+#[derive(Debug)]
+enum Selection {
+    /// 0 based ranks that may count from the end when negative
+    Rank(i64, i64),
+    Range(Range),
+}
+
+#[derive(Debug)]
+struct RangeQuery {
+    selection: Selection,
+    rev: bool,
+    offset: usize,
+    limit: Option<usize>,
+    with_scores: bool,
+}
+
+impl RangeQuery {
+    /// Parses the arguments after the key.
+    fn parse(args: &[Vec<u8>], can_reply_scores: bool) -> io::Result<RangeQuery> {
+        let (mut by_score, mut by_lex, mut rev, mut with_scores) = (false, false, false, false);
+        let mut limit = None;
+        let mut options = args[2..].iter();
+        while let Some(option) = options.next() {
+            match bytes_to_string(option)
+                .unwrap_or("".to_string())
+                .to_lowercase()
+                .as_str()
+            {
+                "byscore" => by_score = true,
+                "bylex" => by_lex = true,
+                "rev" => rev = true,
+                "withscores" if can_reply_scores => with_scores = true,
+                "limit" => {
+                    let offset = parse_integer(options.next().ok_or_else(syntax_error)?)?;
+                    let count = parse_integer(options.next().ok_or_else(syntax_error)?)?;
+                    limit = Some((offset, count));
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        if by_score && by_lex {
+            return Err(syntax_error());
+        }
+        if limit.is_some() && !by_score && !by_lex {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX",
+            ));
+        }
+        if with_scores && by_lex {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "ERR syntax error, WITHSCORES not supported in combination with BYLEX",
+            ));
+        }
+
+        // a reversed range is given from its end to its start
+        let (start, stop) = match rev && (by_score || by_lex) {
+            true => (&args[1], &args[0]),
+            false => (&args[0], &args[1]),
+        };
+        let selection = if by_score {
+            Selection::Range(Range::Score(
+                ScoreBound::parse(start)?,
+                ScoreBound::parse(stop)?,
+            ))
+        } else if by_lex {
+            Selection::Range(Range::Lex(LexBound::parse(start)?, LexBound::parse(stop)?))
+        } else {
+            Selection::Rank(parse_integer(start)?, parse_integer(stop)?)
+        };
+
+        // a negative offset selects nothing and a negative count everything after the offset
+        let (offset, limit) = match limit {
+            Some((offset, _)) if offset < 0 => (0, Some(0)),
+            Some((offset, count)) => (offset as usize, (count >= 0).then_some(count as usize)),
+            None => (0, None),
+        };
+        Ok(RangeQuery {
+            selection,
+            rev,
+            offset,
+            limit,
+            with_scores,
+        })
+    }
+
+    fn select(&self, zset: &SortedSet) -> Vec<(Vec<u8>, f64)> {
+        match &self.selection {
+            Selection::Rank(start, stop) => match clamp_range(*start, *stop, zset.len()) {
+                Some((start, stop)) => zset.range_by_rank(start, stop, self.rev),
+                None => vec![],
+            },
+            Selection::Range(range) => zset.range(range, self.rev, self.offset, self.limit),
+        }
+    }
+}
+
+/// Replies with members, each followed by its score when `with_scores`.
+pub fn scored_reply(entries: Vec<(Vec<u8>, f64)>, with_scores: bool, resp3: bool) -> RESPDatatypes {
+    let mut reply = Vec::with_capacity(entries.len() * (1 + with_scores as usize));
+    for (member, score) in entries {
+        match (with_scores, resp3) {
+            (false, _) => reply.push(RESPDatatypes::BufBulk(member)),
+            (true, false) => {
+                reply.push(RESPDatatypes::BufBulk(member));
+                reply.push(RESPDatatypes::Double(score));
+            }
+            (true, true) => reply.push(RESPDatatypes::Array(vec![
+                RESPDatatypes::BufBulk(member),
+                RESPDatatypes::Double(score),
+            ])),
+        }
+    }
+    RESPDatatypes::Array(reply)
+}
+
+impl Command for ZRange {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let name = if self.stores { "zrangestore" } else { "zrange" };
+        let Some((name, args)) = parse_args(cmd, &[name]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        let stores = self.name == "zrangestore";
+        if self.args.len() < 3 + stores as usize {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => {
+                self.resp3 = conn.codec.protocol() == Protocol::Resp3;
+                Some(conn.propagator())
+            }
+            None => None,
+        };
+
+        Box::pin(async move {
+            let mut args = self.args.as_slice();
+            let destination = match stores {
+                true => {
+                    let (destination, rest) = args.split_first().unwrap();
+                    args = rest;
+                    Some(parse_key(destination)?)
+                }
+                false => None,
+            };
+            let key = parse_key(&args[0])?;
+            let query = RangeQuery::parse(&args[1..], !stores)?;
+
+            let cache = cache_repo.lock().await;
+            let selected = cache
+                .view(key, |value| {
+                    Ok::<_, io::Error>(as_zset(value)?.map_or(vec![], |zset| query.select(zset)))
+                })
+                .await?;
+
+            let Some(destination) = destination else {
+                return Ok(scored_reply(selected, query.with_scores, self.resp3));
+            };
+            let len = selected.len();
+            if len > 0 {
+                let mut zset = SortedSet::default();
+                for (member, score) in selected {
+                    zset.insert(member, score);
+                }
+                cache
+                    .insert(destination.to_string(), Value::SortedSet(zset), None)
+                    .await?;
+            } else {
+                cache.remove(destination.to_string()).await;
+            }
+            if let Some(propagator) = propagator.as_ref() {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            wake_blocked(&cache, destination, propagator.as_ref()).await;
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    cache::sorted_set::as_zset,
+    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+};
+
+use super::core::{
+    fail, parse_args, parse_key, queued, syntax_error, wrong_arity, Command, RunResult,
+};
+
+/// `ZRANK` and `ZREVRANK key member [WITHSCORE]`
+#[derive(Debug, Default)]
+pub struct ZRank {
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for ZRank {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["zrank", "zrevrank"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 || self.args.len() > 3 {
+            return fail(wrong_arity(&self.name));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let member = &self.args[1];
+            let with_score = match self.args.get(2) {
+                Some(arg) => {
+                    if bytes_to_string(arg)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        != "withscore"
+                    {
+                        return Err(syntax_error());
+                    }
+                    true
+                }
+                None => false,
+            };
+            let rev = self.name == "zrevrank";
+
+            let ranked = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(
+                        as_zset(value)?
+                            .and_then(|zset| Some((zset.rank(member, rev)?, zset.score(member)?))),
+                    )
+                })
+                .await?;
+            Ok(match ranked {
+                Some((rank, score)) if with_score => RESPDatatypes::Array(vec![
+                    RESPDatatypes::Integer(rank as i64),
+                    RESPDatatypes::Double(score),
+                ]),
+                Some((rank, _)) => RESPDatatypes::Integer(rank as i64),
+                None if with_score => RESPDatatypes::NullArray,
+                None => RESPDatatypes::NullString,
+            })
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{cache::sorted_set::as_zset_mut, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `ZREM key member [member ...]`
+#[derive(Debug, Default)]
+pub struct ZRem {
+    pub cmd: Vec<u8>,
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for ZRem {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["zrem"]) else {
+            return false;
+        };
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 2 {
+            return fail(wrong_arity("zrem"));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+
+            let cache = cache_repo.lock().await;
+            let removed = cache
+                .modify(key, |value| {
+                    let Some(zset) = as_zset_mut(value)? else {
+                        return Ok(0);
+                    };
+                    Ok::<_, std::io::Error>(
+                        self.args[1..]
+                            .iter()
+                            .filter(|member| zset.remove(member))
+                            .count(),
+                    )
+                })
+                .await?;
+
+            if removed > 0 {
+                if let Some(propagator) = propagator {
+                    propagator.propagate(self.cmd.to_vec()).await;
+                }
+            }
+            Ok(RESPDatatypes::Integer(removed as i64))
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{cache::sorted_set::as_zset, resp::core::RESPDatatypes};
+
+use super::core::{fail, parse_args, parse_key, queued, wrong_arity, Command, RunResult};
+
+/// `ZSCORE key member`
+#[derive(Debug, Default)]
+pub struct ZScore {
+    pub args: Vec<Vec<u8>>,
+}
+
+impl Command for ZScore {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((_, args)) = parse_args(cmd, &["zscore"]) else {
+            return false;
+        };
+        self.args = args;
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() != 2 {
+            return fail(wrong_arity("zscore"));
+        }
+        if let Some(conn) = conn {
+            if conn.is_in_transaction() {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+        }
+
+        Box::pin(async move {
+            let key = parse_key(&self.args[0])?;
+            let member = &self.args[1];
+
+            let score = cache_repo
+                .lock()
+                .await
+                .view(key, |value| {
+                    Ok::<_, std::io::Error>(as_zset(value)?.and_then(|zset| zset.score(member)))
+                })
+                .await?;
+            Ok(match score {
+                Some(score) => RESPDatatypes::Double(score),
+                None => RESPDatatypes::NullString,
+            })
+        })
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    io::{self, Error},
+};
+
+use crate::{
+    cache::{
+        core::{wrong_type, Value},
+        sorted_set::SortedSet,
+    },
+    resp::{
+        core::RESPDatatypes,
+        deserialize::{bytes_to_string, bytes_to_type},
+    },
+};
+
+use super::core::{
+    fail, parse_args, parse_integer, parse_key, queued, syntax_error, wake_blocked, wrong_arity,
+    Command, RunResult,
+};
+
+/// `ZUNIONSTORE` and `ZINTERSTORE destination numkeys key [key ...] [WEIGHTS weight
+/// [weight ...]] [AGGREGATE SUM | MIN | MAX]`
+///
+/// Plain sets can be given too, their members count with a score of 1.
+#[derive(Debug, Default)]
+pub struct ZStore {
+    pub cmd: Vec<u8>,
+    pub name: String,
+    pub args: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            // inf - inf counts as 0 rather than turning into a score that isn't a number
+            Aggregate::Sum => match a + b {
+                sum if sum.is_nan() => 0.0,
+                sum => sum,
+            },
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ZStoreOptions {
+    destination: String,
+    keys: Vec<String>,
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+}
+
+impl ZStore {
+    fn parse_options(&self) -> io::Result<ZStoreOptions> {
+        let numkeys = parse_integer(&self.args[1])?;
+        if numkeys < 1 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "ERR at least 1 input key is needed for '{}' command",
+                    self.name
+                ),
+            ));
+        }
+        let numkeys = numkeys as usize;
+        if numkeys > self.args.len() - 2 {
+            return Err(syntax_error());
+        }
+
+        let mut options = ZStoreOptions {
+            destination: parse_key(&self.args[0])?,
+            keys: self.args[2..2 + numkeys]
+                .iter()
+                .map(|key| parse_key(key))
+                .collect::<io::Result<_>>()?,
+            weights: vec![1.0; numkeys],
+            aggregate: Aggregate::Sum,
+        };
+        let mut args = self.args[2 + numkeys..].iter();
+        while let Some(option) = args.next() {
+            match bytes_to_string(option)
+                .unwrap_or("".to_string())
+                .to_lowercase()
+                .as_str()
+            {
+                "weights" => {
+                    for weight in options.weights.iter_mut() {
+                        let arg = args.next().ok_or_else(syntax_error)?;
+                        *weight = bytes_to_type::<f64>(arg)
+                            .ok()
+                            .filter(|weight| !weight.is_nan())
+                            .ok_or_else(|| {
+                                Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    "ERR weight value is not a float",
+                                )
+                            })?;
+                    }
+                }
+                "aggregate" => {
+                    let aggregate = args.next().ok_or_else(syntax_error)?;
+                    options.aggregate = match bytes_to_string(aggregate)
+                        .unwrap_or("".to_string())
+                        .to_lowercase()
+                        .as_str()
+                    {
+                        "sum" => Aggregate::Sum,
+                        "min" => Aggregate::Min,
+                        "max" => Aggregate::Max,
+                        _ => return Err(syntax_error()),
+                    };
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// The members of a sorted set or a set with their scores, nothing for a missing key.
+fn scored_members(value: Option<&Value>) -> io::Result<Vec<(Vec<u8>, f64)>> {
+    match value {
+        Some(Value::SortedSet(zset)) => Ok(zset
+            .iter()
+            .map(|(member, score)| (member.to_vec(), score))
+            .collect()),
+        Some(Value::Set(set)) => Ok(set.iter().map(|member| (member.to_vec(), 1.0)).collect()),
+        Some(_) => Err(wrong_type()),
+        None => Ok(vec![]),
+    }
+}
+
+impl Command for ZStore {
+    fn can_execute(&mut self, cmd: &RESPDatatypes) -> bool {
+        let Some((name, args)) = parse_args(cmd, &["zunionstore", "zinterstore"]) else {
+            return false;
+        };
+        self.name = name;
+        self.args = args;
+        self.cmd = cmd.encode();
+        true
+    }
+
+    fn run(
+        &mut self,
+        cache_repo: std::sync::Arc<tokio::sync::Mutex<crate::cache::core::CacheRepository>>,
+        conn: Option<&mut crate::connections::connection::Connection>,
+    ) -> RunResult<'_> {
+        if self.args.len() < 3 {
+            return fail(wrong_arity(&self.name));
+        }
+        let propagator = match conn {
+            Some(conn) if conn.is_in_transaction() => {
+                conn.add_tnx(Box::new(std::mem::take(self)));
+                return queued();
+            }
+            Some(conn) => Some(conn.propagator()),
+            None => None,
+        };
+
+        Box::pin(async move {
+            let options = self.parse_options()?;
+            let intersect = self.name == "zinterstore";
+
+            let cache = cache_repo.lock().await;
+            let mut combined: HashMap<Vec<u8>, f64> = HashMap::new();
+            for (idx, key) in options.keys.iter().enumerate() {
+                let members = cache.view(key.to_string(), scored_members).await?;
+                let weight = options.weights[idx];
+                let weighted = members.into_iter().map(|(member, score)| {
+                    // 0 * inf counts as 0 as well
+                    let score = match score * weight {
+                        score if score.is_nan() => 0.0,
+                        score => score,
+                    };
+                    (member, score)
+                });
+
+                if idx == 0 {
+                    combined.extend(weighted);
+                } else if intersect {
+                    let mut kept = HashMap::with_capacity(combined.len());
+                    for (member, score) in weighted {
+                        if let Some(current) = combined.get(&member) {
+                            let score = options.aggregate.apply(*current, score);
+                            kept.insert(member, score);
+                        }
+                    }
+                    combined = kept;
+                } else {
+                    for (member, score) in weighted {
+                        combined
+                            .entry(member)
+                            .and_modify(|current| {
+                                *current = options.aggregate.apply(*current, score)
+                            })
+                            .or_insert(score);
+                    }
+                }
+            }
+
+            let len = combined.len();
+            if len > 0 {
+                let mut zset = SortedSet::default();
+                for (member, score) in combined {
+                    zset.insert(member, score);
+                }
+                cache
+                    .insert(
+                        options.destination.to_string(),
+                        Value::SortedSet(zset),
+                        None,
+                    )
+                    .await?;
+            } else {
+                cache.remove(options.destination.to_string()).await;
+            }
+            if let Some(propagator) = propagator.as_ref() {
+                propagator.propagate(self.cmd.to_vec()).await;
+            }
+            wake_blocked(&cache, options.destination, propagator.as_ref()).await;
+            Ok(RESPDatatypes::Integer(len as i64))
+        })
+    }
+}
@@ -1,34 +1,56 @@
 use std::{
     io::{self},
     net::SocketAddr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Instant,
 };
 
+use bytes::BytesMut;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
     sync::Mutex,
 };
+use tokio_util::codec::Decoder;
 
 use crate::{
     cache::core::CacheRepository,
     cli::config::Config,
-    cmd_queue::core::CmdQueue,
+    cmd_queue::core::{CmdQueue, Propagator},
     command::core::{run, run_command, Command},
-    resp::{
-        core::RESPDatatypes,
-        deserialize::{bytes_to_string, Deseralize},
-    },
+    rdb::{self, decode::RdbDecoder},
+    resp::{codec::RespCodec, core::RESPDatatypes, deserialize::bytes_to_string},
 };
 
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
 #[derive(Debug)]
 pub struct SlaveConfig {
     pub port: String,
     pub exp: Instant,
-    pub last_cmd_id: Option<String>,
     pub send_output: Option<bool>,
-    pub bytes_offset: u16,
+    /// replication offset the replica has been sent up to
+    pub offset: u64,
+    /// replication offset the replica last acknowledged with `REPLCONF ACK`
+    pub ack_offset: Arc<AtomicU64>,
+    /// replication id the replica synced with, its offsets mean nothing under another one
+    pub repl_id: String,
+}
+
+impl SlaveConfig {
+    pub fn new(port: String) -> Self {
+        SlaveConfig {
+            port,
+            exp: Instant::now(),
+            send_output: None,
+            offset: 0,
+            ack_offset: Arc::new(AtomicU64::new(0)),
+            repl_id: String::new(),
+        }
+    }
 }
 
 pub struct Connection {
@@ -39,9 +61,21 @@ pub struct Connection {
     pub tnxs: Option<Vec<Box<dyn Command + 'static>>>,
     pub server_config: Config,
     pub slave_config: Option<SlaveConfig>,
-    pub send_rdb_file: Option<()>,
+    pub send_rdb_file: Option<Vec<u8>>,
+    pub is_replica: bool,
     pub cmdq: Arc<Mutex<CmdQueue>>,
     pub is_master: bool,
+    pub buffer: BytesMut,
+    pub awaiting_rdb: bool,
+    pub client_id: u64,
+    pub client_name: Option<String>,
+    pub codec: RespCodec,
+    pub write_offset: Arc<AtomicU64>,
+    pub upstream_frame: Option<Vec<u8>>,
+    /// collects the writes of the transaction `EXEC` is running
+    pub tnx_propagator: Option<Propagator>,
+    /// set once the client closed its side, noticed while a command was blocked
+    pub closed: bool,
 }
 
 impl Connection {
@@ -62,152 +96,236 @@ impl Connection {
             server_config: config,
             slave_config: None,
             send_rdb_file: None,
+            is_replica: false,
             cmdq,
             is_master,
+            buffer: BytesMut::new(),
+            awaiting_rdb: is_master,
+            client_id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            client_name: None,
+            codec: RespCodec::requests(),
+            write_offset: Arc::new(AtomicU64::new(0)),
+            upstream_frame: None,
+            tnx_propagator: None,
+            closed: false,
         }
     }
 
     pub async fn process(&mut self) {
         loop {
             // println!("called with {}", self.send_rdb_file.is_none());
-            if self.is_master && self.process_master().await {
-                break;
-            } else if self.send_rdb_file.is_none() && self.process_client().await {
-                break;
-            } else if self.send_rdb_file.is_some() && self.process_slave().await {
+            let closed = if self.is_master {
+                self.process_master().await
+            } else if !self.is_replica {
+                self.process_client().await
+            } else {
+                self.process_slave().await
+            };
+            if closed {
                 break;
             }
         }
+        // the keep alive probe holds a clone of the socket, so dropping ours doesn't close it
+        let _ = self.stream.shutdown().await;
     }
 
     async fn process_master(&mut self) -> bool {
         let repo = self.repo.clone();
-        let mut buff: Vec<u8> = Vec::new();
-
-        let mut buf = Vec::with_capacity(10);
-        let mut counter = 0;
-        loop {
-            let size = self
-                .stream
-                .read_buf(&mut buf)
-                .await
-                .expect("cant read message from master");
-
-            buff.append(&mut buf);
-
-            if size <= counter || size == 0 {
-                break;
+        // the handshake may already have buffered the start of the stream, so only read once
+        // there is nothing left to apply
+        match self.drain_master_stream(repo).await {
+            Ok(true) => false,
+            Ok(false) => {
+                let read_count = self.stream.read_buf(&mut self.buffer).await.unwrap_or(0);
+                if read_count == 0 {
+                    println!("master closed the replication link");
+                    return true;
+                }
+                self.server_config
+                    .replication_config
+                    .write()
+                    .await
+                    .master_link
+                    .last_io = Some(Instant::now());
+                false
+            }
+            Err(err) => {
+                println!("dropping the replication link: {}", err);
+                true
             }
-            counter = size;
-
-            buf = Vec::with_capacity(100);
         }
-        let mut last_rdb_idx = 0;
+    }
 
-        loop {
-            if last_rdb_idx >= buff.len() {
-                break;
-            }
-            let (_, rest) = buff.split_at(last_rdb_idx);
+    /// Applies every complete frame of the replication stream that is buffered. Returns
+    /// whether anything was consumed, or an error when the stream can't be followed anymore.
+    async fn drain_master_stream(&mut self, repo: Arc<Mutex<CacheRepository>>) -> io::Result<bool> {
+        let mut progressed = false;
 
-            match bytes_to_string(rest) {
-                Ok(_) => {
-                    break;
-                }
-                Err(err) if err.kind() == io::ErrorKind::InvalidInput => {
-                    last_rdb_idx += 1;
-                }
-                Err(e) => {
-                    println!("{}", e);
-                    panic!("something went wrong");
-                }
+        if self.awaiting_rdb {
+            let Some(rdb) = self.codec.decode_rdb(&mut self.buffer)? else {
+                return Ok(progressed);
             };
+            self.awaiting_rdb = false;
+            progressed = true;
+            let loaded = self.load_rdb_from_master(&rdb).await?;
+            println!("loaded {} keys from the master", loaded);
+
+            let mut replication_config = self.server_config.replication_config.write().await;
+            replication_config.master_link.sync_in_progress = false;
+            replication_config.master_link.up = true;
         }
 
-        let (_, mut rem_cmd_buf) = buff.split_at(last_rdb_idx);
-        let mut cmd_idx = 0;
-        let deserialzer = Deseralize {};
-
         loop {
-            let cache_repo = repo.clone();
-            let (cmd, rem) = rem_cmd_buf.split_at(cmd_idx);
-
-            match deserialzer.deseralize(&mut cmd.to_vec()) {
-                Ok(RESPDatatypes::Array(resp)) => {
-                    println!("called for cmd {:?}", resp);
-                    let cmd = RESPDatatypes::Array(resp);
-                    let byte_size = cmd.encode().len();
-                    let op = run_command(cmd, cache_repo, self).await;
-                    rem_cmd_buf = rem;
-                    cmd_idx = 0;
-                    if let Some(slave_config) = self.slave_config.as_mut() {
-                        if let Some(send_output) = slave_config.send_output.take() {
-                            if send_output {
-                                self.stream.write_all(&op).await.unwrap();
-                            }
-                        }
-
-                        slave_config.bytes_offset += byte_size as u16;
-                        println!("{:?}", slave_config);
-                    }
-
-                    continue;
-                }
-
-                _ => {
-                    // println!("{:?}", bytes_to_string(cmd));
-                    cmd_idx += 1;
+            let Some((cmd, consumed)) = self.codec.decode_frame(&self.buffer)? else {
+                break;
+            };
+            let raw = self.buffer.split_to(consumed).to_vec();
+            progressed = true;
+
+            // sub-replicas get the stream exactly as it came, writes forward it themselves
+            // while the keyspace is locked, anything else is forwarded once it ran
+            let frame_end = self.cmdq.lock().await.backlog.offset() + raw.len() as u64;
+            self.upstream_frame = Some(raw);
+            let op = run_command(cmd, repo.clone(), self).await;
+            if let Some(raw) = self.upstream_frame.take() {
+                let mut cmdq = self.cmdq.lock().await;
+                if cmdq.backlog.offset() < frame_end {
+                    cmdq.feed(&raw).await;
                 }
             }
 
-            if rem.is_empty() {
-                break;
+            if let Some(slave_config) = self.slave_config.as_mut() {
+                if let Some(send_output) = slave_config.send_output.take() {
+                    if send_output {
+                        self.stream.write_all(&op).await?;
+                    }
+                }
             }
         }
-        false
+        Ok(progressed)
     }
 
     async fn process_client(&mut self) -> bool {
         let repo = self.repo.clone();
-        let mut buff = Vec::new();
 
-        let read_count = self.stream.read_buf(&mut buff).await.unwrap_or(0);
+        let read_count = self.stream.read_buf(&mut self.buffer).await.unwrap_or(0);
         if read_count == 0 {
             return true;
         }
 
-        let res = run(&mut buff, repo, self).await;
-        if res.is_empty() {
-            println!("found eof");
-            return true;
+        loop {
+            let mut buff = std::mem::take(&mut self.buffer);
+            let res = run(&mut buff, repo.clone(), self).await;
+            // what was read while a command blocked comes after what is left to run
+            let read_while_blocked = std::mem::replace(&mut self.buffer, buff);
+            self.buffer.extend_from_slice(&read_while_blocked);
+            if self.closed {
+                return true;
+            }
+
+            if !res.is_empty() {
+                if self.stream.write_all(&res).await.is_err() {
+                    return true;
+                }
+                self.send_rdb_file_to_replica().await;
+            }
+            if read_while_blocked.is_empty() || self.is_replica {
+                return false;
+            }
         }
+    }
 
-        if !self.is_master {
-            self.stream.write_all(&res).await.unwrap();
-            self.send_rdb_file_to_replica().await;
+    /// Reads what the client sends while one of its commands is blocked, which runs once that
+    /// command is done, and returns when the client closed the connection.
+    pub async fn read_until_closed(&mut self) {
+        loop {
+            match self.stream.read_buf(&mut self.buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
         }
-        false
+        self.closed = true;
     }
 
     async fn process_slave(&mut self) -> bool {
         let mut slave_config = self.slave_config.take().unwrap();
-        let last_cmd_id = slave_config.last_cmd_id.take();
-        let cmdq = self.cmdq.lock().await;
-        if let Some((last_id, mut cmd_buffs)) = cmdq.get_all_cmds_after_id(last_cmd_id).await {
-            slave_config.last_cmd_id = Some(last_id);
-            while let Some(cmd) = cmd_buffs.pop() {
-                match self.stream.write_all(&cmd).await {
-                    Ok(_) => {}
-                    Err(err) => {
-                        println!("slave has died err {}", err);
-                        return true;
-                    }
+        let closed = self.stream_to_replica(&mut slave_config).await;
+        self.slave_config.replace(slave_config);
+        closed
+    }
+
+    /// Streams the replication backlog to a replica, waiting for new writes or for its
+    /// acknowledgements once it has caught up.
+    async fn stream_to_replica(&mut self, slave_config: &mut SlaveConfig) -> bool {
+        self.read_replica_acks(slave_config).await;
+
+        // happens when this server was promoted or resynced from its own master
+        let history_changed = self
+            .server_config
+            .replication_config
+            .read()
+            .await
+            .master_repl_id
+            != slave_config.repl_id;
+        if history_changed {
+            println!("replication history changed, dropping replica");
+            return true;
+        }
+
+        let (pending, mut offset_rx) = {
+            let cmdq = self.cmdq.lock().await;
+            (
+                cmdq.backlog.read_from(slave_config.offset),
+                cmdq.subscribe(),
+            )
+        };
+
+        match pending {
+            None => {
+                println!("replica fell behind the replication backlog");
+                true
+            }
+            Some(pending) if pending.is_empty() => {
+                tokio::select! {
+                    changed = offset_rx.changed() => changed.is_err(),
+                    read = self.stream.read_buf(&mut self.buffer) => matches!(read, Ok(0) | Err(_)),
                 }
             }
+            Some(pending) => match self.stream.write_all(&pending).await {
+                Ok(_) => {
+                    slave_config.offset += pending.len() as u64;
+                    false
+                }
+                Err(err) => {
+                    println!("slave has died err {}", err);
+                    true
+                }
+            },
         }
+    }
 
-        self.slave_config.replace(slave_config);
-        false
+    /// Records the `REPLCONF ACK <offset>` replies the replica has sent so far.
+    async fn read_replica_acks(&mut self, slave_config: &SlaveConfig) {
+        let mut acked = false;
+        loop {
+            match self.codec.decode(&mut self.buffer) {
+                Ok(Some(frame)) => {
+                    if let Some(offset) = parse_replconf_ack(&frame) {
+                        slave_config.ack_offset.store(offset, Ordering::Release);
+                        acked = true;
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    println!("invalid reply from replica: {}", err);
+                    self.buffer.clear();
+                    break;
+                }
+            }
+        }
+        if acked {
+            self.cmdq.lock().await.acknowledge();
+        }
     }
 
     pub fn is_in_transaction(&self) -> bool {
@@ -236,34 +354,126 @@ impl Connection {
         self.tnxs.take()
     }
 
+    /// The handle write commands propagate through.
+    pub fn propagator(&self) -> Propagator {
+        if let Some(propagator) = self.tnx_propagator.as_ref() {
+            return propagator.clone();
+        }
+        Propagator::new(
+            self.cmdq.clone(),
+            self.write_offset.clone(),
+            self.upstream_frame.clone(),
+        )
+    }
+
     pub fn get_id(&self) -> String {
         self.id.to_string()
     }
 
     pub async fn send_rdb_file_to_replica(&mut self) {
-        // println!("{:?}", self.send_rdb_file);
-        if self.send_rdb_file.is_some() {
-            // println!("called for send_rdb_file");
+        if let Some(rdb) = self.send_rdb_file.take() {
+            self.stream
+                .write_all(&RESPDatatypes::RDBFile(rdb).encode())
+                .await
+                .unwrap();
+        }
+    }
 
-            let res = self.send_empty_rdb_file();
-            let buff = match res {
-                Ok(buf) => buf,
-                Err(err) => {
-                    self.slave_config.take();
-                    RESPDatatypes::SimpleError(Box::new(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        err,
-                    )))
-                    .encode()
-                }
-            };
+    /// Replaces the keyspace with the snapshot a master sent on `FULLRESYNC`.
+    async fn load_rdb_from_master(&mut self, rdb: &[u8]) -> io::Result<usize> {
+        let entries = RdbDecoder::default().decode(rdb)?;
+        let offset = self
+            .server_config
+            .replication_config
+            .read()
+            .await
+            .master_repl_offset;
+
+        let cache = self.repo.lock().await;
+        // the stream continues from the snapshot, so does the backlog sub-replicas read
+        self.cmdq.lock().await.reset_backlog(offset).await;
+        cache.clear().await;
+        rdb::core::restore(&cache, entries).await
+    }
+}
 
-            self.stream.write_all(&buff).await.unwrap();
+fn parse_replconf_ack(frame: &RESPDatatypes) -> Option<u64> {
+    let RESPDatatypes::Array(args) = frame else {
+        return None;
+    };
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match arg {
+            RESPDatatypes::BufBulk(buf) => bytes_to_string(buf).unwrap_or("".to_string()),
+            _ => "".to_string(),
+        })
+        .collect();
+    match args.as_slice() {
+        [cmd, sub_cmd, offset]
+            if cmd.to_lowercase() == "replconf" && sub_cmd.to_lowercase() == "ack" =>
+        {
+            offset.parse().ok()
         }
+        _ => None,
     }
+}
 
-    pub fn send_empty_rdb_file(&mut self) -> io::Result<Vec<u8>> {
-        let vec = hex::decode("524544495330303131fa0972656469732d76657205372e322e30fa0a72656469732d62697473c040fa056374696d65c26d08bc65fa08757365642d6d656dc2b0c41000fa08616f662d62617365c000fff06e3bfec0ff5aa2").unwrap();
-        Ok(RESPDatatypes::RDBFile(vec).encode())
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::{io::AsyncReadExt, sync::Mutex};
+
+    use crate::{
+        cache::{blocking::encode_command, core::CacheRepository},
+        cli::core::Roles,
+        command::core::test_support::client,
+    };
+
+    #[tokio::test]
+    async fn forwards_the_master_stream_unchanged() {
+        let repo = Arc::new(Mutex::new(CacheRepository::default()));
+        let (mut conn, mut master) = client(repo.clone(), None).await;
+        conn.is_master = true;
+        conn.server_config.replication_config.write().await.role =
+            Roles::Slave("127.0.0.1 6379".to_string());
+
+        let mut applied = encode_command(&["MULTI"]);
+        applied.extend(encode_command(&["SET", "k", "1"]));
+        applied.extend(encode_command(&["EXEC"]));
+        // changes nothing, it still moves the offset and reaches the sub-replicas
+        applied.extend(encode_command(&["SET", "k", "2", "NX"]));
+        let mut stream = applied.clone();
+        stream.extend(encode_command(&["REPLCONF", "GETACK", "*"]));
+        // the last frame is incomplete and waits for the rest
+        let partial = encode_command(&["SET", "later", "1"]);
+        conn.buffer.extend_from_slice(&stream);
+        conn.buffer.extend_from_slice(&partial[..5]);
+
+        assert!(conn.drain_master_stream(repo.clone()).await.unwrap());
+        assert_eq!(&conn.buffer[..], &partial[..5]);
+        assert!(!conn.drain_master_stream(repo.clone()).await.unwrap());
+
+        let cmdq = conn.cmdq.lock().await;
+        assert_eq!(cmdq.backlog.read_from(0).unwrap(), stream);
+        let offset = conn
+            .server_config
+            .replication_config
+            .read()
+            .await
+            .master_repl_offset;
+        assert_eq!(offset, stream.len() as u64);
+        drop(cmdq);
+        assert_eq!(
+            repo.lock().await.get("k".to_string()).await.unwrap(),
+            Some(b"1".to_vec())
+        );
+
+        // the acknowledgement counts what was applied before GETACK
+        let ack_offset = applied.len().to_string();
+        let ack = encode_command(&["REPLCONF", "ACK", ack_offset.as_str()]);
+        let mut reply = vec![0; ack.len()];
+        master.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply, ack);
     }
 }
@@ -0,0 +1,293 @@
+use std::{
+    fmt::Display,
+    io::{self, Error},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bytes::BytesMut;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Mutex,
+    time,
+};
+use tokio_util::codec::Decoder;
+
+use crate::{
+    cache::core::CacheRepository,
+    cli::config::{Config, ReplicationConfig},
+    cmd_queue::core::CmdQueue,
+    resp::{codec::RespCodec, core::RESPDatatypes},
+};
+
+use super::connection::Connection;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+// a master that accepted the connection but never answers the handshake is as good as down
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub enum Capabilities {
+    Psync,
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Psync => write!(f, "psync"),
+        }
+    }
+}
+
+pub enum Replconf {
+    ListeningPort(u16),
+    Capa(Capabilities),
+}
+
+/// A replica's connection to its master. It keeps reconnecting, with exponential backoff,
+/// for as long as the server runs, so a master restart doesn't take the replica down.
+pub struct MasterLink {
+    addr: String,
+    config: Config,
+    repo: Arc<Mutex<CacheRepository>>,
+    cmdq: Arc<Mutex<CmdQueue>>,
+    // set after the first sync, from then on the replica asks to continue where it stopped
+    synced: bool,
+}
+
+impl MasterLink {
+    pub fn new(
+        addr: String,
+        config: Config,
+        repo: Arc<Mutex<CacheRepository>>,
+        cmdq: Arc<Mutex<CmdQueue>>,
+    ) -> Self {
+        MasterLink {
+            addr,
+            config,
+            repo,
+            cmdq,
+            synced: false,
+        }
+    }
+
+    /// Starts supervising the link in the background, in place of the current one if any.
+    pub async fn spawn(self) {
+        let replication_config = self.config.replication_config.clone();
+        let task = tokio::spawn(self.supervise());
+
+        let mut replication_config = replication_config.write().await;
+        if let Some(previous) = replication_config
+            .master_link
+            .task
+            .replace(task.abort_handle())
+        {
+            previous.abort();
+        }
+    }
+
+    /// Stops replicating, dropping the link to the current master if there is one.
+    pub fn stop(replication_config: &mut ReplicationConfig) {
+        if let Some(task) = replication_config.master_link.task.take() {
+            task.abort();
+        }
+        replication_config.master_link.up = false;
+        replication_config.master_link.sync_in_progress = false;
+        replication_config.master_link.last_io = None;
+    }
+
+    async fn supervise(mut self) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            match self.connect().await {
+                Ok(mut connection) => {
+                    backoff = INITIAL_BACKOFF;
+                    self.synced = true;
+                    connection.process().await;
+                }
+                Err(err) => println!("unable to sync with master {}: {}", self.addr, err),
+            }
+
+            {
+                let mut replication_config = self.config.replication_config.write().await;
+                replication_config.master_link.up = false;
+                replication_config.master_link.sync_in_progress = false;
+            }
+
+            time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Connects and runs the PING/REPLCONF/PSYNC handshake, returning the connection the
+    /// replication stream is applied from.
+    async fn connect(&mut self) -> io::Result<Connection> {
+        let addr = self.addr.trim().replace(" ", ":");
+        let mut master = TcpStream::connect(addr).await?;
+        let peer_addr = master.peer_addr()?;
+
+        let mut buff = BytesMut::new();
+        let full_resync = time::timeout(HANDSHAKE_TIMEOUT, self.handshake(&mut master, &mut buff))
+            .await
+            .map_err(|_| Error::new(io::ErrorKind::TimedOut, "master didn't answer in time"))??;
+
+        {
+            let mut replication_config = self.config.replication_config.write().await;
+            replication_config.master_link.sync_in_progress = full_resync;
+            replication_config.master_link.up = !full_resync;
+        }
+
+        let mut connection = Connection::new(
+            master,
+            peer_addr,
+            self.repo.clone(),
+            self.config.clone(),
+            self.cmdq.clone(),
+            true,
+        );
+        // the handshake may already have read the start of the stream
+        connection.buffer = buff;
+        connection.awaiting_rdb = full_resync;
+        Ok(connection)
+    }
+
+    async fn handshake(&mut self, master: &mut TcpStream, buff: &mut BytesMut) -> io::Result<bool> {
+        let ping_cmd =
+            RESPDatatypes::Array(vec![RESPDatatypes::BulkString("PING".to_string())]).encode();
+        master.write_all(&ping_cmd).await?;
+
+        let reply = self.read_reply(master, buff).await?;
+        if reply_to_string(&reply) != "PONG" {
+            return Err(Error::new(
+                io::ErrorKind::InvalidData,
+                "master didn't answer PING",
+            ));
+        }
+
+        self.send_replconf(
+            master,
+            buff,
+            Replconf::ListeningPort(self.config.server_config.port),
+        )
+        .await?;
+
+        self.send_replconf(master, buff, Replconf::Capa(Capabilities::Psync))
+            .await?;
+
+        self.send_psync(master, buff).await
+    }
+
+    /// Reads from the master until `buff` holds a complete reply and takes it off the buffer.
+    async fn read_reply(
+        &mut self,
+        master: &mut TcpStream,
+        buff: &mut BytesMut,
+    ) -> io::Result<RESPDatatypes> {
+        let mut codec = RespCodec::default();
+        loop {
+            if let Some(reply) = codec.decode(buff)? {
+                self.config
+                    .replication_config
+                    .write()
+                    .await
+                    .master_link
+                    .last_io = Some(Instant::now());
+                return Ok(reply);
+            }
+            if master.read_buf(buff).await? == 0 {
+                return Err(Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "master closed connection",
+                ));
+            }
+        }
+    }
+
+    pub async fn send_replconf(
+        &mut self,
+        master: &mut TcpStream,
+        buff: &mut BytesMut,
+        arg: Replconf,
+    ) -> io::Result<()> {
+        let cmd = match arg {
+            Replconf::ListeningPort(port) => RESPDatatypes::Array(vec![
+                RESPDatatypes::BulkString("REPLCONF".to_string()),
+                RESPDatatypes::BulkString("listening-port".to_string()),
+                RESPDatatypes::BulkString(format!("{}", port)),
+            ])
+            .encode(),
+            Replconf::Capa(cap) => RESPDatatypes::Array(vec![
+                RESPDatatypes::BulkString("REPLCONF".to_string()),
+                RESPDatatypes::BulkString("capa".to_string()),
+                RESPDatatypes::BulkString(cap.to_string()),
+            ])
+            .encode(),
+        };
+        master.write_all(&cmd).await?;
+
+        let reply = reply_to_string(&self.read_reply(master, buff).await?);
+        if reply != "OK" {
+            return Err(Error::new(
+                io::ErrorKind::InvalidData,
+                format!("master refused REPLCONF: {}", reply),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Asks the master for the replication stream, from where the last sync stopped if there
+    /// was one. Returns true when it answered with `FULLRESYNC`, meaning a snapshot comes
+    /// first, and false on `CONTINUE`.
+    pub async fn send_psync(
+        &mut self,
+        master: &mut TcpStream,
+        buff: &mut BytesMut,
+    ) -> io::Result<bool> {
+        let (repl_id, offset) = if self.synced {
+            let replication_config = self.config.replication_config.read().await;
+            (
+                replication_config.master_repl_id.to_string(),
+                // the first byte that hasn't been applied yet
+                (replication_config.master_repl_offset + 1).to_string(),
+            )
+        } else {
+            ("?".to_string(), "-1".to_string())
+        };
+        let cmd = RESPDatatypes::Array(vec![
+            RESPDatatypes::BulkString("PSYNC".to_string()),
+            RESPDatatypes::BulkString(repl_id),
+            RESPDatatypes::BulkString(offset),
+        ])
+        .encode();
+        master.write_all(&cmd).await?;
+
+        let reply = reply_to_string(&self.read_reply(master, buff).await?);
+        let vars: Vec<&str> = reply.split(" ").collect();
+        let mut replication_config = self.config.replication_config.write().await;
+        match vars.as_slice() {
+            ["FULLRESYNC", repl_id, offset] => {
+                replication_config.master_repl_id = repl_id.to_string();
+                replication_config.master_repl_offset = offset.parse().unwrap_or(0);
+                Ok(true)
+            }
+            ["CONTINUE", repl_id] => {
+                replication_config.master_repl_id = repl_id.to_string();
+                Ok(false)
+            }
+            ["CONTINUE"] => Ok(false),
+            _ => Err(Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid PSYNC reply from master: {}", reply),
+            )),
+        }
+    }
+}
+
+fn reply_to_string(reply: &RESPDatatypes) -> String {
+    match reply {
+        RESPDatatypes::SimpleString(data) => data.trim().to_string(),
+        _ => "".to_string(),
+    }
+}
@@ -1,2 +1,3 @@
 pub mod connection;
+pub mod master_link;
 pub mod server;
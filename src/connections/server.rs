@@ -1,51 +1,36 @@
 use core::panic;
 use std::{
     io::{self, Write},
-    net::{Ipv4Addr, SocketAddr},
     sync::Arc,
     time::Duration,
 };
 
 use clap::Parser;
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
     net::{TcpListener, TcpStream},
-    sync::Mutex,
+    sync::{Mutex, RwLock},
     task::JoinHandle,
     time,
 };
 
 use crate::{
+    aof::core::{fsync_every_second, replay, Aof},
     cache::core::CacheRepository,
-    cli::{config::Config, core::BaseCliArgs},
+    cli::{
+        config::{Config, FsyncPolicy, MasterLinkState, ReplicationConfig},
+        core::{BaseCliArgs, Roles},
+    },
     cmd_queue::core::CmdQueue,
-    resp::{core::RESPDatatypes, deserialize::bytes_to_string},
+    rdb::core::{load_from_file, restore},
 };
 
-use super::connection::Connection;
+use super::{connection::Connection, master_link::MasterLink};
 
 pub struct Server {
     listener: TcpListener,
     config: Config,
 }
 
-pub enum Capabilities {
-    Psync,
-}
-
-impl Capabilities {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Psync => "psync".to_string(),
-        }
-    }
-}
-
-pub enum Replconf {
-    ListeningPort(u16),
-    Capa(Capabilities),
-}
-
 impl Server {
     pub async fn new() -> Self {
         let args = BaseCliArgs::parse();
@@ -56,135 +41,66 @@ impl Server {
             listener: TcpListener::bind(addr).await.unwrap(),
             config: Config {
                 server_config: crate::cli::config::ServerConfig { port },
-                replication_config: crate::cli::config::ReplicationConfig {
+                replication_config: Arc::new(RwLock::new(ReplicationConfig {
                     role: args.get_role(),
-                    master_repl_offset: None,
-                    master_repl_id: None,
+                    master_repl_id: args.generate_master_id(),
+                    master_repl_offset: 0,
+                    master_link: MasterLinkState::default(),
+                    replica_read_only: args.is_replica_read_only(),
+                })),
+                persistence_config: crate::cli::config::PersistenceConfig {
+                    dir: args.get_dir(),
+                    dbfilename: args.get_dbfilename(),
+                    appendonly: args.is_appendonly(),
+                    appendfilename: args.get_appendfilename(),
+                    appendfsync: args.get_appendfsync(),
                 },
             },
         }
     }
 
-    pub async fn initalize(&mut self) -> io::Result<Option<TcpStream>> {
-        if let crate::cli::core::Roles::Slave(raw_addr) =
-            self.config.replication_config.role.clone()
-        {
-            let addr = raw_addr.trim().replace(" ", ":");
-
-            // we dont want propapate error upwards when server addr is incorrect or something.
-            let mut master = TcpStream::connect(addr).await?;
-            let ping_cmd =
-                RESPDatatypes::Array(vec![RESPDatatypes::BulkString("PING".to_string())]).encode();
-            let write_size = master.write(&ping_cmd).await.unwrap();
-            if write_size == 0 {
-                panic!("unable to PING master");
-            }
-
-            let mut buff = Vec::new();
-            let read_count = master.read_buf(&mut buff).await.unwrap_or(0);
-            if read_count == 0 {
-                panic!("Master closed connection")
-            }
-
-            let reply = bytes_to_string(&buff)
-                .unwrap_or("".to_string())
-                .trim()
-                .to_string();
-            if reply != "+PONG" {
-                panic!("Master closed connection")
-            }
-
-            self.send_replconf(
-                &mut master,
-                Replconf::ListeningPort(self.config.server_config.port),
-            )
-            .await?;
-
-            self.send_replconf(&mut master, Replconf::Capa(Capabilities::Psync))
-                .await?;
-
-            self.send_psync(&mut master).await?;
-
-            return Ok(Some(master));
-        }
-        Ok(None)
+    pub fn config(&self) -> &Config {
+        &self.config
     }
 
-    pub async fn send_replconf(&mut self, master: &mut TcpStream, arg: Replconf) -> io::Result<()> {
-        let cmd = match arg {
-            Replconf::ListeningPort(port) => RESPDatatypes::Array(vec![
-                RESPDatatypes::BulkString("REPLCONF".to_string()),
-                RESPDatatypes::BulkString("listening-port".to_string()),
-                RESPDatatypes::BulkString(format!("{}", port)),
-            ])
-            .encode(),
-            Replconf::Capa(cap) => RESPDatatypes::Array(vec![
-                RESPDatatypes::BulkString("REPLCONF".to_string()),
-                RESPDatatypes::BulkString("capa".to_string()),
-                RESPDatatypes::BulkString(cap.to_string()),
-            ])
-            .encode(),
+    /// Restores the keyspace from the RDB file, if there is one. A file that exists but can't
+    /// be read is fatal, starting empty would drop whatever it holds on the next save.
+    async fn load_snapshot(&self, cache_repo: Arc<Mutex<CacheRepository>>) {
+        let path = self.config.persistence_config.rdb_path();
+        let entries = match load_from_file(&path).await {
+            Ok(Some(entries)) => entries,
+            Ok(None) => return,
+            Err(err) => panic!("unable to load {}: {}", path.display(), err),
         };
 
-        let write_size = master.write(&cmd).await.unwrap_or(0);
-        if write_size == 0 {
-            panic!("unable to PING master");
+        let repo = cache_repo.lock().await;
+        match restore(&repo, entries).await {
+            Ok(loaded) => println!("loaded {} keys from {}", loaded, path.display()),
+            Err(err) => panic!("unable to load {}: {}", path.display(), err),
         }
-
-        let mut buff = Vec::new();
-        let read_count = master.read_buf(&mut buff).await.unwrap_or(0);
-        if read_count == 0 {
-            panic!("Master closed connection")
-        }
-
-        let reply = bytes_to_string(&buff)
-            .unwrap_or("".to_string())
-            .trim()
-            .to_string();
-
-        if reply != "+OK" {
-            panic!("Master failed replication");
-        }
-
-        Ok(())
     }
 
-    pub async fn send_psync(&mut self, master: &mut TcpStream) -> io::Result<()> {
-        let cmd = RESPDatatypes::Array(vec![
-            RESPDatatypes::BulkString("PSYNC".to_string()),
-            RESPDatatypes::BulkString("?".to_string()),
-            RESPDatatypes::BulkString("-1".to_string()),
-        ])
-        .encode();
-
-        let write_size = master.write(&cmd).await.unwrap_or(0);
-        if write_size == 0 {
-            panic!("unable to PING master");
-        }
-
-        let mut buff = Vec::new();
-        let read_count = master.read_buf(&mut buff).await.unwrap_or(0);
-        if read_count == 0 {
-            panic!("Master closed connection")
+    /// Replays the append only file, which supersedes the snapshot when enabled, then keeps
+    /// appending every write to it.
+    async fn load_aof(&self, cache_repo: Arc<Mutex<CacheRepository>>, cmdq: Arc<Mutex<CmdQueue>>) {
+        let persistence_config = &self.config.persistence_config;
+        let path = persistence_config.aof_path();
+        match replay(&path, cache_repo).await {
+            Ok(replayed) => println!("replayed {} commands from {}", replayed, path.display()),
+            Err(err) => panic!("unable to load {}: {}", path.display(), err),
         }
 
-        let reply = bytes_to_string(&buff)
-            .unwrap_or("".to_string())
-            .trim()
-            .to_string();
-
-        if reply.starts_with("+FULLRESYNC") {
-            let vars: Vec<&str> = reply.split(" ").collect();
-            let repl_id = vars.get(1).unwrap().to_string();
-            let offset = vars.get(2).unwrap().to_string();
+        let aof = match Aof::open(path.clone(), persistence_config.appendfsync) {
+            Ok(aof) => aof,
+            Err(err) => panic!("unable to open {}: {}", path.display(), err),
+        };
+        cmdq.lock().await.aof = Some(aof);
 
-            self.config.replication_config.master_repl_id = Some(repl_id);
-            self.config.replication_config.master_repl_offset = Some(offset.parse().unwrap_or(0));
-        } else {
-            panic!("Invalid response from master");
+        if persistence_config.appendfsync == FsyncPolicy::EverySec {
+            tokio::spawn(async move {
+                fsync_every_second(cmdq).await;
+            });
         }
-
-        Ok(())
     }
 
     pub async fn event_loop(
@@ -193,14 +109,22 @@ impl Server {
         cmd_queue: Arc<Mutex<CmdQueue>>,
     ) {
         // println!("event loop in thread {:?}", std::thread::current().id());
-        if let Some(master) = self.initalize().await.unwrap() {
-            let stream = (
-                master,
-                SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
-            );
-            let repo = cache_repo.clone();
-            let cmdq = cmd_queue.clone();
-            self.event_processor(Ok(stream), repo, cmdq, true);
+        if self.config.persistence_config.appendonly {
+            self.load_aof(cache_repo.clone(), cmd_queue.clone()).await;
+        } else {
+            self.load_snapshot(cache_repo.clone()).await;
+        }
+
+        let role = self.config.replication_config.read().await.role.clone();
+        if let Roles::Slave(addr) = role {
+            MasterLink::new(
+                addr,
+                self.config.clone(),
+                cache_repo.clone(),
+                cmd_queue.clone(),
+            )
+            .spawn()
+            .await;
         }
 
         loop {
@@ -214,7 +138,7 @@ impl Server {
             let repo = cache_repo.clone();
             let cmdq = cmd_queue.clone();
 
-            self.event_processor(stream, repo, cmdq, false);
+            self.event_processor(stream, repo, cmdq);
         }
     }
 
@@ -223,7 +147,6 @@ impl Server {
         stream: Result<(TcpStream, std::net::SocketAddr), io::Error>,
         repo: Arc<Mutex<CacheRepository>>,
         cmdq: Arc<Mutex<CmdQueue>>,
-        is_master: bool,
     ) {
         match stream {
             Ok((stream, addr)) => {
@@ -237,7 +160,7 @@ impl Server {
                     repo.clone(),
                     self.config.clone(),
                     cmdq.clone(),
-                    is_master,
+                    false,
                 );
                 let jh = tokio::spawn(async move {
                     // println!("connected! and in thread {:?}", std::thread::current().id());
@@ -1,3 +1,5 @@
 pub mod command_not_found;
 pub mod eof;
 pub mod value_is_not_type;
+pub mod wrong_arity;
+pub mod wrong_type;
@@ -0,0 +1,18 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug)]
+pub struct WrongArity {
+    pub cmd: String,
+}
+
+impl Error for WrongArity {}
+
+impl Display for WrongArity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ERR wrong number of arguments for '{}' command",
+            self.cmd.to_lowercase()
+        )
+    }
+}
@@ -0,0 +1,15 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug)]
+pub struct WrongType;
+
+impl Error for WrongType {}
+
+impl Display for WrongType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        )
+    }
+}
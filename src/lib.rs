@@ -0,0 +1,10 @@
+pub mod aof;
+pub mod cache;
+pub mod cli;
+pub mod cmd_queue;
+pub mod command;
+pub mod connections;
+pub mod errors;
+pub mod rdb;
+pub mod resp;
+pub mod utils;
@@ -0,0 +1,112 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::cache::core::{instant_to_unix_millis, unix_millis_to_instant, CacheRepository, Value};
+
+use super::{decode::RdbDecoder, encode::RdbEncoder};
+
+pub const RDB_MAGIC: &[u8] = b"REDIS";
+pub const RDB_VERSION: &[u8] = b"0011";
+
+pub const OPCODE_AUX: u8 = 0xFA;
+pub const OPCODE_RESIZEDB: u8 = 0xFB;
+pub const OPCODE_EXPIRETIME_MS: u8 = 0xFC;
+pub const OPCODE_EXPIRETIME: u8 = 0xFD;
+pub const OPCODE_SELECTDB: u8 = 0xFE;
+pub const OPCODE_EOF: u8 = 0xFF;
+
+pub const TYPE_STRING: u8 = 0;
+pub const TYPE_LIST: u8 = 1;
+pub const TYPE_SET: u8 = 2;
+pub const TYPE_ZSET: u8 = 3;
+pub const TYPE_HASH: u8 = 4;
+pub const TYPE_ZSET_2: u8 = 5;
+pub const TYPE_SET_INTSET: u8 = 11;
+pub const TYPE_HASH_LISTPACK: u8 = 16;
+pub const TYPE_ZSET_LISTPACK: u8 = 17;
+pub const TYPE_LIST_QUICKLIST_2: u8 = 18;
+pub const TYPE_STREAM_LISTPACKS: u8 = 15;
+pub const TYPE_STREAM_LISTPACKS_2: u8 = 19;
+pub const TYPE_SET_LISTPACK: u8 = 20;
+pub const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+
+pub const QUICKLIST_NODE_PLAIN: u64 = 1;
+pub const QUICKLIST_NODE_PACKED: u64 = 2;
+
+/// Entries per listpack when streams are written, like redis' `stream-node-max-entries`.
+pub const STREAM_NODE_MAX_ENTRIES: usize = 100;
+pub const STREAM_ITEM_FLAG_DELETED: i64 = 1;
+/// the entry has the same fields as the first entry of its node, so only its values are stored
+pub const STREAM_ITEM_FLAG_SAMEFIELDS: i64 = 2;
+
+pub const LEN_6BIT: u8 = 0;
+pub const LEN_14BIT: u8 = 1;
+pub const LEN_32BIT: u8 = 0x80;
+pub const LEN_64BIT: u8 = 0x81;
+pub const ENC_SPECIAL: u8 = 3;
+
+pub const ENC_INT8: u8 = 0;
+pub const ENC_INT16: u8 = 1;
+pub const ENC_INT32: u8 = 2;
+pub const ENC_LZF: u8 = 3;
+
+pub const CRC64: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_REDIS);
+
+/// A key as persisted in a snapshot, with its expiry as milliseconds since the unix epoch.
+#[derive(Debug, Clone)]
+pub struct RdbEntry {
+    pub key: String,
+    pub value: Value,
+    pub expires_at: Option<u64>,
+}
+
+/// Copies the keyspace into entries that can be encoded without holding any lock.
+pub async fn snapshot(repo: &CacheRepository) -> Vec<RdbEntry> {
+    repo.entries()
+        .await
+        .into_iter()
+        .map(|(key, (value, expiry))| RdbEntry {
+            key,
+            value,
+            expires_at: expiry.map(instant_to_unix_millis),
+        })
+        .collect()
+}
+
+/// Loads entries into the keyspace, skipping the ones that expired while on disk.
+pub async fn restore(repo: &CacheRepository, entries: Vec<RdbEntry>) -> io::Result<usize> {
+    let now = instant_to_unix_millis(repo.now());
+    let mut loaded = 0;
+    for entry in entries {
+        let expiry = match entry.expires_at {
+            Some(expires_at) if expires_at <= now => continue,
+            Some(expires_at) => Some(unix_millis_to_instant(expires_at)),
+            None => None,
+        };
+        repo.insert(entry.key, entry.value, expiry).await?;
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+/// Writes the snapshot next to `path` first and renames it over, so a crash mid-save never
+/// leaves a truncated file behind.
+pub async fn save_to_file(path: &Path, entries: &[RdbEntry]) -> io::Result<()> {
+    let buf = RdbEncoder::default().encode(entries);
+    let mut tmp_path = PathBuf::from(path);
+    tmp_path.set_extension(format!("tmp-{}", std::process::id()));
+
+    tokio::fs::write(&tmp_path, buf).await?;
+    tokio::fs::rename(&tmp_path, path).await
+}
+
+/// Reads a snapshot from disk. A missing file is not an error, it just means an empty dataset.
+pub async fn load_from_file(path: &Path) -> io::Result<Option<Vec<RdbEntry>>> {
+    match tokio::fs::read(path).await {
+        Ok(buf) => Ok(Some(RdbDecoder::default().decode(&buf)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,849 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    io::{self, Error},
+};
+
+use crate::cache::{
+    core::Value,
+    sorted_set::SortedSet,
+    stream::{Consumer, ConsumerGroup, PendingEntry, Stream, StreamFields, StreamId},
+};
+
+use super::core::{
+    RdbEntry, CRC64, ENC_INT16, ENC_INT32, ENC_INT8, ENC_LZF, ENC_SPECIAL, LEN_14BIT, LEN_32BIT,
+    LEN_64BIT, LEN_6BIT, OPCODE_AUX, OPCODE_EOF, OPCODE_EXPIRETIME, OPCODE_EXPIRETIME_MS,
+    OPCODE_RESIZEDB, OPCODE_SELECTDB, QUICKLIST_NODE_PACKED, QUICKLIST_NODE_PLAIN, RDB_MAGIC,
+    STREAM_ITEM_FLAG_DELETED, STREAM_ITEM_FLAG_SAMEFIELDS, TYPE_HASH, TYPE_HASH_LISTPACK,
+    TYPE_LIST, TYPE_LIST_QUICKLIST_2, TYPE_SET, TYPE_SET_INTSET, TYPE_SET_LISTPACK,
+    TYPE_STREAM_LISTPACKS, TYPE_STREAM_LISTPACKS_2, TYPE_STREAM_LISTPACKS_3, TYPE_STRING,
+    TYPE_ZSET, TYPE_ZSET_2, TYPE_ZSET_LISTPACK,
+};
+
+/// A length prefix is either a plain length or announces a specially encoded string.
+enum Length {
+    Plain(u64),
+    Special(u8),
+}
+
+#[derive(Debug, Default)]
+pub struct RdbDecoder {
+    pos: usize,
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid rdb file: {}", msg),
+    )
+}
+
+impl RdbDecoder {
+    /// Parses an RDB file into its keys. Only database 0 is kept since the server has no
+    /// `SELECT`.
+    pub fn decode(mut self, input: &[u8]) -> io::Result<Vec<RdbEntry>> {
+        if input.len() < 9 || &input[0..5] != RDB_MAGIC {
+            return Err(invalid("bad magic string"));
+        }
+        self.pos = 9;
+
+        let mut entries = Vec::new();
+        let mut db = 0;
+        let mut expires_at = None;
+        loop {
+            let opcode = self.read_u8(input)?;
+            match opcode {
+                OPCODE_AUX => {
+                    self.read_string(input)?;
+                    self.read_string(input)?;
+                }
+                OPCODE_SELECTDB => db = self.read_length(input)?,
+                OPCODE_RESIZEDB => {
+                    self.read_length(input)?;
+                    self.read_length(input)?;
+                }
+                OPCODE_EXPIRETIME_MS => {
+                    let bytes = self.read_bytes(input, 8)?;
+                    expires_at = Some(u64::from_le_bytes(bytes.try_into().unwrap()));
+                }
+                OPCODE_EXPIRETIME => {
+                    let bytes = self.read_bytes(input, 4)?;
+                    expires_at = Some(u32::from_le_bytes(bytes.try_into().unwrap()) as u64 * 1000);
+                }
+                OPCODE_EOF => {
+                    self.verify_checksum(input)?;
+                    break;
+                }
+                value_type => {
+                    let key = self.read_string(input)?;
+                    let value = self.read_value(input, value_type)?;
+                    if db == 0 {
+                        entries.push(RdbEntry {
+                            key: String::from_utf8(key).map_err(|_| invalid("key is not utf-8"))?,
+                            value,
+                            expires_at: expires_at.take(),
+                        });
+                    }
+                    expires_at = None;
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn read_value(&mut self, input: &[u8], value_type: u8) -> io::Result<Value> {
+        let value = match value_type {
+            TYPE_STRING => Value::String(self.read_string(input)?),
+            TYPE_LIST => {
+                let len = self.read_length(input)?;
+                let mut list = VecDeque::new();
+                for _ in 0..len {
+                    list.push_back(self.read_string(input)?);
+                }
+                Value::List(list)
+            }
+            TYPE_LIST_QUICKLIST_2 => {
+                let nodes = self.read_length(input)?;
+                let mut list = VecDeque::new();
+                for _ in 0..nodes {
+                    let container = self.read_length(input)?;
+                    let node = self.read_string(input)?;
+                    match container {
+                        QUICKLIST_NODE_PLAIN => list.push_back(node),
+                        QUICKLIST_NODE_PACKED => list.extend(read_listpack(&node)?),
+                        _ => return Err(invalid("unknown quicklist container")),
+                    }
+                }
+                Value::List(list)
+            }
+            TYPE_SET => {
+                let len = self.read_length(input)?;
+                let mut set = HashSet::new();
+                for _ in 0..len {
+                    set.insert(self.read_string(input)?);
+                }
+                Value::Set(set)
+            }
+            TYPE_SET_INTSET => Value::Set(
+                read_intset(&self.read_string(input)?)?
+                    .into_iter()
+                    .collect(),
+            ),
+            TYPE_SET_LISTPACK => Value::Set(
+                read_listpack(&self.read_string(input)?)?
+                    .into_iter()
+                    .collect(),
+            ),
+            TYPE_ZSET | TYPE_ZSET_2 => {
+                let len = self.read_length(input)?;
+                let mut zset = SortedSet::default();
+                for _ in 0..len {
+                    let member = self.read_string(input)?;
+                    let score = if value_type == TYPE_ZSET_2 {
+                        let bytes = self.read_bytes(input, 8)?;
+                        f64::from_le_bytes(bytes.try_into().unwrap())
+                    } else {
+                        self.read_text_score(input)?
+                    };
+                    zset.insert(member, score);
+                }
+                Value::SortedSet(zset)
+            }
+            TYPE_ZSET_LISTPACK => {
+                let mut zset = SortedSet::default();
+                for pair in read_listpack(&self.read_string(input)?)?.chunks(2) {
+                    let [member, score] = pair else {
+                        return Err(invalid("odd sorted set listpack"));
+                    };
+                    zset.insert(member.to_vec(), parse_score(score)?);
+                }
+                Value::SortedSet(zset)
+            }
+            TYPE_HASH => {
+                let len = self.read_length(input)?;
+                let mut hash = HashMap::new();
+                for _ in 0..len {
+                    let field = self.read_string(input)?;
+                    hash.insert(field, self.read_string(input)?);
+                }
+                Value::Hash(hash)
+            }
+            TYPE_HASH_LISTPACK => {
+                let mut hash = HashMap::new();
+                for pair in read_listpack(&self.read_string(input)?)?.chunks(2) {
+                    let [field, value] = pair else {
+                        return Err(invalid("odd hash listpack"));
+                    };
+                    hash.insert(field.to_vec(), value.to_vec());
+                }
+                Value::Hash(hash)
+            }
+            TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+                Value::Stream(self.read_stream(input, value_type)?)
+            }
+            value_type => {
+                return Err(invalid(&format!("unsupported value type {}", value_type)));
+            }
+        };
+        Ok(value)
+    }
+
+    /// Reads a stream, whose later encodings added counters and consumer activity times.
+    fn read_stream(&mut self, input: &[u8], value_type: u8) -> io::Result<Stream> {
+        let mut stream = Stream::default();
+        let nodes = self.read_length(input)?;
+        for _ in 0..nodes {
+            let master_id = StreamId::from_be_bytes(&self.read_string(input)?)
+                .ok_or_else(|| invalid("bad stream node key"))?;
+            let node = read_listpack(&self.read_string(input)?)?;
+            read_stream_node(master_id, &node, &mut stream.entries)?;
+        }
+
+        let len = self.read_length(input)?;
+        stream.last_id = self.read_id(input)?;
+        if value_type == TYPE_STREAM_LISTPACKS {
+            stream.entries_added = len;
+        } else {
+            // the first id, which the entries tell already
+            self.read_id(input)?;
+            stream.max_deleted_id = self.read_id(input)?;
+            stream.entries_added = self.read_length(input)?;
+        }
+
+        let groups = self.read_length(input)?;
+        for _ in 0..groups {
+            let name = self.read_string(input)?;
+            let last_delivered = self.read_id(input)?;
+            let entries_read = match value_type {
+                TYPE_STREAM_LISTPACKS => None,
+                _ => Some(self.read_length(input)?).filter(|read| *read != u64::MAX),
+            };
+            let mut group = ConsumerGroup::new(last_delivered, entries_read);
+
+            // the consumer of each pending entry is only told by the consumers after them
+            let mut unowned = BTreeMap::new();
+            for _ in 0..self.read_length(input)? {
+                let id = self.read_raw_id(input)?;
+                let delivered_at = self.read_millis(input)?;
+                unowned.insert(id, (delivered_at, self.read_length(input)?));
+            }
+            for _ in 0..self.read_length(input)? {
+                let consumer = self.read_string(input)?;
+                let seen_at = self.read_millis(input)?;
+                let active_at = match value_type {
+                    TYPE_STREAM_LISTPACKS_3 => {
+                        Some(self.read_millis(input)?).filter(|at| *at != u64::MAX)
+                    }
+                    _ => Some(seen_at),
+                };
+                for _ in 0..self.read_length(input)? {
+                    let id = self.read_raw_id(input)?;
+                    let (delivered_at, deliveries) = unowned
+                        .remove(&id)
+                        .ok_or_else(|| invalid("consumer pending entry missing from its group"))?;
+                    group.pending.insert(
+                        id,
+                        PendingEntry {
+                            consumer: consumer.to_vec(),
+                            delivered_at,
+                            deliveries,
+                        },
+                    );
+                }
+                group
+                    .consumers
+                    .insert(consumer, Consumer { seen_at, active_at });
+            }
+            if !unowned.is_empty() {
+                return Err(invalid("group pending entry without a consumer"));
+            }
+            stream.groups.insert(name, group);
+        }
+        Ok(stream)
+    }
+
+    fn read_id(&mut self, input: &[u8]) -> io::Result<StreamId> {
+        Ok(StreamId {
+            ms: self.read_length(input)?,
+            seq: self.read_length(input)?,
+        })
+    }
+
+    fn read_raw_id(&mut self, input: &[u8]) -> io::Result<StreamId> {
+        Ok(StreamId::from_be_bytes(self.read_bytes(input, 16)?).unwrap())
+    }
+
+    fn read_millis(&mut self, input: &[u8]) -> io::Result<u64> {
+        let bytes = self.read_bytes(input, 8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// The score encoding of the first sorted set type, a length prefixed decimal string.
+    fn read_text_score(&mut self, input: &[u8]) -> io::Result<f64> {
+        match self.read_u8(input)? {
+            253 => Ok(f64::NAN),
+            254 => Ok(f64::INFINITY),
+            255 => Ok(f64::NEG_INFINITY),
+            len => parse_score(self.read_bytes(input, len as usize)?),
+        }
+    }
+
+    fn verify_checksum(&mut self, input: &[u8]) -> io::Result<()> {
+        let end = self.pos;
+        // files written with rdbchecksum disabled carry a zeroed checksum
+        let Ok(bytes) = self.read_bytes(input, 8) else {
+            return Ok(());
+        };
+        let expected = u64::from_le_bytes(bytes.try_into().unwrap());
+        if expected != 0 && expected != CRC64.checksum(&input[0..end]) {
+            return Err(invalid("checksum mismatch"));
+        }
+        Ok(())
+    }
+
+    fn read_u8(&mut self, input: &[u8]) -> io::Result<u8> {
+        Ok(self.read_bytes(input, 1)?[0])
+    }
+
+    /// Takes the next `len` bytes. Lengths come from the file, so they are checked against
+    /// what is left of it rather than trusted.
+    fn read_bytes<'a>(&mut self, input: &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= input.len())
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        let bytes = &input[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_length_or_encoding(&mut self, input: &[u8]) -> io::Result<Length> {
+        let first = self.read_u8(input)?;
+        match first >> 6 {
+            LEN_6BIT => Ok(Length::Plain((first & 0x3F) as u64)),
+            LEN_14BIT => {
+                let second = self.read_u8(input)?;
+                Ok(Length::Plain(
+                    (((first & 0x3F) as u64) << 8) | second as u64,
+                ))
+            }
+            ENC_SPECIAL => Ok(Length::Special(first & 0x3F)),
+            _ if first == LEN_32BIT => {
+                let bytes = self.read_bytes(input, 4)?;
+                Ok(Length::Plain(
+                    u32::from_be_bytes(bytes.try_into().unwrap()) as u64
+                ))
+            }
+            _ if first == LEN_64BIT => {
+                let bytes = self.read_bytes(input, 8)?;
+                Ok(Length::Plain(u64::from_be_bytes(bytes.try_into().unwrap())))
+            }
+            _ => Err(invalid("unknown length encoding")),
+        }
+    }
+
+    fn read_length(&mut self, input: &[u8]) -> io::Result<u64> {
+        match self.read_length_or_encoding(input)? {
+            Length::Plain(len) => Ok(len),
+            Length::Special(_) => Err(invalid("expected a length")),
+        }
+    }
+
+    fn read_string(&mut self, input: &[u8]) -> io::Result<Vec<u8>> {
+        match self.read_length_or_encoding(input)? {
+            Length::Plain(len) => Ok(self.read_bytes(input, to_usize(len)?)?.to_vec()),
+            Length::Special(ENC_INT8) => {
+                let val = self.read_u8(input)? as i8;
+                Ok(val.to_string().into_bytes())
+            }
+            Length::Special(ENC_INT16) => {
+                let bytes = self.read_bytes(input, 2)?;
+                Ok(i16::from_le_bytes(bytes.try_into().unwrap())
+                    .to_string()
+                    .into_bytes())
+            }
+            Length::Special(ENC_INT32) => {
+                let bytes = self.read_bytes(input, 4)?;
+                Ok(i32::from_le_bytes(bytes.try_into().unwrap())
+                    .to_string()
+                    .into_bytes())
+            }
+            Length::Special(ENC_LZF) => {
+                let compressed_len = to_usize(self.read_length(input)?)?;
+                let len = to_usize(self.read_length(input)?)?;
+                let compressed = self.read_bytes(input, compressed_len)?;
+                lzf_decompress(compressed, len)
+            }
+            Length::Special(_) => Err(invalid("unknown string encoding")),
+        }
+    }
+}
+
+fn to_usize(len: u64) -> io::Result<usize> {
+    usize::try_from(len).map_err(|_| invalid("length out of range"))
+}
+
+/// The most an LZF back reference expands to, 264 bytes out of 3.
+const LZF_MAX_RATIO: usize = 88;
+
+/// Expands an LZF compressed string, which redis uses for long values when rdbcompression
+/// is enabled. `len` is the declared size of the result, it can't be more than `input`
+/// expands to and the output never grows past it.
+fn lzf_decompress(input: &[u8], len: usize) -> io::Result<Vec<u8>> {
+    if len > input.len().saturating_mul(LZF_MAX_RATIO) {
+        return Err(invalid("lzf length mismatch"));
+    }
+    let mut out: Vec<u8> = Vec::with_capacity(len);
+    let mut pos = 0;
+    while pos < input.len() {
+        let ctrl = input[pos] as usize;
+        pos += 1;
+        if ctrl < 32 {
+            // a run of ctrl + 1 literal bytes
+            let run = ctrl + 1;
+            if pos + run > input.len() || out.len() + run > len {
+                return Err(invalid("corrupt lzf literal"));
+            }
+            out.extend_from_slice(&input[pos..pos + run]);
+            pos += run;
+        } else {
+            // a back reference into the output produced so far
+            let mut run = ctrl >> 5;
+            if run == 7 {
+                run += *input
+                    .get(pos)
+                    .ok_or_else(|| invalid("corrupt lzf reference"))?
+                    as usize;
+                pos += 1;
+            }
+            run += 2;
+            let low = *input
+                .get(pos)
+                .ok_or_else(|| invalid("corrupt lzf reference"))? as usize;
+            pos += 1;
+            let back = ((ctrl & 0x1F) << 8) + low + 1;
+            if back > out.len() || out.len() + run > len {
+                return Err(invalid("corrupt lzf reference"));
+            }
+            let start = out.len() - back;
+            for idx in 0..run {
+                out.push(out[start + idx]);
+            }
+        }
+    }
+    if out.len() != len {
+        return Err(invalid("lzf length mismatch"));
+    }
+    Ok(out)
+}
+
+fn parse_score(score: &[u8]) -> io::Result<f64> {
+    std::str::from_utf8(score)
+        .ok()
+        .and_then(|score| score.parse().ok())
+        .ok_or_else(|| invalid("bad sorted set score"))
+}
+
+/// Reads the elements of a listpack, the compact encoding redis uses for small collections.
+/// Integers are returned in their decimal form, like every other element.
+fn read_listpack(buf: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+    let truncated = || invalid("truncated listpack");
+    // 4 bytes of total length and 2 of element count
+    let mut pos = 6;
+    let mut elems = Vec::new();
+    loop {
+        let encoding = *buf.get(pos).ok_or_else(truncated)?;
+        if encoding == 0xFF {
+            break;
+        }
+        let start = pos;
+        let (elem, len) = match encoding {
+            _ if encoding & 0x80 == 0 => ((encoding & 0x7F).to_string().into_bytes(), 1),
+            _ if encoding & 0xC0 == 0x80 => {
+                let len = (encoding & 0x3F) as usize;
+                let data = buf.get(pos + 1..pos + 1 + len).ok_or_else(truncated)?;
+                (data.to_vec(), 1 + len)
+            }
+            _ if encoding & 0xE0 == 0xC0 => {
+                let low = *buf.get(pos + 1).ok_or_else(truncated)? as i64;
+                let mut val = (((encoding & 0x1F) as i64) << 8) | low;
+                if val >= 1 << 12 {
+                    val -= 1 << 13;
+                }
+                (val.to_string().into_bytes(), 2)
+            }
+            _ if encoding & 0xF0 == 0xE0 => {
+                let low = *buf.get(pos + 1).ok_or_else(truncated)? as usize;
+                let len = (((encoding & 0x0F) as usize) << 8) | low;
+                let data = buf.get(pos + 2..pos + 2 + len).ok_or_else(truncated)?;
+                (data.to_vec(), 2 + len)
+            }
+            0xF0 => {
+                let bytes = buf.get(pos + 1..pos + 5).ok_or_else(truncated)?;
+                let len = u32::from_le_bytes(bytes.try_into().unwrap()) as usize;
+                let data = buf.get(pos + 5..pos + 5 + len).ok_or_else(truncated)?;
+                (data.to_vec(), 5 + len)
+            }
+            0xF1..=0xF4 => {
+                let width = match encoding {
+                    0xF1 => 2,
+                    0xF2 => 3,
+                    0xF3 => 4,
+                    _ => 8,
+                };
+                let bytes = buf.get(pos + 1..pos + 1 + width).ok_or_else(truncated)?;
+                // sign extend the little endian integer from its width
+                let mut raw = [0u8; 8];
+                raw[..width].copy_from_slice(bytes);
+                let shift = 64 - 8 * width as u32;
+                let val = (i64::from_le_bytes(raw) << shift) >> shift;
+                (val.to_string().into_bytes(), 1 + width)
+            }
+            _ => return Err(invalid("unknown listpack encoding")),
+        };
+        elems.push(elem);
+
+        // every element is followed by its own length, so the list can be walked backwards
+        let backlen = match len {
+            0..=127 => 1,
+            128..=16382 => 2,
+            16383..=2097150 => 3,
+            2097151..=268435454 => 4,
+            _ => 5,
+        };
+        pos = start + len + backlen;
+    }
+    Ok(elems)
+}
+
+/// Reads the entries of a stream node, skipping the ones marked deleted. See
+/// `encode::stream_node` for the layout.
+fn read_stream_node(
+    master_id: StreamId,
+    node: &[Vec<u8>],
+    entries: &mut BTreeMap<StreamId, StreamFields>,
+) -> io::Result<()> {
+    let mut elems = node.iter();
+    let mut next = || {
+        elems
+            .next()
+            .map(|elem| elem.as_slice())
+            .ok_or_else(|| invalid("truncated stream node"))
+    };
+    let integer = |elem: &[u8]| {
+        std::str::from_utf8(elem)
+            .ok()
+            .and_then(|elem| elem.parse::<i64>().ok())
+            .ok_or_else(|| invalid("bad stream node integer"))
+    };
+
+    // the count of valid and deleted entries, which the entries tell too
+    next()?;
+    next()?;
+    let master_fields = (0..integer(next()?)?)
+        .map(|_| next())
+        .collect::<io::Result<Vec<&[u8]>>>()?;
+    next()?;
+
+    // the entries run to the end of the node
+    while let Ok(flags) = next() {
+        let flags = integer(flags)?;
+        let id = StreamId {
+            ms: master_id.ms.wrapping_add(integer(next()?)? as u64),
+            seq: master_id.seq.wrapping_add(integer(next()?)? as u64),
+        };
+        let mut fields = Vec::new();
+        if flags & STREAM_ITEM_FLAG_SAMEFIELDS != 0 {
+            for field in master_fields.iter() {
+                fields.push((field.to_vec(), next()?.to_vec()));
+            }
+        } else {
+            for _ in 0..integer(next()?)? {
+                let field = next()?.to_vec();
+                fields.push((field, next()?.to_vec()));
+            }
+        }
+        // how many elements the entry took
+        next()?;
+        if flags & STREAM_ITEM_FLAG_DELETED == 0 {
+            entries.insert(id, fields);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the sorted integers of an intset, the encoding of small sets of integers.
+fn read_intset(buf: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+    let header = buf.get(0..8).ok_or_else(|| invalid("truncated intset"))?;
+    let width = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+    let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+    if ![2, 4, 8].contains(&width) || buf.len() < 8 + width * len {
+        return Err(invalid("corrupt intset"));
+    }
+
+    Ok(buf[8..8 + width * len]
+        .chunks(width)
+        .map(|bytes| {
+            let val = match width {
+                2 => i16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                4 => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                _ => i64::from_le_bytes(bytes.try_into().unwrap()),
+            };
+            val.to_string().into_bytes()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cache::stream::{Trim, TrimStrategy},
+        rdb::encode::{write_listpack, RdbEncoder},
+    };
+
+    /// A file holding the single string key `k`, whose value is encoded as `value`.
+    fn rdb_with_string(value: &[u8]) -> Vec<u8> {
+        let mut rdb = b"REDIS0011".to_vec();
+        rdb.extend([TYPE_STRING, 1, b'k']);
+        rdb.extend(value);
+        rdb.push(OPCODE_EOF);
+        rdb.extend([0; 8]);
+        rdb
+    }
+
+    fn decode_error(rdb: &[u8]) -> Error {
+        match RdbDecoder::default().decode(rdb) {
+            Err(err) => err,
+            Ok(entries) => panic!("expected an error, got {:?}", entries),
+        }
+    }
+
+    #[test]
+    fn decodes_a_plain_string() {
+        let entries = RdbDecoder::default()
+            .decode(&rdb_with_string(&[3, b'a', b'b', b'c']))
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, Value::String(b"abc".to_vec()));
+    }
+
+    fn stream() -> Stream {
+        let mut stream = Stream::default();
+        for seq in 1..=250u64 {
+            let mut fields = vec![(b"field".to_vec(), seq.to_string().into_bytes())];
+            if seq % 7 == 0 {
+                fields.push((b"other".to_vec(), b"value".to_vec()));
+            }
+            stream.add(StreamId { ms: 1000, seq }, fields);
+        }
+        stream.trim(&Trim {
+            strategy: TrimStrategy::MaxLen(240),
+            limit: None,
+        });
+
+        let mut group = ConsumerGroup::new(StreamId { ms: 1000, seq: 20 }, Some(10));
+        group.consumers.insert(
+            b"alice".to_vec(),
+            Consumer {
+                seen_at: 5,
+                active_at: Some(4),
+            },
+        );
+        group.consumers.insert(
+            b"bob".to_vec(),
+            Consumer {
+                seen_at: 6,
+                active_at: None,
+            },
+        );
+        for (seq, consumer) in [(11, "alice"), (12, "bob"), (15, "alice")] {
+            group.pending.insert(
+                StreamId { ms: 1000, seq },
+                PendingEntry {
+                    consumer: consumer.as_bytes().to_vec(),
+                    delivered_at: 100 + seq,
+                    deliveries: seq,
+                },
+            );
+        }
+        stream.groups.insert(b"group".to_vec(), group);
+        stream
+    }
+
+    fn every_type() -> Vec<RdbEntry> {
+        let mut zset = SortedSet::default();
+        zset.insert(b"a".to_vec(), 1.5);
+        zset.insert(b"b".to_vec(), f64::NEG_INFINITY);
+        zset.insert(b"c".to_vec(), -0.25);
+        let values = [
+            Value::String(b"plain".to_vec()),
+            Value::String(b"-12345".to_vec()),
+            Value::String(b"007".to_vec()),
+            Value::String(vec![b'x'; 20000]),
+            Value::List(
+                ["1", "two", "-300000"]
+                    .map(|e| e.as_bytes().to_vec())
+                    .into(),
+            ),
+            Value::Set(["x", "y", "100"].map(|e| e.as_bytes().to_vec()).into()),
+            Value::SortedSet(zset),
+            Value::Hash(HashMap::from([
+                (b"f".to_vec(), b"v".to_vec()),
+                (b"n".to_vec(), b"70000".to_vec()),
+            ])),
+            Value::Stream(stream()),
+        ];
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(idx, value)| RdbEntry {
+                key: format!("key:{}", idx),
+                value,
+                expires_at: (idx % 2 == 0).then_some(1_700_000_000_000 + idx as u64),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_every_type() {
+        let entries = every_type();
+        let decoded = RdbDecoder::default()
+            .decode(&RdbEncoder::default().encode(&entries))
+            .unwrap();
+        assert_eq!(decoded.len(), entries.len());
+        for (decoded, entry) in decoded.iter().zip(entries.iter()) {
+            assert_eq!(decoded.key, entry.key);
+            assert_eq!(decoded.value, entry.value, "{}", entry.key);
+            assert_eq!(decoded.expires_at, entry.expires_at);
+        }
+    }
+
+    #[test]
+    fn detects_corruption_with_the_checksum() {
+        let rdb = RdbEncoder::default().encode(&every_type());
+        // the checksum of the reference check string of CRC-64/REDIS
+        assert_eq!(CRC64.checksum(b"123456789"), 0xe9c6d914c4b8d9ca);
+        let end = rdb.len() - 8;
+        assert_eq!(
+            rdb[end..],
+            CRC64.checksum(&rdb[..end]).to_le_bytes(),
+            "the checksum covers everything before it"
+        );
+
+        let mut rdb = RdbEncoder::default().encode(&[RdbEntry {
+            key: "key".to_string(),
+            value: Value::String(b"hello world".to_vec()),
+            expires_at: None,
+        }]);
+        // still a well formed file, only the checksum gives the change away
+        let idx = rdb.windows(11).position(|w| w == b"hello world").unwrap();
+        rdb[idx] ^= 0x20;
+        let err = decode_error(&rdb);
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn accepts_a_disabled_checksum() {
+        let mut rdb = RdbEncoder::default().encode(&every_type());
+        let end = rdb.len() - 8;
+        rdb[end..].fill(0);
+        assert_eq!(RdbDecoder::default().decode(&rdb).unwrap().len(), 9);
+    }
+
+    #[test]
+    fn round_trips_listpacks() {
+        let mut elems: Vec<Vec<u8>> = [
+            0,
+            127,
+            128,
+            -1,
+            -4096,
+            4095,
+            4096,
+            i16::MIN as i64,
+            i16::MAX as i64 + 1,
+            -8388608,
+            8388608,
+            i32::MIN as i64,
+            i32::MAX as i64 + 1,
+            i64::MIN,
+            i64::MAX,
+        ]
+        .iter()
+        .map(|val| val.to_string().into_bytes())
+        .collect();
+        for len in [0, 1, 63, 64, 127, 128, 4095, 4096, 20000] {
+            elems.push(vec![b'a'; len]);
+        }
+        // not canonical integers, they stay strings
+        elems.push(b"01".to_vec());
+        elems.push(b"+1".to_vec());
+
+        assert_eq!(read_listpack(&write_listpack(&elems)).unwrap(), elems);
+        assert!(read_listpack(&write_listpack(&elems)[..100]).is_err());
+    }
+
+    #[test]
+    fn reads_intsets() {
+        let mut intset = 4u32.to_le_bytes().to_vec();
+        intset.extend(3u32.to_le_bytes());
+        for val in [-70000i32, 1, 70000] {
+            intset.extend(val.to_le_bytes());
+        }
+        let expected: Vec<Vec<u8>> = ["-70000", "1", "70000"]
+            .iter()
+            .map(|val| val.as_bytes().to_vec())
+            .collect();
+        assert_eq!(read_intset(&intset).unwrap(), expected);
+        assert!(read_intset(&intset[..15]).is_err());
+
+        let mut rdb = b"REDIS0011".to_vec();
+        rdb.extend([TYPE_SET_INTSET, 1, b's', intset.len() as u8]);
+        rdb.extend(&intset);
+        rdb.push(OPCODE_EOF);
+        rdb.extend([0; 8]);
+        let entries = RdbDecoder::default().decode(&rdb).unwrap();
+        assert_eq!(entries[0].value, Value::Set(expected.into_iter().collect()));
+    }
+
+    #[test]
+    fn rejects_lengths_past_the_end_of_the_file() {
+        for len in [u64::MAX, u64::MAX - 4, 1 << 40, 4] {
+            let mut value = vec![LEN_64BIT];
+            value.extend(len.to_be_bytes());
+            value.extend(b"abc");
+            let err = decode_error(&rdb_with_string(&value));
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn rejects_lzf_lengths_the_input_cannot_expand_to() {
+        // a compressed string of 2 bytes claiming to expand to 4GB
+        let mut value = vec![0xC0 | ENC_LZF, 2, LEN_32BIT];
+        value.extend(u32::MAX.to_be_bytes());
+        value.extend([0, b'a']);
+        let err = decode_error(&rdb_with_string(&value));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut value = vec![0xC0 | ENC_LZF, LEN_64BIT];
+        value.extend(u64::MAX.to_be_bytes());
+        value.extend([1, 0]);
+        let err = decode_error(&rdb_with_string(&value));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn lzf_output_stays_within_its_declared_length() {
+        // "abc" as literals, then a reference copying 3 bytes from 3 bytes back
+        let compressed = [2, b'a', b'b', b'c', 0x20, 2];
+        assert_eq!(lzf_decompress(&compressed, 6).unwrap(), b"abcabc");
+        assert!(lzf_decompress(&compressed, 5).is_err());
+        assert!(lzf_decompress(&compressed, 2).is_err());
+        assert!(lzf_decompress(&compressed, 7).is_err());
+        // a reference before the start of the output
+        assert!(lzf_decompress(&[0x20, 5], 3).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::cache::{
+    core::{unix_time_secs, Value},
+    stream::{Stream, StreamFields, StreamId},
+};
+
+use super::core::{
+    RdbEntry, CRC64, ENC_INT16, ENC_INT32, ENC_INT8, ENC_SPECIAL, LEN_14BIT, LEN_32BIT, LEN_64BIT,
+    OPCODE_AUX, OPCODE_EOF, OPCODE_EXPIRETIME_MS, OPCODE_RESIZEDB, OPCODE_SELECTDB, RDB_MAGIC,
+    RDB_VERSION, STREAM_ITEM_FLAG_SAMEFIELDS, STREAM_NODE_MAX_ENTRIES, TYPE_HASH, TYPE_LIST,
+    TYPE_SET, TYPE_STREAM_LISTPACKS_3, TYPE_STRING, TYPE_ZSET_2,
+};
+
+#[derive(Debug, Default)]
+pub struct RdbEncoder {
+    buf: Vec<u8>,
+}
+
+impl RdbEncoder {
+    /// Serializes `entries` as database 0 of an RDB file, checksum included.
+    pub fn encode(mut self, entries: &[RdbEntry]) -> Vec<u8> {
+        self.buf.extend_from_slice(RDB_MAGIC);
+        self.buf.extend_from_slice(RDB_VERSION);
+
+        self.write_aux("redis-ver", b"7.2.0");
+        self.write_aux("redis-bits", b"64");
+        self.write_aux("ctime", unix_time_secs().to_string().as_bytes());
+        self.write_aux("used-mem", b"0");
+        self.write_aux("aof-base", b"0");
+
+        self.buf.push(OPCODE_SELECTDB);
+        self.write_length(0);
+        self.buf.push(OPCODE_RESIZEDB);
+        self.write_length(entries.len() as u64);
+        self.write_length(
+            entries
+                .iter()
+                .filter(|entry| entry.expires_at.is_some())
+                .count() as u64,
+        );
+
+        for entry in entries {
+            if let Some(expires_at) = entry.expires_at {
+                self.buf.push(OPCODE_EXPIRETIME_MS);
+                self.buf.extend_from_slice(&expires_at.to_le_bytes());
+            }
+            self.write_entry(&entry.key, &entry.value);
+        }
+
+        self.buf.push(OPCODE_EOF);
+        let checksum = CRC64.checksum(&self.buf);
+        self.buf.extend_from_slice(&checksum.to_le_bytes());
+        self.buf
+    }
+
+    /// Collections use the plain encodings, which every redis version since 4.0 can load.
+    /// Streams have none, they use the encoding of redis 7.2.
+    fn write_entry(&mut self, key: &str, value: &Value) {
+        match value {
+            Value::String(buff) => {
+                self.buf.push(TYPE_STRING);
+                self.write_string(key.as_bytes());
+                self.write_string(buff);
+            }
+            Value::List(list) => {
+                self.buf.push(TYPE_LIST);
+                self.write_string(key.as_bytes());
+                self.write_length(list.len() as u64);
+                for elem in list {
+                    self.write_string(elem);
+                }
+            }
+            Value::Set(set) => {
+                self.buf.push(TYPE_SET);
+                self.write_string(key.as_bytes());
+                self.write_length(set.len() as u64);
+                for member in set {
+                    self.write_string(member);
+                }
+            }
+            Value::SortedSet(zset) => {
+                self.buf.push(TYPE_ZSET_2);
+                self.write_string(key.as_bytes());
+                self.write_length(zset.len() as u64);
+                for (member, score) in zset.iter() {
+                    self.write_string(member);
+                    self.buf.extend_from_slice(&score.to_le_bytes());
+                }
+            }
+            Value::Hash(hash) => {
+                self.buf.push(TYPE_HASH);
+                self.write_string(key.as_bytes());
+                self.write_length(hash.len() as u64);
+                for (field, value) in hash {
+                    self.write_string(field);
+                    self.write_string(value);
+                }
+            }
+            Value::Stream(stream) => {
+                self.buf.push(TYPE_STREAM_LISTPACKS_3);
+                self.write_string(key.as_bytes());
+                self.write_stream(stream);
+            }
+        }
+    }
+
+    /// The entries in listpacks keyed by the id the ids inside are relative to, then the
+    /// stream's own ids and counters, then its consumer groups.
+    fn write_stream(&mut self, stream: &Stream) {
+        let entries: Vec<(&StreamId, &StreamFields)> = stream.entries.iter().collect();
+        let nodes = entries.chunks(STREAM_NODE_MAX_ENTRIES);
+        self.write_length(nodes.len() as u64);
+        for node in nodes {
+            let master_id = *node[0].0;
+            self.write_string(&master_id.to_be_bytes());
+            self.write_string(&stream_node(master_id, node));
+        }
+
+        self.write_length(stream.len() as u64);
+        self.write_id(stream.last_id);
+        self.write_id(stream.first_id());
+        self.write_id(stream.max_deleted_id);
+        self.write_length(stream.entries_added);
+
+        self.write_length(stream.groups.len() as u64);
+        for (name, group) in stream.groups.iter() {
+            self.write_string(name);
+            self.write_id(group.last_delivered);
+            // an unknown count is stored as -1
+            self.write_length(group.entries_read.unwrap_or(u64::MAX));
+            self.write_length(group.pending.len() as u64);
+            for (id, pending) in group.pending.iter() {
+                self.buf.extend_from_slice(&id.to_be_bytes());
+                self.buf
+                    .extend_from_slice(&pending.delivered_at.to_le_bytes());
+                self.write_length(pending.deliveries);
+            }
+
+            self.write_length(group.consumers.len() as u64);
+            for (name, consumer) in group.consumers.iter() {
+                self.write_string(name);
+                self.buf.extend_from_slice(&consumer.seen_at.to_le_bytes());
+                let active_at = consumer.active_at.unwrap_or(u64::MAX);
+                self.buf.extend_from_slice(&active_at.to_le_bytes());
+                let pending: Vec<&StreamId> = group.pending_of(name).map(|(id, _)| id).collect();
+                self.write_length(pending.len() as u64);
+                for id in pending {
+                    self.buf.extend_from_slice(&id.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    fn write_id(&mut self, id: StreamId) {
+        self.write_length(id.ms);
+        self.write_length(id.seq);
+    }
+
+    fn write_aux(&mut self, key: &str, value: &[u8]) {
+        self.buf.push(OPCODE_AUX);
+        self.write_string(key.as_bytes());
+        self.write_string(value);
+    }
+
+    fn write_length(&mut self, len: u64) {
+        if len < 1 << 6 {
+            self.buf.push(len as u8);
+        } else if len < 1 << 14 {
+            self.buf.push((LEN_14BIT << 6) | (len >> 8) as u8);
+            self.buf.push(len as u8);
+        } else if len <= u32::MAX as u64 {
+            self.buf.push(LEN_32BIT);
+            self.buf.extend_from_slice(&(len as u32).to_be_bytes());
+        } else {
+            self.buf.push(LEN_64BIT);
+            self.buf.extend_from_slice(&len.to_be_bytes());
+        }
+    }
+
+    /// Strings holding a canonical integer are stored in the compact integer encoding.
+    fn write_string(&mut self, data: &[u8]) {
+        if let Some(val) = as_canonical_integer(data) {
+            if let Ok(val) = i8::try_from(val) {
+                self.buf.push((ENC_SPECIAL << 6) | ENC_INT8);
+                self.buf.extend_from_slice(&val.to_le_bytes());
+                return;
+            }
+            if let Ok(val) = i16::try_from(val) {
+                self.buf.push((ENC_SPECIAL << 6) | ENC_INT16);
+                self.buf.extend_from_slice(&val.to_le_bytes());
+                return;
+            }
+            if let Ok(val) = i32::try_from(val) {
+                self.buf.push((ENC_SPECIAL << 6) | ENC_INT32);
+                self.buf.extend_from_slice(&val.to_le_bytes());
+                return;
+            }
+        }
+        self.write_length(data.len() as u64);
+        self.buf.extend_from_slice(data);
+    }
+}
+
+/// Only strings that print back to exactly the same bytes can be stored as integers.
+fn as_canonical_integer(data: &[u8]) -> Option<i64> {
+    if data.is_empty() || data.len() > 20 {
+        return None;
+    }
+    let val: i64 = std::str::from_utf8(data).ok()?.parse().ok()?;
+    if val.to_string().as_bytes() != data {
+        return None;
+    }
+    Some(val)
+}
+
+fn integer_elem(val: i64) -> Vec<u8> {
+    val.to_string().into_bytes()
+}
+
+/// The listpack of a stream node. A master entry holding the fields of the first entry comes
+/// first, then every entry with its id relative to `master_id`, skipping the fields when they
+/// are the master's.
+fn stream_node(master_id: StreamId, entries: &[(&StreamId, &StreamFields)]) -> Vec<u8> {
+    let master_fields: Vec<&Vec<u8>> = entries[0].1.iter().map(|(field, _)| field).collect();
+    let mut elems = vec![
+        integer_elem(entries.len() as i64),
+        // deleted entries
+        integer_elem(0),
+        integer_elem(master_fields.len() as i64),
+    ];
+    elems.extend(master_fields.iter().map(|field| field.to_vec()));
+    elems.push(integer_elem(0));
+
+    for (id, fields) in entries {
+        let same_fields = fields.len() == master_fields.len()
+            && fields
+                .iter()
+                .zip(master_fields.iter())
+                .all(|((field, _), master_field)| field == *master_field);
+        let flags = if same_fields {
+            STREAM_ITEM_FLAG_SAMEFIELDS
+        } else {
+            0
+        };
+        elems.push(integer_elem(flags));
+        elems.push(integer_elem(id.ms.wrapping_sub(master_id.ms) as i64));
+        elems.push(integer_elem(id.seq.wrapping_sub(master_id.seq) as i64));
+        if same_fields {
+            elems.extend(fields.iter().map(|(_, value)| value.to_vec()));
+        } else {
+            elems.push(integer_elem(fields.len() as i64));
+            for (field, value) in fields.iter() {
+                elems.push(field.to_vec());
+                elems.push(value.to_vec());
+            }
+        }
+        // how many elements the entry took, so the node can be walked backwards
+        let lp_count = match same_fields {
+            true => fields.len() + 3,
+            false => fields.len() * 2 + 4,
+        };
+        elems.push(integer_elem(lp_count as i64));
+    }
+    write_listpack(&elems)
+}
+
+/// Packs `elems` into a listpack, the ones holding an integer in an integer encoding.
+pub(super) fn write_listpack(elems: &[Vec<u8>]) -> Vec<u8> {
+    // 4 bytes of total length and 2 of element count, filled in at the end
+    let mut buf = vec![0; 6];
+    for elem in elems {
+        let start = buf.len();
+        match as_canonical_integer(elem) {
+            Some(val @ 0..=127) => buf.push(val as u8),
+            Some(val @ -4096..=4095) => {
+                buf.push(0xC0 | ((val >> 8) as u8 & 0x1F));
+                buf.push(val as u8);
+            }
+            Some(val) if i16::try_from(val).is_ok() => {
+                buf.push(0xF1);
+                buf.extend_from_slice(&(val as i16).to_le_bytes());
+            }
+            Some(val @ -8388608..=8388607) => {
+                buf.push(0xF2);
+                buf.extend_from_slice(&(val as i32).to_le_bytes()[..3]);
+            }
+            Some(val) if i32::try_from(val).is_ok() => {
+                buf.push(0xF3);
+                buf.extend_from_slice(&(val as i32).to_le_bytes());
+            }
+            Some(val) => {
+                buf.push(0xF4);
+                buf.extend_from_slice(&val.to_le_bytes());
+            }
+            None if elem.len() < 64 => {
+                buf.push(0x80 | elem.len() as u8);
+                buf.extend_from_slice(elem);
+            }
+            None if elem.len() < 4096 => {
+                buf.push(0xE0 | (elem.len() >> 8) as u8);
+                buf.push(elem.len() as u8);
+                buf.extend_from_slice(elem);
+            }
+            None => {
+                buf.push(0xF0);
+                buf.extend_from_slice(&(elem.len() as u32).to_le_bytes());
+                buf.extend_from_slice(elem);
+            }
+        }
+
+        // every element is followed by its own length, 7 bits per byte, the first byte holding
+        // the most significant ones
+        let len = buf.len() - start;
+        let width = match len {
+            0..=127 => 1,
+            128..=16382 => 2,
+            16383..=2097150 => 3,
+            2097151..=268435454 => 4,
+            _ => 5,
+        };
+        for idx in (0..width).rev() {
+            let bits = (len >> (7 * idx)) as u8 & 0x7F;
+            buf.push(if idx == width - 1 { bits } else { bits | 0x80 });
+        }
+    }
+    buf.push(0xFF);
+
+    let total = buf.len() as u32;
+    buf[0..4].copy_from_slice(&total.to_le_bytes());
+    let count = elems.len().min(u16::MAX as usize) as u16;
+    buf[4..6].copy_from_slice(&count.to_le_bytes());
+    buf
+}
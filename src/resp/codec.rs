@@ -0,0 +1,117 @@
+use std::io;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{
+    core::{Protocol, RESPDatatypes},
+    deserialize::{Deseralize, Frame, Progress},
+};
+
+/// Frames RESP over any byte stream.
+///
+/// The server reads client commands through it, and clients can wrap a socket with
+/// `Framed::new(stream, RespCodec::default())` to exchange `RESPDatatypes` with the server.
+/// Outgoing frames are encoded for the negotiated `protocol`.
+#[derive(Debug, Default)]
+pub struct RespCodec {
+    protocol: Protocol,
+    // only accepts commands, see `RespCodec::requests`
+    requests: bool,
+    // the array frame being read, when it didn't fully arrive yet
+    progress: Option<Progress>,
+}
+
+/// A clone starts decoding at the next frame, it doesn't share a partly read one.
+impl Clone for RespCodec {
+    fn clone(&self) -> Self {
+        RespCodec {
+            requests: self.requests,
+            ..RespCodec::new(self.protocol)
+        }
+    }
+}
+
+impl RespCodec {
+    pub fn new(protocol: Protocol) -> Self {
+        RespCodec {
+            protocol,
+            requests: false,
+            progress: None,
+        }
+    }
+
+    /// A codec for the side reading commands, which rejects frames that aren't an array of
+    /// plain values or an inline command rather than decoding arbitrarily nested replies.
+    pub fn requests() -> Self {
+        RespCodec {
+            requests: true,
+            ..RespCodec::default()
+        }
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    /// Decodes the RDB payload a master sends after `FULLRESYNC`, which unlike a bulk string
+    /// has no trailing CLRF.
+    pub fn decode_rdb(&mut self, src: &mut BytesMut) -> io::Result<Option<Vec<u8>>> {
+        match Deseralize.parse_rdb(src)? {
+            Some((rdb, consumed)) => {
+                src.advance(consumed);
+                Ok(Some(rdb))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the frame at the front of `src` without consuming it, for callers that need
+    /// its raw bytes. Until it returns a frame, the next call must get the same bytes, only
+    /// extended with what was read since.
+    pub fn decode_frame(&mut self, src: &[u8]) -> io::Result<Option<Frame>> {
+        match self.requests {
+            true => Deseralize.parse_request(src, &mut self.progress),
+            false => Deseralize.parse_resumable(src, &mut self.progress),
+        }
+    }
+}
+
+impl Decoder for RespCodec {
+    type Item = RESPDatatypes;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        match self.decode_frame(src)? {
+            Some((frame, consumed)) => {
+                src.advance(consumed);
+                Ok(Some(frame))
+            }
+            None => {
+                src.reserve(1024);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Encoder<RESPDatatypes> for RespCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: RESPDatatypes, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode(&item, dst)
+    }
+}
+
+impl Encoder<&RESPDatatypes> for RespCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &RESPDatatypes, dst: &mut BytesMut) -> io::Result<()> {
+        dst.extend_from_slice(&item.encode_for(self.protocol));
+        Ok(())
+    }
+}
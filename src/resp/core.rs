@@ -14,6 +14,30 @@ pub const BOOLEAN_PREFIX: &[u8] = b"#";
 pub const BULK_STRING_PREFIX: &[u8] = b"$";
 pub const BULK_BUF_STRING_PREFIX: &[u8] = b"$";
 pub const ARRAY_PREFIX: &[u8] = b"*";
+pub const BULK_ERROR_PREFIX: &[u8] = b"!";
+pub const VERBATIM_STRING_PREFIX: &[u8] = b"=";
+pub const BIG_NUMBER_PREFIX: &[u8] = b"(";
+pub const MAP_PREFIX: &[u8] = b"%";
+pub const ATTRIBUTE_PREFIX: &[u8] = b"|";
+pub const SET_PREFIX: &[u8] = b"~";
+pub const PUSH_PREFIX: &[u8] = b">";
+
+/// Wire protocol negotiated by a client through `HELLO`. Connections start out on RESP2.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+impl Protocol {
+    pub fn version(&self) -> i32 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum RESPDatatypes {
@@ -21,7 +45,7 @@ pub enum RESPDatatypes {
     NullString,
     NullArray,
 
-    Integer(i32),
+    Integer(i64),
     Double(f64),
 
     SimpleString(String),
@@ -31,14 +55,29 @@ pub enum RESPDatatypes {
     BufBulk(Vec<u8>),
 
     Boolean(bool),
+    BigNumber(String),
+    BulkError(String),
+    // the three character format (`txt`, `mkd`) followed by the payload
+    VerbatimString(String, Vec<u8>),
 
     Array(Vec<RESPDatatypes>),
+    Map(Vec<(RESPDatatypes, RESPDatatypes)>),
+    Attribute(Vec<(RESPDatatypes, RESPDatatypes)>),
+    Set(Vec<RESPDatatypes>),
+    Push(Vec<RESPDatatypes>),
 }
 
 impl RESPDatatypes {
+    /// Encodes for a RESP2 peer, which is what replicas and freshly connected clients speak.
     pub fn encode(&self) -> Vec<u8> {
+        self.encode_for(Protocol::Resp2)
+    }
+
+    /// Encodes for a peer speaking `protocol`. RESP3 only types are downgraded to their
+    /// RESP2 equivalents when needed.
+    pub fn encode_for(&self, protocol: Protocol) -> Vec<u8> {
         let mut buf = Vec::new();
-        SerializeRESP.encode(self, &mut buf);
+        SerializeRESP { protocol }.encode(self, &mut buf);
         buf
     }
 }
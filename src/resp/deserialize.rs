@@ -1,11 +1,40 @@
 use std::io::{self, Error, Result};
 
-use crate::errors::{command_not_found::CommandNotFoundError, eof::Eof};
+use crate::errors::eof::Eof;
 
 use super::core::{RESPDatatypes, CLRF};
 
 pub struct Deseralize;
 
+/// Upper bound for a single bulk string, matching the default `proto-max-bulk-len` of redis.
+pub const MAX_BULK_LENGTH: i64 = 512 * 1024 * 1024;
+
+/// Upper bound for the number of elements of an aggregate, like the one redis puts on the
+/// multibulk length of commands.
+pub const MAX_MULTIBULK_LENGTH: i64 = MAX_BULK_LENGTH;
+
+const INVALID_BULK_LENGTH: &str = "Protocol error: invalid bulk length";
+
+const INVALID_MULTIBULK_LENGTH: &str = "Protocol error: invalid multibulk length";
+
+/// How deeply aggregates can nest in a reply. Parsing recurses once per level, so without a
+/// bound a long run of `*1\r\n` would overflow the stack.
+pub const MAX_NESTING_DEPTH: usize = 32;
+
+const TOO_DEEPLY_NESTED: &str = "Protocol error: aggregates nested too deeply";
+
+/// A decoded frame together with the number of bytes it occupied in the input.
+pub type Frame = (RESPDatatypes, usize);
+
+/// How much of an array frame arriving over several reads was decoded already, so the
+/// elements that are complete aren't decoded again every time more of it is read.
+#[derive(Debug)]
+pub struct Progress {
+    elements: Vec<RESPDatatypes>,
+    remaining: usize,
+    consumed: usize,
+}
+
 pub fn bytes_to_string(vec: &[u8]) -> Result<String> {
     match String::from_utf8(vec.to_vec()) {
         Ok(str) => Ok(str),
@@ -16,7 +45,7 @@ pub fn bytes_to_string(vec: &[u8]) -> Result<String> {
 pub fn bytes_to_type<T: std::str::FromStr>(vec: &[u8]) -> Result<T>
 where
     <T as std::str::FromStr>::Err: std::convert::Into<
-        std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
+        std::boxed::Box<dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static>,
     >,
 {
     let str_val = bytes_to_string(vec)?;
@@ -26,158 +55,540 @@ where
     }
 }
 
-pub fn index_till_first_clrf(vec: &mut Vec<u8>) -> usize {
-    let mut index = 0;
-    for buff in vec.windows(2) {
-        if buff == CLRF {
-            break;
-        }
-        index += 1;
+/// Parses the element count of an aggregate header, within [`MAX_MULTIBULK_LENGTH`].
+fn parse_multibulk_length(line: &[u8]) -> Result<i64> {
+    match bytes_to_type::<i64>(line) {
+        Ok(len) if len <= MAX_MULTIBULK_LENGTH => Ok(len),
+        _ => Err(Error::new(
+            io::ErrorKind::InvalidInput,
+            INVALID_MULTIBULK_LENGTH,
+        )),
     }
-    index
 }
 
-pub fn drain_till_index_till_first_clrf(vec: &mut Vec<u8>) {
-    let mut index = 0;
-    for buff in vec.windows(2) {
-        if buff == CLRF {
-            break;
-        }
-        index += 1;
+/// Whether a frame starting with `byte` holds other frames.
+fn is_aggregate(byte: u8) -> bool {
+    matches!(byte, b'*' | b'~' | b'>' | b'%' | b'|')
+}
+
+/// The error for a request that isn't an array of plain values, like the one redis gives.
+fn unexpected_aggregate(expected: char, got: u8) -> Error {
+    Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Protocol error: expected '{}', got '{}'",
+            expected, got as char
+        ),
+    )
+}
+
+/// Returns the position of the first CLRF at or after `from`, if the input contains one.
+fn find_clrf(input: &[u8], from: usize) -> Option<usize> {
+    if from >= input.len() {
+        return None;
     }
-    vec.drain(0..index + 2);
+    input[from..]
+        .windows(2)
+        .position(|window| window == CLRF)
+        .map(|idx| idx + from)
 }
 
 impl Deseralize {
+    /// Decodes one frame from the front of `input` and drains the bytes it occupied.
+    /// An incomplete frame is reported as `UnexpectedEof` and leaves `input` untouched.
     pub fn deseralize(&self, input: &mut Vec<u8>) -> Result<RESPDatatypes> {
-        let len = input.len();
-        if len == 0 {
-            return Err(Error::new(io::ErrorKind::UnexpectedEof, Eof {}));
+        match self.parse(input)? {
+            Some((data, consumed)) => {
+                input.drain(0..consumed);
+                Ok(data)
+            }
+            None => Err(Error::new(io::ErrorKind::UnexpectedEof, Eof {})),
         }
-        if len < 3 {
-            return Err(Error::new(
-                io::ErrorKind::InvalidInput,
-                CommandNotFoundError {
-                    cmd: String::from_utf8_lossy(input).to_string(),
-                },
-            ));
+    }
+
+    /// Resumable decoding of a single frame.
+    ///
+    /// Returns `Ok(None)` when `input` holds only part of a frame, so the caller can keep the
+    /// bytes around and retry once more data has been read. Otherwise returns the frame and
+    /// the number of bytes consumed; anything after that belongs to the next frame.
+    pub fn parse(&self, input: &[u8]) -> Result<Option<Frame>> {
+        self.parse_at(input, 0, 0)
+    }
+
+    /// Like [`Deseralize::parse`], but an array frame that is only partly in `input` keeps
+    /// the elements decoded so far in `progress`. The next call must be made with the same
+    /// input, only extended with what was read since. `progress` is reset on errors.
+    pub fn parse_resumable(
+        &self,
+        input: &[u8],
+        progress: &mut Option<Progress>,
+    ) -> Result<Option<Frame>> {
+        let res = self.resume(input, progress, false);
+        if res.is_err() {
+            progress.take();
         }
-        let last_two_bytes = &input[(input.len() - 2)..];
-        if last_two_bytes != CLRF {
-            return Err(Error::new(
-                io::ErrorKind::InvalidInput,
-                CommandNotFoundError {
-                    cmd: String::from_utf8_lossy(input).to_string(),
-                },
-            ));
+        res
+    }
+
+    /// Like [`Deseralize::parse_resumable`], for the commands clients send. Those are an
+    /// array of plain values, or an inline command, so any other aggregate is an error.
+    pub fn parse_request(
+        &self,
+        input: &[u8],
+        progress: &mut Option<Progress>,
+    ) -> Result<Option<Frame>> {
+        let res = self.resume(input, progress, true);
+        if res.is_err() {
+            progress.take();
+        }
+        res
+    }
+
+    fn resume(
+        &self,
+        input: &[u8],
+        progress: &mut Option<Progress>,
+        request: bool,
+    ) -> Result<Option<Frame>> {
+        if progress.is_none() {
+            match input.first() {
+                Some(b'*') => {}
+                Some(byte) if request && is_aggregate(*byte) => {
+                    return Err(unexpected_aggregate('*', *byte));
+                }
+                _ => return self.parse(input),
+            }
+            let Some(line_end) = find_clrf(input, 0) else {
+                return Ok(None);
+            };
+            let len = parse_multibulk_length(&input[1..line_end])?;
+            if len == -1 {
+                return Ok(Some((RESPDatatypes::NullArray, line_end + 2)));
+            }
+            if len < 0 {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    INVALID_MULTIBULK_LENGTH,
+                ));
+            }
+            progress.replace(Progress {
+                elements: Vec::with_capacity(len.min(1024) as usize),
+                remaining: len as usize,
+                consumed: line_end + 2,
+            });
         }
 
-        let base_type = &[input[0]; 1];
-
-        match base_type {
-            b"+" => {
-                let idx = index_till_first_clrf(input);
-                let data = bytes_to_string(&input[1..idx])?;
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::SimpleString(data))
-            }
-            b"-" => {
-                let idx = index_till_first_clrf(input);
-                let data = bytes_to_string(&input[1..idx])?;
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::SimpleError(Box::new(Error::new(
-                    io::ErrorKind::Other,
-                    data,
-                ))))
-            }
-            b"_" => {
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::Null)
-            }
-            b":" => {
-                let idx = index_till_first_clrf(input);
-                let data = bytes_to_type(&input[1..idx])?;
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::Integer(data))
-            }
-            b"," => {
-                let idx = index_till_first_clrf(input);
-                let data = bytes_to_type(&input[1..idx])?;
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::Double(data))
-            }
-            b"#" => {
-                let idx = index_till_first_clrf(input);
-                let data = bytes_to_type(&input[1..idx])?;
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::Boolean(data))
-            }
-
-            b"$" => {
-                if input.len() < 6 {
-                    return Err(Error::new(
-                        io::ErrorKind::InvalidInput,
-                        "invalid buf string",
-                    ));
+        let Some(state) = progress.as_mut() else {
+            return Ok(None);
+        };
+        while state.remaining > 0 {
+            match input.get(state.consumed) {
+                Some(byte) if request && is_aggregate(*byte) => {
+                    return Err(unexpected_aggregate('$', *byte));
                 }
-                let mut index = index_till_first_clrf(input);
-                if index > input.len() {
+                _ => {}
+            }
+            let Some((data, end)) = self.parse_at(input, state.consumed, 1)? else {
+                return Ok(None);
+            };
+            state.elements.push(data);
+            state.consumed = end;
+            state.remaining -= 1;
+        }
+        Ok(progress
+            .take()
+            .map(|state| (RESPDatatypes::Array(state.elements), state.consumed)))
+    }
+
+    /// Decodes the RDB payload a master sends after `FULLRESYNC`. It is framed like a bulk
+    /// string but has no trailing CLRF.
+    pub fn parse_rdb(&self, input: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+        let Some(line_end) = find_clrf(input, 0) else {
+            return Ok(None);
+        };
+        if input[0] != b'$' {
+            return Err(Error::new(io::ErrorKind::InvalidInput, "invalid rdb file"));
+        }
+        let rdb_len: usize = bytes_to_type(&input[1..line_end])
+            .map_err(|_| Error::new(io::ErrorKind::InvalidInput, INVALID_BULK_LENGTH))?;
+        let start = line_end + 2;
+        let end = start
+            .checked_add(rdb_len)
+            .ok_or_else(|| Error::new(io::ErrorKind::InvalidInput, INVALID_BULK_LENGTH))?;
+        if input.len() < end {
+            return Ok(None);
+        }
+        Ok(Some((input[start..end].to_vec(), end)))
+    }
+
+    /// Decodes the frame starting at `pos`, within `depth` aggregates, and returns it with
+    /// the position right after it.
+    fn parse_at(&self, input: &[u8], pos: usize, depth: usize) -> Result<Option<Frame>> {
+        if pos >= input.len() {
+            return Ok(None);
+        }
+        let Some(line_end) = find_clrf(input, pos) else {
+            return Ok(None);
+        };
+        // an empty line has no type byte
+        let line = &input[(pos + 1).min(line_end)..line_end];
+        let next = line_end + 2;
+
+        match input[pos] {
+            b'+' => Ok(Some((
+                RESPDatatypes::SimpleString(bytes_to_string(line)?),
+                next,
+            ))),
+            b'-' => Ok(Some((
+                RESPDatatypes::SimpleError(Box::new(Error::other(bytes_to_string(line)?))),
+                next,
+            ))),
+            b'_' => Ok(Some((RESPDatatypes::Null, next))),
+            b':' => Ok(Some((RESPDatatypes::Integer(bytes_to_type(line)?), next))),
+            b',' => Ok(Some((RESPDatatypes::Double(bytes_to_type(line)?), next))),
+            b'#' => match line {
+                b"t" => Ok(Some((RESPDatatypes::Boolean(true), next))),
+                b"f" => Ok(Some((RESPDatatypes::Boolean(false), next))),
+                _ => Err(Error::new(io::ErrorKind::InvalidInput, "invalid boolean")),
+            },
+            b'(' => {
+                let data = bytes_to_string(line)?;
+                let digits = data.strip_prefix(['-', '+']).unwrap_or(&data);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
                     return Err(Error::new(
                         io::ErrorKind::InvalidInput,
-                        "invalid buf string",
+                        "invalid big number",
                     ));
                 }
-
-                let input_buf_len: i32 = bytes_to_type(&input[1..index])?;
+                Ok(Some((RESPDatatypes::BigNumber(data), next)))
+            }
+            b'$' => {
+                let input_buf_len: i64 = bytes_to_type(line)
+                    .map_err(|_| Error::new(io::ErrorKind::InvalidInput, INVALID_BULK_LENGTH))?;
                 if input_buf_len == -1 {
-                    return Ok(RESPDatatypes::NullString);
+                    return Ok(Some((RESPDatatypes::NullString, next)));
                 }
-
-                if input_buf_len < -1 {
+                Ok(self
+                    .parse_blob(input, next, input_buf_len)?
+                    .map(|(data, end)| (RESPDatatypes::BufBulk(data), end)))
+            }
+            b'!' => {
+                let input_buf_len: i64 = bytes_to_type(line)
+                    .map_err(|_| Error::new(io::ErrorKind::InvalidInput, INVALID_BULK_LENGTH))?;
+                let Some((data, end)) = self.parse_blob(input, next, input_buf_len)? else {
+                    return Ok(None);
+                };
+                Ok(Some((
+                    RESPDatatypes::BulkError(bytes_to_string(&data)?),
+                    end,
+                )))
+            }
+            b'=' => {
+                let input_buf_len: i64 = bytes_to_type(line)
+                    .map_err(|_| Error::new(io::ErrorKind::InvalidInput, INVALID_BULK_LENGTH))?;
+                let Some((data, end)) = self.parse_blob(input, next, input_buf_len)? else {
+                    return Ok(None);
+                };
+                if data.len() < 4 || data[3] != b':' {
                     return Err(Error::new(
                         io::ErrorKind::InvalidInput,
-                        "invalid buf string",
+                        "invalid verbatim string",
                     ));
                 }
-                drain_till_index_till_first_clrf(input);
-                index = index_till_first_clrf(input);
-                let data = input[0..index].to_vec();
-                drain_till_index_till_first_clrf(input);
-                Ok(RESPDatatypes::BufBulk(data))
-            }
-            b"*" => {
-                if input.len() < 6 {
-                    return Err(Error::new(io::ErrorKind::InvalidInput, "invalid array"));
+                let format = bytes_to_string(&data[0..3])?;
+                Ok(Some((
+                    RESPDatatypes::VerbatimString(format, data[4..].to_vec()),
+                    end,
+                )))
+            }
+            b'*' => {
+                let input_buf_len = parse_multibulk_length(line)?;
+                if input_buf_len == -1 {
+                    return Ok(Some((RESPDatatypes::NullArray, next)));
                 }
-                let index = index_till_first_clrf(input);
-                if index > input.len() {
-                    return Err(Error::new(io::ErrorKind::InvalidInput, "invalid array"));
+                Ok(self
+                    .parse_elements(input, next, input_buf_len, depth)?
+                    .map(|(res, end)| (RESPDatatypes::Array(res), end)))
+            }
+            b'~' => {
+                let input_buf_len = parse_multibulk_length(line)?;
+                Ok(self
+                    .parse_elements(input, next, input_buf_len, depth)?
+                    .map(|(res, end)| (RESPDatatypes::Set(res), end)))
+            }
+            b'>' => {
+                let input_buf_len = parse_multibulk_length(line)?;
+                Ok(self
+                    .parse_elements(input, next, input_buf_len, depth)?
+                    .map(|(res, end)| (RESPDatatypes::Push(res), end)))
+            }
+            b'%' | b'|' => {
+                // every pair is two elements, which must be within the limit as well
+                let elements = parse_multibulk_length(line)?
+                    .checked_mul(2)
+                    .filter(|elements| (0..=MAX_MULTIBULK_LENGTH).contains(elements))
+                    .ok_or_else(|| {
+                        Error::new(io::ErrorKind::InvalidInput, INVALID_MULTIBULK_LENGTH)
+                    })?;
+                let Some((res, end)) = self.parse_elements(input, next, elements, depth)? else {
+                    return Ok(None);
+                };
+                let mut pairs = Vec::with_capacity(res.len() / 2);
+                let mut elements = res.into_iter();
+                while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+                    pairs.push((key, value));
                 }
-                let input_buf_len: i32 = bytes_to_type(&input[1..index])?;
-                if input_buf_len == -1 {
-                    return Ok(RESPDatatypes::NullArray);
+                if input[pos] == b'%' {
+                    Ok(Some((RESPDatatypes::Map(pairs), end)))
+                } else {
+                    Ok(Some((RESPDatatypes::Attribute(pairs), end)))
                 }
-                drain_till_index_till_first_clrf(input);
-
-                let mut res: Vec<RESPDatatypes> = Vec::with_capacity(input_buf_len as usize);
-                let mut i = 0;
-                while i < input_buf_len {
-                    match self.deseralize(input) {
-                        Ok(data) => {
-                            res.push(data);
-                        }
-                        Err(err) => {
-                            println!("error while parsing array input {}", err);
-                            return Err(Error::new(io::ErrorKind::InvalidInput, "invalid input"));
-                        }
-                    }
-
-                    i += 1;
+            }
+            // inline commands, as typed into a plain telnet session
+            _ => Ok(Some((
+                RESPDatatypes::SimpleString(bytes_to_string(&input[pos..line_end])?),
+                next,
+            ))),
+        }
+    }
+
+    /// Reads a length prefixed payload followed by CLRF, as used by bulk strings, bulk errors
+    /// and verbatim strings.
+    fn parse_blob(&self, input: &[u8], start: usize, len: i64) -> Result<Option<(Vec<u8>, usize)>> {
+        if !(0..=MAX_BULK_LENGTH).contains(&len) {
+            return Err(Error::new(io::ErrorKind::InvalidInput, INVALID_BULK_LENGTH));
+        }
+
+        // the payload is read by its declared length, so it may itself contain CLRF
+        let data_end = start + len as usize;
+        if input.len() < data_end + CLRF.len() {
+            return Ok(None);
+        }
+        if &input[data_end..data_end + CLRF.len()] != CLRF {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                "Protocol error: bulk string longer than its declared length",
+            ));
+        }
+        Ok(Some((
+            input[start..data_end].to_vec(),
+            data_end + CLRF.len(),
+        )))
+    }
+
+    /// Reads `len` consecutive frames starting at `start`, as used by every aggregate type.
+    /// `depth` is how many aggregates enclose the one they belong to.
+    fn parse_elements(
+        &self,
+        input: &[u8],
+        start: usize,
+        len: i64,
+        depth: usize,
+    ) -> Result<Option<(Vec<RESPDatatypes>, usize)>> {
+        if len < 0 {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                INVALID_MULTIBULK_LENGTH,
+            ));
+        }
+        if depth >= MAX_NESTING_DEPTH {
+            return Err(Error::new(io::ErrorKind::InvalidInput, TOO_DEEPLY_NESTED));
+        }
+        // every frame takes at least its CLRF, there is no point walking the elements before
+        // that many bytes arrived
+        if ((input.len().saturating_sub(start)) as u64) < len as u64 * CLRF.len() as u64 {
+            return Ok(None);
+        }
+
+        let mut res: Vec<RESPDatatypes> = Vec::with_capacity(len.min(1024) as usize);
+        let mut cursor = start;
+        for _ in 0..len {
+            match self.parse_at(input, cursor, depth + 1)? {
+                Some((data, end)) => {
+                    res.push(data);
+                    cursor = end;
                 }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some((res, cursor)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::Decoder;
+
+    use super::*;
+    use crate::resp::codec::RespCodec;
+
+    fn command(args: &[&str]) -> Vec<u8> {
+        RESPDatatypes::Array(
+            args.iter()
+                .map(|arg| RESPDatatypes::BulkString(arg.to_string()))
+                .collect(),
+        )
+        .encode()
+    }
 
-                Ok(RESPDatatypes::Array(res))
+    fn protocol_error(input: &[u8]) -> String {
+        match Deseralize.parse(input) {
+            Err(err) => err.to_string(),
+            Ok(frame) => panic!("expected a protocol error, got {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn rejects_aggregate_lengths_over_the_limit() {
+        for header in ["*", "~", ">"] {
+            let input = format!("{}9223372036854775807\r\n", header);
+            assert_eq!(protocol_error(input.as_bytes()), INVALID_MULTIBULK_LENGTH);
+            let input = format!("{}{}\r\n", header, MAX_MULTIBULK_LENGTH + 1);
+            assert_eq!(protocol_error(input.as_bytes()), INVALID_MULTIBULK_LENGTH);
+        }
+        assert_eq!(protocol_error(b"*-2\r\n"), INVALID_MULTIBULK_LENGTH);
+        assert_eq!(protocol_error(b"*x\r\n"), INVALID_MULTIBULK_LENGTH);
+
+        let mut progress = None;
+        let err = Deseralize
+            .parse_resumable(b"*9223372036854775807\r\n", &mut progress)
+            .unwrap_err();
+        assert_eq!(err.to_string(), INVALID_MULTIBULK_LENGTH);
+        assert!(progress.is_none());
+    }
+
+    #[test]
+    fn rejects_map_lengths_that_overflow() {
+        for header in ["%", "|"] {
+            let input = format!("{}9223372036854775807\r\n", header);
+            assert_eq!(protocol_error(input.as_bytes()), INVALID_MULTIBULK_LENGTH);
+            let input = format!("{}{}\r\n", header, MAX_MULTIBULK_LENGTH / 2 + 1);
+            assert_eq!(protocol_error(input.as_bytes()), INVALID_MULTIBULK_LENGTH);
+            let input = format!("{}-1\r\n", header);
+            assert_eq!(protocol_error(input.as_bytes()), INVALID_MULTIBULK_LENGTH);
+        }
+
+        let (map, consumed) = Deseralize.parse(b"%1\r\n+k\r\n:1\r\n").unwrap().unwrap();
+        assert_eq!(consumed, 12);
+        assert!(matches!(map, RESPDatatypes::Map(pairs) if pairs.len() == 1));
+    }
+
+    #[test]
+    fn waits_for_aggregates_that_cannot_fit_yet() {
+        assert!(Deseralize.parse(b"*3\r\n$1\r\na\r\n").unwrap().is_none());
+        assert!(Deseralize
+            .parse(format!("*{}\r\n", MAX_MULTIBULK_LENGTH).as_bytes())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_rdb_lengths_that_overflow() {
+        let input = format!("${}\r\nREDIS", usize::MAX);
+        assert!(Deseralize.parse_rdb(input.as_bytes()).is_err());
+        assert!(Deseralize.parse_rdb(b"$6\r\nREDIS").unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_lines_do_not_panic() {
+        let (_, consumed) = Deseralize.parse(b"\r\n").unwrap().unwrap();
+        assert_eq!(consumed, 2);
+        let (_, consumed) = Deseralize.parse(b"*1\r\n\r\n").unwrap().unwrap();
+        assert_eq!(consumed, 6);
+    }
+
+    #[test]
+    fn rejects_aggregates_nested_too_deeply() {
+        // used to overflow the stack of the connection task
+        let input = b"*1\r\n".repeat(200_000);
+        assert_eq!(protocol_error(&input), TOO_DEEPLY_NESTED);
+        let mut progress = None;
+        let err = Deseralize
+            .parse_resumable(&input, &mut progress)
+            .unwrap_err();
+        assert_eq!(err.to_string(), TOO_DEEPLY_NESTED);
+
+        let nested = |depth| {
+            let mut input = b"*1\r\n".repeat(depth - 1);
+            input.extend(b"*0\r\n");
+            input
+        };
+        assert!(Deseralize
+            .parse(&nested(MAX_NESTING_DEPTH))
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            protocol_error(&nested(MAX_NESTING_DEPTH + 1)),
+            TOO_DEEPLY_NESTED
+        );
+    }
+
+    #[test]
+    fn rejects_aggregates_inside_requests() {
+        let request = |input: &[u8]| {
+            let mut progress = None;
+            let res = Deseralize.parse_request(input, &mut progress);
+            assert!(progress.is_none() || res.as_ref().is_ok_and(|res| res.is_none()));
+            res
+        };
+        let err = request(&b"*1\r\n".repeat(200_000)).unwrap_err();
+        assert_eq!(err.to_string(), "Protocol error: expected '$', got '*'");
+        let err = request(b"*2\r\n$3\r\nGET\r\n%1\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "Protocol error: expected '$', got '%'");
+        let err = request(b"~1\r\n$4\r\nPING\r\n").unwrap_err();
+        assert_eq!(err.to_string(), "Protocol error: expected '*', got '~'");
+
+        let (cmd, consumed) = request(&command(&["GET", "key"])).unwrap().unwrap();
+        assert_eq!(consumed, command(&["GET", "key"]).len());
+        assert_eq!(cmd.encode(), command(&["GET", "key"]));
+        assert!(request(b"PING\r\n").unwrap().is_some());
+        assert!(request(b"*2\r\n$3\r\nGET\r\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn resumes_frames_split_across_reads() {
+        let mut pipeline = command(&["SET", "key", "a value\r\nwith a CLRF"]);
+        pipeline.extend(command(&["GET", "key"]));
+        pipeline.extend(b"*-1\r\n");
+        pipeline.extend(b"+PING\r\n");
+
+        // every split point must decode to the same frames
+        for split in 0..=pipeline.len() {
+            let mut codec = RespCodec::default();
+            let mut buf = BytesMut::from(&pipeline[..split]);
+            let mut frames = Vec::new();
+            while let Some(frame) = codec.decode(&mut buf).unwrap() {
+                frames.push(frame.encode());
             }
-            _ => Ok(RESPDatatypes::SimpleString(bytes_to_string(input)?)),
+            buf.extend_from_slice(&pipeline[split..]);
+            while let Some(frame) = codec.decode(&mut buf).unwrap() {
+                frames.push(frame.encode());
+            }
+            assert!(buf.is_empty());
+            assert_eq!(frames.concat(), pipeline, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn resumes_byte_by_byte() {
+        let args: Vec<String> = (0..100).map(|i| format!("arg{}", i)).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let frame = command(&args);
+
+        let mut progress = None;
+        for end in 1..frame.len() {
+            assert!(Deseralize
+                .parse_resumable(&frame[..end], &mut progress)
+                .unwrap()
+                .is_none());
         }
+        let (decoded, consumed) = Deseralize
+            .parse_resumable(&frame, &mut progress)
+            .unwrap()
+            .unwrap();
+        assert_eq!(consumed, frame.len());
+        assert_eq!(decoded.encode(), frame);
+        assert!(progress.is_none());
     }
 }
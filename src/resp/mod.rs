@@ -1,3 +1,4 @@
+pub mod codec;
 pub mod core;
 pub mod deserialize;
 pub mod serialize;
@@ -1,12 +1,15 @@
 use std::error::Error;
 
 use super::core::{
-    RESPDatatypes, ARRAY_PREFIX, BOOLEAN_PREFIX, BULK_STRING_PREFIX, CLRF, DOUBLE_PREFIX,
-    INTEGER_PREFIX, NULL_ARRAY_PREFIX, NULL_PREFIX, NULL_STRING_PREFIX, SIMPLE_ERROR_PREFIX,
-    SIMPLE_STRING_PREFIX,
+    Protocol, RESPDatatypes, ARRAY_PREFIX, ATTRIBUTE_PREFIX, BIG_NUMBER_PREFIX, BOOLEAN_PREFIX,
+    BULK_ERROR_PREFIX, BULK_STRING_PREFIX, CLRF, DOUBLE_PREFIX, INTEGER_PREFIX, MAP_PREFIX,
+    NULL_ARRAY_PREFIX, NULL_PREFIX, NULL_STRING_PREFIX, PUSH_PREFIX, SET_PREFIX,
+    SIMPLE_ERROR_PREFIX, SIMPLE_STRING_PREFIX, VERBATIM_STRING_PREFIX,
 };
 
-pub struct SerializeRESP;
+pub struct SerializeRESP {
+    pub protocol: Protocol,
+}
 
 impl SerializeRESP {
     pub fn encode(&self, value: &RESPDatatypes, buf: &mut Vec<u8>) {
@@ -17,39 +20,75 @@ impl SerializeRESP {
             RESPDatatypes::Integer(data) => self.encode_integer(buf, data),
             RESPDatatypes::Double(data) => self.encode_double(buf, data),
             RESPDatatypes::SimpleString(data) => self.encode_simple_string(buf, data),
-            RESPDatatypes::SimpleError(data) => self.encode_error_string(buf, data),
+            RESPDatatypes::SimpleError(data) => self.encode_error_string(buf, data.as_ref()),
             RESPDatatypes::BulkString(data) => self.encode_bulk_string(buf, data),
             RESPDatatypes::BufBulk(data) => self.encode_buf_string(buf, data, true),
             RESPDatatypes::Boolean(data) => self.encode_boolean(buf, data),
+            RESPDatatypes::BigNumber(data) => self.encode_big_number(buf, data),
+            RESPDatatypes::BulkError(data) => self.encode_bulk_error(buf, data),
+            RESPDatatypes::VerbatimString(format, data) => {
+                self.encode_verbatim_string(buf, format, data)
+            }
             RESPDatatypes::Array(data) => self.encode_array(buf, data),
+            RESPDatatypes::Map(data) => self.encode_map(buf, MAP_PREFIX, data),
+            RESPDatatypes::Attribute(data) => self.encode_attribute(buf, data),
+            RESPDatatypes::Set(data) => self.encode_aggregate(buf, SET_PREFIX, data),
+            RESPDatatypes::Push(data) => self.encode_aggregate(buf, PUSH_PREFIX, data),
             RESPDatatypes::RDBFile(data) => self.encode_buf_string(buf, data, false),
         };
     }
 
+    fn is_resp3(&self) -> bool {
+        self.protocol == Protocol::Resp3
+    }
+
+    fn encode_length(&self, buf: &mut Vec<u8>, prefix: &[u8], len: usize) {
+        buf.extend_from_slice(prefix);
+        buf.extend_from_slice(&format!("{}", len).into_bytes());
+        buf.extend_from_slice(CLRF);
+    }
+
     fn encode_null(&self, buf: &mut Vec<u8>) {
+        if !self.is_resp3() {
+            return self.encode_null_string(buf);
+        }
         buf.extend_from_slice(NULL_PREFIX);
         buf.extend_from_slice(CLRF);
     }
 
     fn encode_null_string(&self, buf: &mut Vec<u8>) {
+        if self.is_resp3() {
+            return self.encode_null(buf);
+        }
         buf.extend_from_slice(NULL_STRING_PREFIX);
         buf.extend_from_slice(CLRF);
     }
 
     fn encode_null_array(&self, buf: &mut Vec<u8>) {
+        if self.is_resp3() {
+            return self.encode_null(buf);
+        }
         buf.extend_from_slice(NULL_ARRAY_PREFIX);
         buf.extend_from_slice(CLRF);
     }
 
-    fn encode_integer(&self, buf: &mut Vec<u8>, data: &i32) {
+    fn encode_integer(&self, buf: &mut Vec<u8>, data: &i64) {
         buf.extend_from_slice(INTEGER_PREFIX);
         buf.extend_from_slice(&data.to_string().into_bytes());
         buf.extend_from_slice(CLRF);
     }
 
     fn encode_double(&self, buf: &mut Vec<u8>, data: &f64) {
+        let repr = if data.is_nan() {
+            "nan".to_string()
+        } else {
+            data.to_string()
+        };
+        if !self.is_resp3() {
+            return self.encode_bulk_string(buf, &repr);
+        }
         buf.extend_from_slice(DOUBLE_PREFIX);
-        buf.extend_from_slice(data.to_string().as_bytes());
+        buf.extend_from_slice(repr.as_bytes());
         buf.extend_from_slice(CLRF);
     }
 
@@ -59,7 +98,7 @@ impl SerializeRESP {
         buf.extend_from_slice(CLRF);
     }
 
-    fn encode_error_string(&self, buf: &mut Vec<u8>, data: &Box<dyn Error>) {
+    fn encode_error_string(&self, buf: &mut Vec<u8>, data: &dyn Error) {
         buf.extend_from_slice(SIMPLE_ERROR_PREFIX);
         buf.extend_from_slice(&format!("{}", data).into_bytes());
         buf.extend_from_slice(CLRF);
@@ -73,7 +112,7 @@ impl SerializeRESP {
         buf.extend_from_slice(CLRF);
     }
 
-    fn encode_buf_string(&self, buf: &mut Vec<u8>, data: &Vec<u8>, add_clrf_to_end: bool) {
+    fn encode_buf_string(&self, buf: &mut Vec<u8>, data: &[u8], add_clrf_to_end: bool) {
         buf.extend_from_slice(BULK_STRING_PREFIX);
         buf.extend_from_slice(&format!("{}", data.len()).into_bytes());
         buf.extend_from_slice(CLRF);
@@ -84,12 +123,48 @@ impl SerializeRESP {
     }
 
     fn encode_boolean(&self, buf: &mut Vec<u8>, data: &bool) {
+        if !self.is_resp3() {
+            return self.encode_integer(buf, &(*data as i64));
+        }
         buf.extend_from_slice(BOOLEAN_PREFIX);
-        buf.extend_from_slice(&format!("{}", data).into_bytes());
+        buf.extend_from_slice(if *data { b"t" } else { b"f" });
         buf.extend_from_slice(CLRF);
     }
 
-    fn encode_array(&self, buf: &mut Vec<u8>, data: &Vec<RESPDatatypes>) {
+    fn encode_big_number(&self, buf: &mut Vec<u8>, data: &String) {
+        if !self.is_resp3() {
+            return self.encode_bulk_string(buf, data);
+        }
+        buf.extend_from_slice(BIG_NUMBER_PREFIX);
+        buf.extend_from_slice(data.as_bytes());
+        buf.extend_from_slice(CLRF);
+    }
+
+    fn encode_bulk_error(&self, buf: &mut Vec<u8>, data: &str) {
+        if !self.is_resp3() {
+            // simple errors can't carry line breaks
+            buf.extend_from_slice(SIMPLE_ERROR_PREFIX);
+            buf.extend_from_slice(data.replace(['\r', '\n'], " ").as_bytes());
+            buf.extend_from_slice(CLRF);
+            return;
+        }
+        self.encode_length(buf, BULK_ERROR_PREFIX, data.len());
+        buf.extend_from_slice(data.as_bytes());
+        buf.extend_from_slice(CLRF);
+    }
+
+    fn encode_verbatim_string(&self, buf: &mut Vec<u8>, format: &str, data: &[u8]) {
+        if !self.is_resp3() {
+            return self.encode_buf_string(buf, data, true);
+        }
+        self.encode_length(buf, VERBATIM_STRING_PREFIX, format.len() + 1 + data.len());
+        buf.extend_from_slice(format.as_bytes());
+        buf.push(b':');
+        buf.extend_from_slice(data);
+        buf.extend_from_slice(CLRF);
+    }
+
+    fn encode_array(&self, buf: &mut Vec<u8>, data: &[RESPDatatypes]) {
         buf.extend_from_slice(ARRAY_PREFIX);
         buf.extend_from_slice(&format!("{}", data.len()).into_bytes());
         buf.extend_from_slice(CLRF);
@@ -97,4 +172,168 @@ impl SerializeRESP {
             self.encode(item, buf);
         }
     }
+
+    /// Sets and pushes are plain arrays to a RESP2 peer.
+    fn encode_aggregate(&self, buf: &mut Vec<u8>, prefix: &[u8], data: &[RESPDatatypes]) {
+        if !self.is_resp3() {
+            return self.encode_array(buf, data);
+        }
+        self.encode_length(buf, prefix, data.len());
+        for item in data {
+            self.encode(item, buf);
+        }
+    }
+
+    /// Maps are flattened into `key value key value ...` arrays for a RESP2 peer.
+    fn encode_map(
+        &self,
+        buf: &mut Vec<u8>,
+        prefix: &[u8],
+        data: &[(RESPDatatypes, RESPDatatypes)],
+    ) {
+        if self.is_resp3() {
+            self.encode_length(buf, prefix, data.len());
+        } else {
+            self.encode_length(buf, ARRAY_PREFIX, data.len() * 2);
+        }
+        for (key, value) in data {
+            self.encode(key, buf);
+            self.encode(value, buf);
+        }
+    }
+
+    /// Attributes are auxiliary metadata that RESP2 has no way to express, so they are
+    /// dropped for RESP2 peers.
+    fn encode_attribute(&self, buf: &mut Vec<u8>, data: &[(RESPDatatypes, RESPDatatypes)]) {
+        if self.is_resp3() {
+            self.encode_map(buf, ATTRIBUTE_PREFIX, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::resp::{
+        core::{Protocol, RESPDatatypes},
+        deserialize::Deseralize,
+    };
+
+    fn every_type() -> RESPDatatypes {
+        RESPDatatypes::Array(vec![
+            RESPDatatypes::Null,
+            RESPDatatypes::NullString,
+            RESPDatatypes::NullArray,
+            RESPDatatypes::Integer(-42),
+            RESPDatatypes::Double(1.5),
+            RESPDatatypes::Double(f64::NEG_INFINITY),
+            RESPDatatypes::SimpleString("OK".to_string()),
+            RESPDatatypes::SimpleError(Box::new(io::Error::other("ERR boom"))),
+            RESPDatatypes::BulkString("bulk".to_string()),
+            RESPDatatypes::BufBulk(b"with\r\nCLRF".to_vec()),
+            RESPDatatypes::Boolean(true),
+            RESPDatatypes::BigNumber("-3492890328409238509324850943850943825024385".to_string()),
+            RESPDatatypes::BulkError("SYNTAX invalid\r\nsyntax".to_string()),
+            RESPDatatypes::VerbatimString("txt".to_string(), b"Some string".to_vec()),
+            RESPDatatypes::Map(vec![(
+                RESPDatatypes::SimpleString("key".to_string()),
+                RESPDatatypes::Set(vec![RESPDatatypes::Integer(1)]),
+            )]),
+            RESPDatatypes::Attribute(vec![(
+                RESPDatatypes::SimpleString("ttl".to_string()),
+                RESPDatatypes::Integer(3600),
+            )]),
+            RESPDatatypes::Push(vec![RESPDatatypes::BulkString("message".to_string())]),
+            RESPDatatypes::Array(vec![]),
+        ])
+    }
+
+    fn round_trip(encoded: &[u8]) -> Vec<u8> {
+        let (decoded, consumed) = Deseralize.parse(encoded).unwrap().unwrap();
+        assert_eq!(consumed, encoded.len());
+        decoded.encode_for(Protocol::Resp3)
+    }
+
+    #[test]
+    fn resp3_round_trips() {
+        let encoded = every_type().encode_for(Protocol::Resp3);
+        assert_eq!(round_trip(&encoded), encoded);
+    }
+
+    #[test]
+    fn resp2_round_trips() {
+        // attributes are dropped for RESP2, so an array holding one has nothing to match
+        let RESPDatatypes::Array(mut values) = every_type() else {
+            unreachable!();
+        };
+        values.retain(|value| !matches!(value, RESPDatatypes::Attribute(_)));
+        let encoded = RESPDatatypes::Array(values).encode();
+        let (decoded, _) = Deseralize.parse(&encoded).unwrap().unwrap();
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    #[test]
+    fn downgrades_resp3_types_for_resp2() {
+        let cases = [
+            (RESPDatatypes::Null, "$-1\r\n"),
+            (RESPDatatypes::Double(2.5), "$3\r\n2.5\r\n"),
+            (RESPDatatypes::Boolean(false), ":0\r\n"),
+            (RESPDatatypes::BigNumber("12".to_string()), "$2\r\n12\r\n"),
+            (
+                RESPDatatypes::BulkError("ERR a\r\nb".to_string()),
+                "-ERR a  b\r\n",
+            ),
+            (
+                RESPDatatypes::VerbatimString("txt".to_string(), b"hi".to_vec()),
+                "$2\r\nhi\r\n",
+            ),
+            (
+                RESPDatatypes::Map(vec![(RESPDatatypes::Integer(1), RESPDatatypes::Integer(2))]),
+                "*2\r\n:1\r\n:2\r\n",
+            ),
+            (
+                RESPDatatypes::Set(vec![RESPDatatypes::Integer(1)]),
+                "*1\r\n:1\r\n",
+            ),
+            (
+                RESPDatatypes::Attribute(vec![(RESPDatatypes::Null, RESPDatatypes::Null)]),
+                "",
+            ),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(
+                String::from_utf8(value.encode()).unwrap(),
+                expected,
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn encodes_resp3_types() {
+        let cases = [
+            (RESPDatatypes::Null, "_\r\n"),
+            (RESPDatatypes::Double(f64::NAN), ",nan\r\n"),
+            (RESPDatatypes::Boolean(true), "#t\r\n"),
+            (
+                RESPDatatypes::BulkError("ERR x".to_string()),
+                "!5\r\nERR x\r\n",
+            ),
+            (
+                RESPDatatypes::VerbatimString("mkd".to_string(), b"# x".to_vec()),
+                "=7\r\nmkd:# x\r\n",
+            ),
+            (
+                RESPDatatypes::Map(vec![(RESPDatatypes::Integer(1), RESPDatatypes::Integer(2))]),
+                "%1\r\n:1\r\n:2\r\n",
+            ),
+        ];
+        for (value, expected) in cases {
+            let encoded = value.encode_for(Protocol::Resp3);
+            assert_eq!(String::from_utf8(encoded.clone()).unwrap(), expected);
+            assert_eq!(round_trip(&encoded), encoded);
+        }
+    }
 }
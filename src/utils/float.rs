@@ -0,0 +1,143 @@
+/// Digits after the decimal point redis keeps when it formats a float with `%.17Lf`.
+const PLACES: u32 = 17;
+
+/// Digits after the decimal point sums are exact to, a couple more than are shown so only
+/// the result gets rounded to `PLACES`.
+const EXACT_PLACES: u32 = 19;
+
+/// Parses a plain decimal like `-12.5` or `1e3` as a count of `10^-EXACT_PLACES`, rounding
+/// digits past that. Anything else, such as `inf`, or too large is left to `f64`.
+fn parse_scaled(input: &[u8]) -> Option<i128> {
+    let input = std::str::from_utf8(input).ok()?.trim();
+    let (mantissa, exp) = match input.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => (mantissa, exp.parse::<i64>().ok()?),
+        None => (input, 0),
+    };
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => (true, mantissa),
+        None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits: Vec<u8> = int.bytes().chain(frac.bytes()).collect();
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let leading_zeros = digits.iter().take_while(|d| **d == b'0').count();
+    let digits = &digits[leading_zeros..];
+    // how many of the significant digits are worth at least 10^-EXACT_PLACES
+    let kept = (int.len() as i64 - leading_zeros as i64)
+        .checked_add(exp)?
+        .checked_add(EXACT_PLACES as i64)?;
+    if digits.is_empty() || kept < 0 {
+        return Some(0);
+    }
+    // past 39 digits the count doesn't fit
+    if kept > 39 {
+        return None;
+    }
+    let mut scaled: i128 = 0;
+    for idx in 0..kept as usize {
+        let digit = digits.get(idx).map_or(0, |d| d - b'0');
+        scaled = scaled.checked_mul(10)?.checked_add(digit as i128)?;
+    }
+    if digits.get(kept as usize).is_some_and(|d| *d >= b'5') {
+        scaled = scaled.checked_add(1)?;
+    }
+    Some(if negative { -scaled } else { scaled })
+}
+
+/// The result of `INCRBYFLOAT` and `HINCRBYFLOAT` as redis formats it.
+///
+/// Redis adds in `long double` and prints `%.17Lf`, 17 digits after the point without the
+/// trailing zeros, so `2001.1 + 0.1` reads `2001.2` even though the closest `f64` is
+/// `2001.1999999999998`. Adding the decimal strings exactly gets the same digits. `sum` is
+/// the `f64` result, used when either operand isn't a plain decimal or is too large.
+pub fn format_float_sum(current: &[u8], increment: &[u8], sum: f64) -> String {
+    let exact = parse_scaled(current)
+        .zip(parse_scaled(increment))
+        .and_then(|(current, increment)| current.checked_add(increment));
+    let Some(exact) = exact else {
+        return format_float(sum);
+    };
+
+    let unit = 10u128.pow(EXACT_PLACES - PLACES);
+    let rounded = (exact.unsigned_abs() + unit / 2) / unit;
+    let places = 10u128.pow(PLACES);
+    let sign = if exact < 0 { "-" } else { "" };
+    let formatted = format!(
+        "{}{}.{:0width$}",
+        sign,
+        rounded / places,
+        rounded % places,
+        width = PLACES as usize
+    );
+    trim_zeros(&formatted)
+}
+
+/// Formats `val` like `%.17Lf`, dropping the trailing zeros.
+pub fn format_float(val: f64) -> String {
+    trim_zeros(&format!("{:.*}", PLACES as usize, val))
+}
+
+/// Drops the zeros after the decimal point, and the sign of a zero, like redis does.
+fn trim_zeros(formatted: &str) -> String {
+    match formatted.trim_end_matches('0').trim_end_matches('.') {
+        "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum(current: &str, increment: &str) -> String {
+        let val = current.trim().parse::<f64>().unwrap() + increment.parse::<f64>().unwrap();
+        format_float_sum(current.as_bytes(), increment.as_bytes(), val)
+    }
+
+    #[test]
+    fn adds_like_long_doubles() {
+        assert_eq!(sum("2001.1", "0.1"), "2001.2");
+        assert_eq!(sum("10.5", "0.1"), "10.6");
+        assert_eq!(sum("0.1", "0.2"), "0.3");
+        assert_eq!(sum("5.0e3", "2.0e2"), "5200");
+        assert_eq!(sum("3.0", "1"), "4");
+        assert_eq!(sum("0.1", "-0.1"), "0");
+        assert_eq!(sum("-5", "2.5"), "-2.5");
+        assert_eq!(sum("2.5", "-5"), "-2.5");
+        assert_eq!(sum(" 1.5 ", "+.5"), "2");
+    }
+
+    #[test]
+    fn keeps_seventeen_decimal_places() {
+        assert_eq!(sum("0.33333333333333333333", "0"), "0.33333333333333333");
+        assert_eq!(sum("0.66666666666666666666", "0"), "0.66666666666666667");
+        assert_eq!(sum("0.999999999999999999", "0"), "1");
+        assert_eq!(sum("0.000000000000000004", "0"), "0");
+        assert_eq!(sum("0.000000000000000005", "0"), "0.00000000000000001");
+        assert_eq!(sum("-0.000000000000000001", "0"), "0");
+        assert_eq!(sum("12345678901234567", "0"), "12345678901234567");
+        assert_eq!(sum("1234567890123456789", "0"), "1234567890123456789");
+    }
+
+    #[test]
+    fn never_uses_scientific_notation() {
+        assert_eq!(sum("1e20", "0"), "100000000000000000000");
+        assert_eq!(sum("0.0001", "0"), "0.0001");
+        assert_eq!(sum("0.00001", "0"), "0.00001");
+        assert_eq!(sum("-1.25e-7", "0"), "-0.000000125");
+        assert_eq!(sum("1e20", "1e20"), "200000000000000000000");
+    }
+
+    #[test]
+    fn falls_back_to_the_f64_sum() {
+        assert_eq!(format_float_sum(b"1e5000", b"0", 1.5), "1.5");
+        assert_eq!(format_float(2001.1999999999998), "2001.1999999999998181");
+        assert_eq!(format_float(0.5), "0.5");
+        assert_eq!(format_float(-3.0), "-3");
+        assert_eq!(format_float(-0.0), "0");
+        assert_eq!(format_float(1e20), "100000000000000000000");
+    }
+}
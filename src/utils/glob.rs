@@ -0,0 +1,101 @@
+/// Matches `string` against a redis style glob `pattern`.
+///
+/// `*` matches any run of bytes, `?` any single byte, `[abc]`, `[a-z]` and `[^abc]` match a
+/// byte out of (or not out of) a set, and `\` escapes the byte after it.
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // where to resume from when what followed the last `*` didn't match
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                while pattern.get(p) == Some(&b'*') {
+                    p += 1;
+                }
+                if p == pattern.len() {
+                    return true;
+                }
+                backtrack = Some((p, s));
+                continue;
+            }
+            Some(b'?') => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            Some(b'[') => {
+                if let Some((matched, next)) = match_class(pattern, p + 1, string[s]) {
+                    if matched {
+                        p = next;
+                        s += 1;
+                        continue;
+                    }
+                }
+            }
+            Some(b'\\') if p + 1 < pattern.len() && pattern[p + 1] == string[s] => {
+                p += 2;
+                s += 1;
+                continue;
+            }
+            // an escaped byte that doesn't match must not be taken as a literal `\\`
+            Some(b'\\') if p + 1 < pattern.len() => {}
+            Some(byte) if *byte == string[s] => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // mismatch, let the last `*` swallow one more byte
+        match backtrack {
+            Some((star_p, star_s)) => {
+                backtrack = Some((star_p, star_s + 1));
+                p = star_p;
+                s = star_s + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p.min(pattern.len())..]
+        .iter()
+        .all(|byte| *byte == b'*')
+}
+
+/// Matches `byte` against the class starting right after a `[` at `start`. Returns whether it
+/// matched along with the index past the closing `]`, or `None` for an unterminated class.
+fn match_class(pattern: &[u8], start: usize, byte: u8) -> Option<(bool, usize)> {
+    let mut p = start;
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern.get(p)? {
+            b']' => break,
+            b'\\' if p + 1 < pattern.len() => {
+                matched |= pattern[p + 1] == byte;
+                p += 2;
+            }
+            low if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() => {
+                let high = pattern[p + 2];
+                let (low, high) = if *low <= high {
+                    (*low, high)
+                } else {
+                    (high, *low)
+                };
+                matched |= (low..=high).contains(&byte);
+                p += 3;
+            }
+            other => {
+                matched |= *other == byte;
+                p += 1;
+            }
+        }
+    }
+    Some((matched != negate, p + 1))
+}
@@ -0,0 +1,3 @@
+pub mod float;
+pub mod glob;
+pub mod range;
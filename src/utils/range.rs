@@ -0,0 +1,15 @@
+/// The inclusive range `start..=end` selects in a sequence of `len` elements, redis style:
+/// negative indexes count from the end and out of range ones are clamped.
+pub fn clamp_range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let end = if end < 0 { len + end } else { end.min(len - 1) };
+    if len == 0 || end < 0 || start > end {
+        return None;
+    }
+    Some((start as usize, end as usize))
+}